@@ -1,6 +1,7 @@
-use std::{env, fs, panic, process};
+use std::{env, panic, process};
+use std::path::Path;
 use anyhow::{Context, Result};
-use crate::system::{System, SystemStartArgs};
+use crate::system::{System, SystemPalette, SystemStartArgs};
 
 mod system;
 
@@ -17,13 +18,26 @@ fn main() -> Result<()>
     else if args.len()>=2
     {
         let rom_file_path = args.last().cloned().unwrap_or_default();
-        let rom_bytes = fs::read(rom_file_path).context(codeloc!())?.into_boxed_slice();
-        let start_args = SystemStartArgs::with_rom_bytes(rom_bytes).context(codeloc!())?;
+        let mut start_args = SystemStartArgs::with_rom_file(Path::new(&rom_file_path)).context(codeloc!())?;
+        start_args.should_disable_sprite_limit = args.iter().any(|arg| arg=="--no-sprite-limit");
+        if args.iter().any(|arg| arg=="--headless")
+        {
+            start_args.should_disable_video = true;
+            start_args.should_disable_audio = true;
+        }
+        if let Some(number_of_frames) = args.iter().position(|arg| arg=="--frames").and_then(|index| args.get(index+1))
+        {
+            start_args.number_of_frames_to_run = Some(number_of_frames.parse::<usize>().context(codeloc!())?);
+        }
+        if let Some(palette) = args.iter().position(|arg| arg=="--palette").and_then(|index| args.get(index+1))
+        {
+            start_args.system_palette = SystemPalette::parse(palette);
+        }
         System::start(start_args).context(codeloc!())?.await_termination();
     }
     else
     {
-        println!("Syntax: <emulator> <rom_file.nes>");
+        println!("Syntax: <emulator> [--no-sprite-limit] [--palette default|ntsc|<file.pal>] [--headless] [--frames N] <rom_file.nes>");
     }
 
     return Ok(());
@@ -1,5 +1,7 @@
 #![allow(non_camel_case_types)]
 
+use std::fs;
+use std::path::Path;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
@@ -7,13 +9,17 @@ use std::thread::JoinHandle;
 use anyhow::{Context, Result};
 use maplit2::hashmap;
 use crate::codeloc;
-use crate::system::apu::{APU, APURunEnvironment};
-use crate::system::cpu::{CPU, CPUChannelsToOtherSystems, CPURunEnvironment};
-use crate::system::cpu::program_rom::ProgramROM;
+use crate::system::apu::APU;
+use crate::system::cpu::{CPU, CPURunEnvironment};
 use crate::system::debugger::{CPUDebugger, LoggingOptions, PPUDebugger};
-use crate::system::ppu::character_rom::CharacterROM;
-use crate::system::ppu::{PPU, PPURunEnvironment};
+pub use crate::system::headless::{FrameSink, InMemoryFrameSink, InMemorySampleSink, SampleSink};
+use crate::system::mappers::{Mapper, MapperRegistry, SharedMapper};
+use crate::system::ppu::PPU;
+pub use crate::system::ppu::system_palette::SystemPalette;
 use crate::system::rom::ROMParser;
+use crate::system::save_state::SaveStateSlots;
+use crate::system::save_file::SaveFile;
+use crate::system::scheduler::{Scheduler, SchedulerRunEnvironment};
 use crate::system::test::Test;
 
 mod cpu;
@@ -21,14 +27,19 @@ mod ppu;
 mod debugger;
 mod test;
 mod rom;
-pub mod apu_channels;
-pub mod ppu_channels;
 mod apu;
 mod input;
+mod mappers;
+mod save_file;
+mod save_state;
+mod snapshot;
+mod scheduler;
+mod video_frontend;
+mod headless;
 
 pub type byte = u8;
 pub type address = u16;
-pub type mapper = u8;
+pub type mapper = u16;
 pub type color = u32;
 
 #[inline]
@@ -39,14 +50,20 @@ pub fn address_from_high_low(high : byte, low : byte) -> address
 
 pub struct SystemStartArgs
 {
-    program_rom : ProgramROM,
-    character_rom : CharacterROM,
+    mapper : Box<dyn Mapper>,
+    save_file : Option<SaveFile>,
+    save_state_slots : Option<SaveStateSlots>,
     logging_options : LoggingOptions,
     cpu_debugger : CPUDebugger,
     ppu_debugger : PPUDebugger,
     pub should_disable_audio : bool,
     pub should_disable_video : bool,
     should_disable_interrupt_vectors : bool,
+    pub should_disable_sprite_limit : bool,
+    pub system_palette : SystemPalette,
+    pub frame_sink : Option<Box<dyn FrameSink + Send>>,
+    pub sample_sink : Option<Box<dyn SampleSink + Send>>,
+    pub number_of_frames_to_run : Option<usize>,
     pub window_title : String,
 }
 
@@ -55,20 +72,41 @@ impl SystemStartArgs
     pub fn with_rom_bytes(rom_data : Box<[byte]>) -> Result<SystemStartArgs>
     {
         let parsed_rom = ROMParser::parse(rom_data).context(codeloc!())?;
+        let mapper = MapperRegistry::build(parsed_rom).context(codeloc!())?;
 
         return Ok(SystemStartArgs
         {
-            program_rom: parsed_rom.program_rom,
-            character_rom: parsed_rom.character_rom,
+            mapper: mapper,
+            save_file: None,
+            save_state_slots: None,
             logging_options: LoggingOptions::defaults(),
             cpu_debugger: CPUDebugger::new(),
             ppu_debugger: PPUDebugger::new(),
             should_disable_audio: true,
             should_disable_video: false,
             should_disable_interrupt_vectors: false,
+            should_disable_sprite_limit: false,
+            system_palette: SystemPalette::Default,
+            frame_sink: None,
+            sample_sink: None,
+            number_of_frames_to_run: None,
             window_title: String::from("Emulator"),
         });
     }
+
+    pub fn with_rom_file(rom_file_path : &Path) -> Result<SystemStartArgs>
+    {
+        let rom_bytes = fs::read(rom_file_path).context(codeloc!())?.into_boxed_slice();
+        let rom_hash = format!("{:x}", md5::compute(&rom_bytes));
+        let mut args = SystemStartArgs::with_rom_bytes(rom_bytes).context(codeloc!())?;
+
+        let save_file = SaveFile::new(rom_file_path);
+        save_file.load(args.mapper.program_ram()).context(codeloc!())?;
+        args.save_file = Some(save_file);
+        args.save_state_slots = Some(SaveStateSlots::new(rom_file_path, rom_hash));
+
+        return Ok(args);
+    }
 }
 
 pub struct System {}
@@ -80,48 +118,42 @@ impl System
     {
         let is_shutting_down = Arc::new(AtomicBool::new(false));
 
-        let cpu_run_environment = CPURunEnvironment
-        {
-            debugger: args.cpu_debugger,
-            logging_options: args.logging_options.clone(),
-            is_shutting_down: is_shutting_down.clone(),
-            should_disable_interrupt_vectors: args.should_disable_interrupt_vectors,
-        };
-
-        let ppu_run_environment = PPURunEnvironment
+        let scheduler_run_environment = SchedulerRunEnvironment
         {
-            debugger: args.ppu_debugger,
-            logging_options: args.logging_options.clone(),
+            cpu_environment: CPURunEnvironment::new(args.cpu_debugger, args.logging_options.clone()),
             is_shutting_down: is_shutting_down.clone(),
+            should_disable_audio: args.should_disable_audio,
             should_disable_video: args.should_disable_video,
+            should_disable_interrupt_vectors: args.should_disable_interrupt_vectors,
+            frame_sink: args.frame_sink,
+            sample_sink: args.sample_sink,
+            number_of_frames_to_run: args.number_of_frames_to_run,
+            save_state_slots: args.save_state_slots,
             window_title: args.window_title,
         };
 
-        let apu_run_environment = APURunEnvironment
-        {
-            logging_options: args.logging_options.clone(),
-            is_shutting_down: is_shutting_down.clone(),
-            should_disable_audio: args.should_disable_audio,
-        };
-
-        let (cpu_to_ppu_channels, ppu_to_cpu_channels) =
-            System::create_ppu_system_channels(args.logging_options.clone());
-
-        let (cpu_to_apu_channels, apu_to_cpu_channels) =
-            System::create_apu_system_channels(args.logging_options.clone());
+        let mapper = SharedMapper::new(args.mapper);
+        let system_colors = args.system_palette.load_colors().context(codeloc!())?;
+        let ppu = PPU::new(mapper.clone(), system_colors, args.ppu_debugger, args.should_disable_sprite_limit);
+        let apu = APU::new(mapper.clone());
+        let cpu = CPU::new(mapper.clone(), ppu, apu, args.logging_options.clone());
+        let save_file_mapper = mapper.clone();
 
-        let cpu_to_other_systems_channels = CPUChannelsToOtherSystems
-            { ppu_channels: cpu_to_ppu_channels, apu_channels: cpu_to_apu_channels };
+        let save_file = args.save_file.clone();
+        let save_file_is_shutting_down = is_shutting_down.clone();
 
         let join_handle = thread::spawn(move ||
         {
-            let join_sub_handles = hashmap!
+            let mut join_sub_handles = hashmap!
             {
-                "CPU" => thread::spawn(move || CPU::new(args.program_rom, cpu_to_other_systems_channels).run(cpu_run_environment)),
-                "PPU" => thread::spawn(move || PPU::new(args.character_rom, ppu_to_cpu_channels).run(ppu_run_environment).unwrap()),
-                "APU" => thread::spawn(move || APU::new(apu_to_cpu_channels).run(apu_run_environment).unwrap()),
+                "EMU" => thread::spawn(move || Scheduler::new(cpu).run(scheduler_run_environment).unwrap()),
             };
 
+            if let Some(save_file) = save_file
+            {
+                join_sub_handles.insert("SAV", thread::spawn(move || save_file.run(save_file_mapper, save_file_is_shutting_down).unwrap()));
+            }
+
             for (thread_name, join_sub_handle) in join_sub_handles
             {
                 if args.logging_options.is_system_threads_shutdown_logging_enabled
@@ -138,7 +170,7 @@ impl System
             }
         });
 
-        return Ok(RunningSystem { join_handle, is_shutting_down });
+        return Ok(RunningSystem { join_handle, is_shutting_down, mapper, save_file: args.save_file });
     }
 }
 
@@ -146,6 +178,8 @@ pub struct RunningSystem
 {
     join_handle : JoinHandle<()>,
     is_shutting_down : Arc<AtomicBool>,
+    mapper : SharedMapper,
+    save_file : Option<SaveFile>,
 }
 
 impl RunningSystem
@@ -153,6 +187,12 @@ impl RunningSystem
     pub fn shutdown(self)
     {
         self.is_shutting_down.store(true, Ordering::Relaxed);
+
+        //the process may exit before the SAV thread wakes up, so the last changes are written right away
+        if let Some(save_file) = &self.save_file
+        {
+            if let Err(error) = save_file.flush(&self.mapper) { eprintln!("{:?}", error); }
+        }
     }
 
     pub fn await_termination(self)
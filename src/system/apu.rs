@@ -2,25 +2,22 @@ mod square_synth;
 mod triangle_synth;
 mod mixer;
 mod noise_synth;
-mod speaker;
+pub mod speaker;
 mod flags;
-mod communication;
-mod clock;
+pub mod communication;
+pub mod clock;
 
 use anyhow::{Context, Result};
-use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
 use crate::codeloc;
 use crate::system::apu::clock::APUClock;
 use crate::system::apu::flags::frame_counter_flags::APUFrameCounterFlags;
 use crate::system::apu::flags::status_flags::APUStatusFlags;
 use crate::system::apu::mixer::Mixer;
 use crate::system::apu::noise_synth::NoiseSynth;
-use crate::system::apu::speaker::Speaker;
 use crate::system::apu::square_synth::SquareSynth;
 use crate::system::apu::triangle_synth::TriangleSynth;
-use crate::system::apu_channels::APUToCPUChannels;
-use crate::system::debugger::LoggingOptions;
+use crate::system::mappers::SharedMapper;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
 
 pub struct APU
 {
@@ -30,15 +27,9 @@ pub struct APU
     pub noise_synth : NoiseSynth,
     pub status_flags : APUStatusFlags,
     pub frame_counter_flags : APUFrameCounterFlags,
-    pub cpu_channels : APUToCPUChannels,
     pub clock : APUClock,
-}
-
-pub struct APURunEnvironment
-{
-    pub logging_options : LoggingOptions,
-    pub is_shutting_down : Arc<AtomicBool>,
-    pub should_disable_audio : bool,
+    pub mapper : SharedMapper,
+    samples : Vec<f64>,
 }
 
 pub trait Synthesizer
@@ -48,7 +39,7 @@ pub trait Synthesizer
 
 impl APU
 {
-    pub fn new(channels : APUToCPUChannels) -> APU
+    pub fn new(mapper : SharedMapper) -> APU
     {
         return APU
         {
@@ -58,34 +49,65 @@ impl APU
             noise_synth: NoiseSynth::new(),
             status_flags: APUStatusFlags::new(),
             frame_counter_flags: APUFrameCounterFlags::new(),
-            cpu_channels: channels,
             clock: APUClock::new(),
+            mapper: mapper,
+            samples: Vec::new(),
         };
     }
 
-    pub fn run(self : &mut APU, env : APURunEnvironment) -> Result<()>
+    //advances the APU by one CPU cycle, that is half of an APU cycle
+    pub fn step(self : &mut APU)
     {
-        if env.should_disable_audio { return Ok(()) }
         let apu = self;
 
-        let mut speaker = Speaker::new().context(codeloc!())?;
-        speaker.play();
-
-        loop
+        let apu_clock_tick_result = apu.clock.tick(&apu.frame_counter_flags.mode);
+        if apu_clock_tick_result.should_signal_frame_irq && !apu.frame_counter_flags.irq_inhibit_flag
         {
-            if env.is_shutting_down.load(Ordering::Relaxed) { speaker.pause(); return Ok(()) }
+            apu.status_flags.frame_interrupt_flag = true;
+        }
 
-            let waveform_index = speaker.advance_to_next_waveform_index();
+        if apu_clock_tick_result.should_output_sample
+        {
+            let waveform_index = apu.clock.get_waveform_index();
             let waveform_value = Mixer::mix(&apu, waveform_index);
-            speaker.accept_waveform_value(waveform_value);
+            apu.samples.push(waveform_value);
+        }
+    }
+
+    pub fn is_irq_pending(&self) -> bool
+    {
+        return self.status_flags.frame_interrupt_flag;
+    }
 
-            apu.handle_read_commands_from_cpu();
-            apu.handle_write_commands_from_cpu();
+    //samples produced since the last call, at the sample rate of the speaker or sample sink
+    pub fn take_samples(&mut self) -> Vec<f64>
+    {
+        return std::mem::take(&mut self.samples);
+    }
+}
 
-            if apu.clock.should_notify_apu_frame_has_ended()
-            {
-                apu.cpu_channels.signal_frame_end();
-            }
-        }
+impl Snapshot for APU
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        self.square1_synth.save_snapshot(writer);
+        self.square2_synth.save_snapshot(writer);
+        self.triangle_synth.save_snapshot(writer);
+        self.noise_synth.save_snapshot(writer);
+        writer.put_byte(self.status_flags.to_byte());
+        writer.put_byte(self.frame_counter_flags.to_byte());
+        self.clock.save_snapshot(writer);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.square1_synth.load_snapshot(reader).context(codeloc!())?;
+        self.square2_synth.load_snapshot(reader).context(codeloc!())?;
+        self.triangle_synth.load_snapshot(reader).context(codeloc!())?;
+        self.noise_synth.load_snapshot(reader).context(codeloc!())?;
+        self.status_flags = APUStatusFlags::from_byte(reader.get_byte().context(codeloc!())?);
+        self.frame_counter_flags = APUFrameCounterFlags::from_byte(reader.get_byte().context(codeloc!())?);
+        self.clock.load_snapshot(reader).context(codeloc!())?;
+        return Ok(());
     }
 }
@@ -1,31 +1,100 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::apu::flags::frame_counter_flags::SequencerMode;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
+
+pub const CPU_FREQUENCY : f64 = 1789773.0;
+
+//https://www.nesdev.org/wiki/APU_Frame_Counter
+//the frame sequencer runs on APU cycles, one APU cycle lasts two CPU cycles
+const FOUR_STEP_SEQUENCE_LENGTH_IN_CPU_CYCLES : u64 = 29830;
+const FIVE_STEP_SEQUENCE_LENGTH_IN_CPU_CYCLES : u64 = 37282;
 
 pub struct APUClock
 {
     cycle_count : u64,
-    cycle_count_threshold : u64,
+    sample_rate : u32,
+    cycles_since_last_sample : f64,
+    current_sample_index : u32,
+}
+
+pub struct APUClockTickResult
+{
+    pub should_signal_frame_irq : bool,
+    pub should_output_sample : bool,
 }
 
 impl APUClock
 {
     pub fn new() -> APUClock
     {
-        //todo thresholds should not be hardcoded, thresholds should be determined based on hardware capabilities!
         return APUClock
         {
             cycle_count: 0,
-            cycle_count_threshold: 30000,
+            sample_rate: 0,
+            cycles_since_last_sample: 0.0,
+            current_sample_index: 0,
         }
     }
 
-    pub fn should_notify_apu_frame_has_ended(&mut self) -> bool
+    //samples are produced only after a speaker or a sample sink asked for them
+    pub fn set_sample_rate(&mut self, sample_rate : u32)
     {
+        self.sample_rate = sample_rate;
+    }
+
+    pub fn reset_frame_sequence(&mut self)
+    {
+        self.cycle_count = 0;
+    }
+
+    //advances by one CPU cycle
+    pub fn tick(&mut self, mode : &SequencerMode) -> APUClockTickResult
+    {
+        let sequence_length = if *mode == SequencerMode::FourStep { FOUR_STEP_SEQUENCE_LENGTH_IN_CPU_CYCLES }
+            else { FIVE_STEP_SEQUENCE_LENGTH_IN_CPU_CYCLES };
+
         self.cycle_count += 1;
-        if self.cycle_count >= self.cycle_count_threshold
+        let was_sequence_ended = self.cycle_count >= sequence_length;
+        if was_sequence_ended { self.cycle_count = 0; }
+
+        let mut should_output_sample = false;
+        if self.sample_rate > 0
         {
-            self.cycle_count = 0;
-            return true;
+            self.cycles_since_last_sample += 1.0;
+            let cycles_per_sample = CPU_FREQUENCY / (self.sample_rate as f64);
+            if self.cycles_since_last_sample >= cycles_per_sample
+            {
+                self.cycles_since_last_sample -= cycles_per_sample;
+                self.current_sample_index = (self.current_sample_index+1) % self.sample_rate;
+                should_output_sample = true;
+            }
         }
 
-        return false;
+        return APUClockTickResult
+        {
+            //only the four step sequence raises the frame interrupt
+            should_signal_frame_irq: was_sequence_ended && *mode == SequencerMode::FourStep,
+            should_output_sample: should_output_sample,
+        };
+    }
+
+    pub fn get_waveform_index(&self) -> f64
+    {
+        return (self.current_sample_index as f64) / (self.sample_rate as f64);
+    }
+}
+
+impl Snapshot for APUClock
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        writer.put_u64(self.cycle_count);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.cycle_count = reader.get_u64().context(codeloc!())?;
+        return Ok(());
     }
 }
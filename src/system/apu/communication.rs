@@ -1,66 +1,121 @@
 use crate::system::apu::APU;
 use crate::system::apu::flags::frame_counter_flags::APUFrameCounterFlags;
 use crate::system::apu::flags::status_flags::APUStatusFlags;
-use crate::system::apu_channels::CPUToAPUCommTarget;
-use crate::system::byte;
+use crate::system::{address, byte};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum CPUToAPUCommTarget
+{
+    Square1Envelope,
+    Square1Sweep,
+    Square1PeriodLow,
+    Square1PeriodHigh,
+    Square2Envelope,
+    Square2Sweep,
+    Square2PeriodLow,
+    Square2PeriodHigh,
+    TriangleCounter,
+    TrianglePeriodLow,
+    TrianglePeriodHigh,
+    NoiseVolume,
+    NoisePeriodLow,
+    NoisePeriodHigh,
+    StatusFlags,
+    FrameCounterFlags,
+    Unknown,
+}
+
+impl CPUToAPUCommTarget
+{
+    pub fn from_address(address : address) -> CPUToAPUCommTarget
+    {
+        return match address
+        {
+            0x4000 => CPUToAPUCommTarget::Square1Envelope,
+            0x4001 => CPUToAPUCommTarget::Square1Sweep,
+            0x4002 => CPUToAPUCommTarget::Square1PeriodLow,
+            0x4003 => CPUToAPUCommTarget::Square1PeriodHigh,
+            0x4004 => CPUToAPUCommTarget::Square2Envelope,
+            0x4005 => CPUToAPUCommTarget::Square2Sweep,
+            0x4006 => CPUToAPUCommTarget::Square2PeriodLow,
+            0x4007 => CPUToAPUCommTarget::Square2PeriodHigh,
+            0x4008 => CPUToAPUCommTarget::TriangleCounter,
+            0x400A => CPUToAPUCommTarget::TrianglePeriodLow,
+            0x400B => CPUToAPUCommTarget::TrianglePeriodHigh,
+            0x400C => CPUToAPUCommTarget::NoiseVolume,
+            0x400E => CPUToAPUCommTarget::NoisePeriodLow,
+            0x400F => CPUToAPUCommTarget::NoisePeriodHigh,
+            0x4015 => CPUToAPUCommTarget::StatusFlags,
+            0x4017 => CPUToAPUCommTarget::FrameCounterFlags,
+            _      => CPUToAPUCommTarget::Unknown,
+        };
+    }
+}
 
 impl APU
 {
-    pub fn handle_read_commands_from_cpu(&mut self)
+    pub fn handle_read_command_from_cpu(&mut self, target : CPUToAPUCommTarget) -> byte
     {
         let apu = self;
-        if let Ok(target) = apu.cpu_channels.get_read_command_from_cpu()
+        return match target
         {
-            apu.cpu_channels.respond_to_read_command_from_cpu(target, match target
+            CPUToAPUCommTarget::StatusFlags =>
             {
-                CPUToAPUCommTarget::StatusFlags => apu.status_flags.to_byte_for_cpu_reading(&apu),
-                CPUToAPUCommTarget::FrameCounterFlags => apu.frame_counter_flags.to_byte(),
-                _ => 0 as byte,
-            });
-        }
+                //reading the status acknowledges the frame interrupt
+                let value = apu.status_flags.to_byte_for_cpu_reading(&apu);
+                apu.status_flags.frame_interrupt_flag = false;
+                value
+            }
+            CPUToAPUCommTarget::FrameCounterFlags => apu.frame_counter_flags.to_byte(),
+            _ => 0 as byte,
+        };
     }
 
-    pub fn handle_write_commands_from_cpu(&mut self)
+    pub fn handle_write_command_from_cpu(&mut self, target : CPUToAPUCommTarget, value : byte)
     {
         let apu = self;
-        match apu.cpu_channels.get_write_command_from_cpu()
+        match target
         {
-            Ok((CPUToAPUCommTarget::Square1Envelope, value)) =>
+            CPUToAPUCommTarget::Square1Envelope =>
                 { apu.square1_synth.set_envelope(value); }
-            Ok((CPUToAPUCommTarget::Square1Sweep, value)) =>
+            CPUToAPUCommTarget::Square1Sweep =>
                 { apu.square1_synth.set_sweep(value); }
-            Ok((CPUToAPUCommTarget::Square1PeriodLow, value)) =>
+            CPUToAPUCommTarget::Square1PeriodLow =>
                 { apu.square1_synth.set_period_low(value); }
-            Ok((CPUToAPUCommTarget::Square1PeriodHigh, value)) =>
+            CPUToAPUCommTarget::Square1PeriodHigh =>
                 { apu.square1_synth.set_period_high(value); }
 
-            Ok((CPUToAPUCommTarget::Square2Envelope, value)) =>
+            CPUToAPUCommTarget::Square2Envelope =>
                 { apu.square2_synth.set_envelope(value); }
-            Ok((CPUToAPUCommTarget::Square2Sweep, value)) =>
+            CPUToAPUCommTarget::Square2Sweep =>
                 { apu.square2_synth.set_sweep(value); }
-            Ok((CPUToAPUCommTarget::Square2PeriodLow, value)) =>
+            CPUToAPUCommTarget::Square2PeriodLow =>
                 { apu.square2_synth.set_period_low(value); }
-            Ok((CPUToAPUCommTarget::Square2PeriodHigh, value)) =>
+            CPUToAPUCommTarget::Square2PeriodHigh =>
                 { apu.square2_synth.set_period_high(value); }
 
-            Ok((CPUToAPUCommTarget::TriangleCounter, value)) =>
+            CPUToAPUCommTarget::TriangleCounter =>
                 { apu.triangle_synth.set_counter(value); }
-            Ok((CPUToAPUCommTarget::TrianglePeriodLow, value)) =>
+            CPUToAPUCommTarget::TrianglePeriodLow =>
                 { apu.triangle_synth.set_period_low(value); }
-            Ok((CPUToAPUCommTarget::TrianglePeriodHigh, value)) =>
+            CPUToAPUCommTarget::TrianglePeriodHigh =>
                 { apu.triangle_synth.set_period_high(value); }
 
-            Ok((CPUToAPUCommTarget::NoiseVolume, value)) =>
+            CPUToAPUCommTarget::NoiseVolume =>
                 { apu.noise_synth.set_volume(value); }
-            Ok((CPUToAPUCommTarget::NoisePeriodLow, value)) =>
+            CPUToAPUCommTarget::NoisePeriodLow =>
                 { apu.noise_synth.set_period_low(value); }
-            Ok((CPUToAPUCommTarget::NoisePeriodHigh, value)) =>
+            CPUToAPUCommTarget::NoisePeriodHigh =>
                 { apu.noise_synth.set_period_high(value); }
 
-            Ok((CPUToAPUCommTarget::StatusFlags, value)) =>
+            CPUToAPUCommTarget::StatusFlags =>
                 { apu.status_flags = APUStatusFlags::from_byte_for_cpu_writing(&apu, value); }
-            Ok((CPUToAPUCommTarget::FrameCounterFlags, value)) =>
-                { apu.frame_counter_flags = APUFrameCounterFlags::from_byte(value); }
+            CPUToAPUCommTarget::FrameCounterFlags =>
+            {
+                apu.frame_counter_flags = APUFrameCounterFlags::from_byte(value);
+                apu.clock.reset_frame_sequence();
+                if apu.frame_counter_flags.irq_inhibit_flag { apu.status_flags.frame_interrupt_flag = false; }
+            }
             _ => {}
         }
     }
@@ -30,6 +30,10 @@ impl Mixer
         //     { apu.noise_synth.synthesize(waveform_index) }
         // else { 0.0 };
 
-        return (0.2632 * (square1 + square2)) + (0.29785 * triangle + 0.1729 * noise);
+        let expansion = apu.mapper.lock().expansion_audio_channels().iter()
+            .map(|synth| synth.synthesize(waveform_index))
+            .sum::<f64>();
+
+        return (0.2632 * (square1 + square2)) + (0.29785 * triangle + 0.1729 * noise) + expansion;
     }
 }
@@ -1,6 +1,9 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
 use rand::random;
 use crate::system::apu::Synthesizer;
 use crate::system::byte;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
 
 pub struct NoiseSynth
 {
@@ -58,3 +61,27 @@ impl Synthesizer for NoiseSynth
         return random::<f64>()*2.0-1.0;
     }
 }
+
+impl Snapshot for NoiseSynth
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        writer.put_byte(self.period);
+        writer.put_bool(self.should_loop);
+        writer.put_byte(self.volume);
+        writer.put_bool(self.constant_volume_flag);
+        writer.put_bool(self.length_counter_halt_flag);
+        writer.put_byte(self.length_counter_load);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.period = reader.get_byte().context(codeloc!())?;
+        self.should_loop = reader.get_bool().context(codeloc!())?;
+        self.volume = reader.get_byte().context(codeloc!())?;
+        self.constant_volume_flag = reader.get_bool().context(codeloc!())?;
+        self.length_counter_halt_flag = reader.get_bool().context(codeloc!())?;
+        self.length_counter_load = reader.get_byte().context(codeloc!())?;
+        return Ok(());
+    }
+}
@@ -4,12 +4,12 @@ use anyhow::{anyhow, Context, Result};
 use cpal::{Device, FromSample, OutputCallbackInfo, SampleFormat, SizedSample, Stream, SupportedStreamConfig};
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use crate::codeloc;
+use crate::system::headless::SampleSink;
 
 pub struct Speaker
 {
     audio_stream : Stream,
-    sample_rate : u32,
-    current_sample_index : u32,
+    pub sample_rate : u32,
     sample_value_sender : Sender<f64>,
 }
 
@@ -40,7 +40,6 @@ impl Speaker
         {
             audio_stream: audio_stream,
             sample_rate: config.sample_rate().0,
-            current_sample_index: 0,
             sample_value_sender: waveform_sender,
         });
     }
@@ -58,7 +57,8 @@ impl Speaker
             {
                 if !was_end_of_stream_reached.load(Ordering::Relaxed)
                 {
-                    let raw_value = waveform_receiver.recv().unwrap_or_default();
+                    //samples arrive once per emulated frame, an underrun plays silence instead of stalling the device
+                    let raw_value = waveform_receiver.try_recv().unwrap_or_default();
                     if raw_value.is_nan()
                     {
                         was_end_of_stream_reached.store(true, Ordering::Relaxed);
@@ -86,14 +86,21 @@ impl Speaker
         self.audio_stream.pause().unwrap_or_default();
     }
 
-    pub fn advance_to_next_waveform_index(self : &mut Speaker) -> f64
+    pub fn accept_waveform_value(&self, value : f64)
     {
-        self.current_sample_index = (self.current_sample_index+1) % self.sample_rate;
-        return (self.current_sample_index as f64) / (self.sample_rate as f64);
+        self.sample_value_sender.send(value).unwrap_or_default();
     }
+}
 
-    pub fn accept_waveform_value(&self, value : f64)
+impl SampleSink for Speaker
+{
+    fn get_sample_rate(&self) -> u32
     {
-        self.sample_value_sender.send(value).unwrap_or_default();
+        return self.sample_rate;
+    }
+
+    fn accept_samples(&self, samples : &[f64])
+    {
+        for sample in samples { self.accept_waveform_value(*sample); }
     }
 }
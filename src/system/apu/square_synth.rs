@@ -1,5 +1,8 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
 use crate::system::apu::Synthesizer;
 use crate::system::byte;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
 
 pub struct SquareSynth
 {
@@ -79,3 +82,37 @@ impl Synthesizer for SquareSynth
         return if sine >= 0.0 { 1.0 } else { -1.0 };
     }
 }
+
+impl Snapshot for SquareSynth
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        writer.put_byte(self.duty_cycle);
+        writer.put_bool(self.length_counter_halt_flag);
+        writer.put_byte(self.length_counter_load);
+        writer.put_bool(self.constant_volume_flag);
+        writer.put_byte(self.volume_divider_period);
+        writer.put_bool(self.is_sweep_enabled);
+        writer.put_byte(self.sweep_divider_period);
+        writer.put_bool(self.is_sweep_negated);
+        writer.put_byte(self.sweep_shift_count);
+        writer.put_byte(self.timer_low);
+        writer.put_byte(self.timer_high);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.duty_cycle = reader.get_byte().context(codeloc!())?;
+        self.length_counter_halt_flag = reader.get_bool().context(codeloc!())?;
+        self.length_counter_load = reader.get_byte().context(codeloc!())?;
+        self.constant_volume_flag = reader.get_bool().context(codeloc!())?;
+        self.volume_divider_period = reader.get_byte().context(codeloc!())?;
+        self.is_sweep_enabled = reader.get_bool().context(codeloc!())?;
+        self.sweep_divider_period = reader.get_byte().context(codeloc!())?;
+        self.is_sweep_negated = reader.get_bool().context(codeloc!())?;
+        self.sweep_shift_count = reader.get_byte().context(codeloc!())?;
+        self.timer_low = reader.get_byte().context(codeloc!())?;
+        self.timer_high = reader.get_byte().context(codeloc!())?;
+        return Ok(());
+    }
+}
@@ -1,5 +1,8 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
 use crate::system::apu::Synthesizer;
 use crate::system::byte;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
 
 pub struct TriangleSynth
 {
@@ -58,3 +61,25 @@ impl Synthesizer for TriangleSynth
         return sine / pi_over_two - 1.0;
     }
 }
+
+impl Snapshot for TriangleSynth
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        writer.put_bool(self.length_counter_halt_flag);
+        writer.put_byte(self.length_counter_load);
+        writer.put_byte(self.linear_counter_load);
+        writer.put_byte(self.timer_low);
+        writer.put_byte(self.timer_high);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.length_counter_halt_flag = reader.get_bool().context(codeloc!())?;
+        self.length_counter_load = reader.get_byte().context(codeloc!())?;
+        self.linear_counter_load = reader.get_byte().context(codeloc!())?;
+        self.timer_low = reader.get_byte().context(codeloc!())?;
+        self.timer_high = reader.get_byte().context(codeloc!())?;
+        return Ok(());
+    }
+}
@@ -1,17 +1,19 @@
-use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
+use anyhow::{Context, Result};
+use crate::codeloc;
 
 use crate::system::{address, byte, CPUDebugger};
-use crate::system::apu_channels::CPUToAPUChannels;
+use crate::system::apu::APU;
 use crate::system::cpu::bus::CPUBus;
 use crate::system::cpu::clock::CPUClock;
 use crate::system::cpu::flags::CPUFlags;
 use crate::system::cpu::interrupts::CPUInterrupts;
-use crate::system::cpu::opcodes::build_opcodes_slice;
+use crate::system::cpu::opcodes::{build_opcodes_slice, Opcode};
 use crate::system::cpu::program_iterator::CPUProgramIterator;
-use crate::system::cpu::program_rom::ProgramROM;
+use crate::system::cpu::program_rom::PROGRAM_ROM_START_ADDRESS;
 use crate::system::debugger::LoggingOptions;
-use crate::system::ppu_channels::CPUToPPUChannels;
+use crate::system::mappers::SharedMapper;
+use crate::system::ppu::PPU;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
 
 mod opcodes;
 mod program_iterator;
@@ -21,8 +23,11 @@ mod clock;
 mod interrupts;
 pub mod bus;
 pub mod program_rom;
+pub mod program_ram;
 mod ram;
 
+const INTERRUPT_DURATION_IN_CPU_CYCLES : u64 = 7;
+
 #[allow(non_snake_case)]
 pub struct CPU
 {
@@ -41,19 +46,20 @@ pub struct CPURunEnvironment
 {
     pub debugger : CPUDebugger,
     pub logging_options : LoggingOptions,
-    pub is_shutting_down : Arc<AtomicBool>,
-    pub should_disable_interrupt_vectors : bool,
+    opcodes : Box<[Opcode]>,
 }
 
-pub struct CPUChannelsToOtherSystems
+impl CPURunEnvironment
 {
-    pub ppu_channels : CPUToPPUChannels,
-    pub apu_channels : CPUToAPUChannels,
+    pub fn new(debugger : CPUDebugger, logging_options : LoggingOptions) -> CPURunEnvironment
+    {
+        return CPURunEnvironment { debugger, logging_options, opcodes: build_opcodes_slice() };
+    }
 }
 
 impl CPU
 {
-    pub fn new(program_rom : ProgramROM, channels : CPUChannelsToOtherSystems) -> CPU
+    pub fn new(mapper : SharedMapper, ppu : PPU, apu : APU, logging_options : LoggingOptions) -> CPU
     {
         return CPU
         {
@@ -62,52 +68,88 @@ impl CPU
             Y: 0,
             clock: CPUClock::new(),
             stack_pointer: 0x0200,
-            program_counter: program_rom.program_start_address,
+            program_counter: PROGRAM_ROM_START_ADDRESS,
             flags: CPUFlags::from_byte(0),
-            bus: CPUBus::new(program_rom, channels),
+            bus: CPUBus::new(mapper, ppu, apu, logging_options),
             are_interrupt_vectors_disabled: false,
         };
     }
 
-    pub fn run(self : &mut CPU, env : CPURunEnvironment)
+    pub fn power_on(self : &mut CPU, should_disable_interrupt_vectors : bool)
+    {
+        self.are_interrupt_vectors_disabled = should_disable_interrupt_vectors;
+        CPUInterrupts::hardware_reset(self);
+    }
+
+    //executes one instruction, the PPU and APU are run before each of its bus accesses and after it,
+    //then services pending interrupts
+    pub fn step(self : &mut CPU, env : &CPURunEnvironment)
     {
         let cpu = self;
-        cpu.are_interrupt_vectors_disabled = env.should_disable_interrupt_vectors;
-        CPUInterrupts::hardware_reset(cpu);
+        cpu.clock.notify_cpu_cycle_started();
+        cpu.bus.notify_instruction_started();
 
-        let opcodes = build_opcodes_slice();
+        let opcode_key = CPUProgramIterator::next_byte_from_rom(cpu);
+        let opcode = &env.opcodes[opcode_key as usize];
+        let (address, value) = CPUProgramIterator::next_argument_from_rom(cpu, &opcode);
 
-        loop
+        if env.logging_options.is_cpu_opcode_logging_enabled
         {
-            if env.is_shutting_down.load(Ordering::Relaxed) { return }
-
-            cpu.clock.notify_cpu_cycle_started();
+            println!("[CPU] {} {:#06X} {:#04X}", opcode.name, address, value);
+        }
 
-            let opcode_key = CPUProgramIterator::next_byte_from_rom(cpu);
-            let opcode = &opcodes[opcode_key as usize];
-            let (address, value) = CPUProgramIterator::next_argument_from_rom(cpu, &opcode);
+        (opcode.lambda)(cpu, &opcode.addressing_mode, address, value);
 
-            if env.logging_options.is_cpu_opcode_logging_enabled
-            {
-                println!("[CPU] {} {:#06X} {:#04X}", opcode.name, address, value);
-            }
+        env.debugger.notify_cpu_state_to_watchers(cpu);
 
-            (opcode.lambda)(cpu, &opcode.addressing_mode, address, value);
+        let number_of_stall_cycles = cpu.bus.take_stall_cycle_count();
+        cpu.clock.notify_cpu_stalled(number_of_stall_cycles);
+        let number_of_cycles = cpu.clock.notify_cpu_cycle_stopped(&opcode) + number_of_stall_cycles;
+        cpu.bus.tick_remaining_cycles(number_of_cycles);
 
-            env.debugger.notify_cpu_state_to_watchers(cpu);
+        if cpu.bus.ppu.is_signaling_nmi()
+        {
+            if env.logging_options.is_cpu_opcode_logging_enabled { println!("[CPU] NMI"); }
+            cpu.bus.notify_instruction_started();
+            CPUInterrupts::hardware_nmi(cpu);
+            cpu.clock.notify_cpu_stalled(INTERRUPT_DURATION_IN_CPU_CYCLES);
+            cpu.bus.tick_remaining_cycles(INTERRUPT_DURATION_IN_CPU_CYCLES);
+        }
+        else if !cpu.flags.interrupt && cpu.bus.is_irq_pending()
+        {
+            if env.logging_options.is_cpu_opcode_logging_enabled { println!("[CPU] IRQ"); }
+            cpu.bus.notify_instruction_started();
+            CPUInterrupts::hardware_irq(cpu);
+            cpu.clock.notify_cpu_stalled(INTERRUPT_DURATION_IN_CPU_CYCLES);
+            cpu.bus.tick_remaining_cycles(INTERRUPT_DURATION_IN_CPU_CYCLES);
+        }
+    }
+}
 
-            cpu.clock.notify_cpu_cycle_stopped(&opcode);
+impl Snapshot for CPU
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        writer.put_byte(self.A);
+        writer.put_byte(self.X);
+        writer.put_byte(self.Y);
+        writer.put_u16(self.stack_pointer);
+        writer.put_u16(self.program_counter);
+        writer.put_byte(self.flags.to_byte());
+        self.clock.save_snapshot(writer);
+        self.bus.save_snapshot(writer);
+    }
 
-            if cpu.bus.channels.ppu_channels.ppu_is_signaling_that_vblank_has_started()
-            {
-                if env.logging_options.is_cpu_opcode_logging_enabled { println!("[CPU] NMI"); }
-                CPUInterrupts::hardware_nmi(cpu);
-            }
-            else if cpu.bus.channels.apu_channels.is_apu_signaling_that_frame_has_ended()
-            {
-                if env.logging_options.is_cpu_opcode_logging_enabled { println!("[CPU] IRQ"); }
-                CPUInterrupts::hardware_irq(cpu);
-            }
-        }
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.A = reader.get_byte().context(codeloc!())?;
+        self.X = reader.get_byte().context(codeloc!())?;
+        self.Y = reader.get_byte().context(codeloc!())?;
+        self.stack_pointer = reader.get_u16().context(codeloc!())?;
+        self.program_counter = reader.get_u16().context(codeloc!())?;
+        self.flags = CPUFlags::from_byte(reader.get_byte().context(codeloc!())?);
+        self.clock.load_snapshot(reader).context(codeloc!())?;
+        self.bus.load_snapshot(reader).context(codeloc!())?;
+        return Ok(());
     }
 }
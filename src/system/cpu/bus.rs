@@ -1,7 +1,15 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
 use crate::system::{address, address_from_high_low, byte};
-use crate::system::cpu::CPUChannelsToOtherSystems;
-use crate::system::cpu::program_rom::ProgramROM;
+use crate::system::apu::APU;
+use crate::system::apu::communication::CPUToAPUCommTarget;
 use crate::system::cpu::ram::RAM;
+use crate::system::debugger::LoggingOptions;
+use crate::system::input::InputSubsystem;
+use crate::system::mappers::SharedMapper;
+use crate::system::ppu::communication::CPUToPPUCommTarget;
+use crate::system::ppu::PPU;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
 
 const RAM_START_ADDRESS : address = 0x0000;
 const RAM_END_ADDRESS : address = 0x1FFF;
@@ -13,40 +21,141 @@ const APU_OPEN_BUS_ON_READ_START_ADDRESS : address = 0x4000;
 const APU_OPEN_BUS_ON_READ_END_ADDRESS : address = 0x4014;
 const APU_REGISTERS_START_ADDRESS : address = 0x4000;
 const APU_REGISTERS_END_ADDRESS : address = 0x4017;
-const PROGRAM_ROM_START_ADDRESS : address = 0x4020;
-const PROGRAM_ROM_END_ADDRESS : address = 0xFFFF;
+const CARTRIDGE_START_ADDRESS : address = 0x4020;
+
+const NUMBER_OF_PPU_DOTS_PER_CPU_CYCLE : usize = 3;
+const OAM_DMA_DURATION_IN_CPU_CYCLES : u64 = 513;
 
 pub struct CPUBus
 {
     ram : RAM,
-    pub program_rom : ProgramROM,
-    pub channels : CPUChannelsToOtherSystems,
+    pub mapper : SharedMapper,
+    pub ppu : PPU,
+    pub apu : APU,
+    pub input_subsystem : InputSubsystem,
+    logging_options : LoggingOptions,
     last_read_byte : byte,
+    stall_cycle_count : u64,
+    elapsed_cycle_count : u64,
+    pending_mapper_cycle_count : u64,
 }
 
 impl CPUBus
 {
-    pub fn new(program_rom : ProgramROM, channels : CPUChannelsToOtherSystems) -> CPUBus
+    pub fn new(mapper : SharedMapper, ppu : PPU, apu : APU, logging_options : LoggingOptions) -> CPUBus
     {
         return CPUBus
         {
             ram: RAM::new(),
-            program_rom: program_rom,
-            channels: channels,
+            mapper: mapper,
+            ppu: ppu,
+            apu: apu,
+            input_subsystem: InputSubsystem::new(),
+            logging_options: logging_options,
             last_read_byte: 0,
+            stall_cycle_count: 0,
+            elapsed_cycle_count: 0,
+            pending_mapper_cycle_count: 0,
         };
     }
 
+    pub fn notify_instruction_started(self : &mut CPUBus)
+    {
+        self.elapsed_cycle_count = 0;
+    }
+
+    //every bus access takes one CPU cycle, during which the PPU and APU are run before the access happens.
+    //this only leaves the internal cycles of the instruction, which are run after it was executed
+    pub fn tick_remaining_cycles(self : &mut CPUBus, number_of_cycles : u64)
+    {
+        let number_of_remaining_cycles = number_of_cycles.saturating_sub(self.elapsed_cycle_count);
+        self.tick(number_of_remaining_cycles);
+        self.elapsed_cycle_count = 0;
+        self.notify_mapper_of_pending_cycles();
+    }
+
+    //runs the PPU and APU for the duration of the CPU cycles that have just passed
+    fn tick(self : &mut CPUBus, number_of_cycles : u64)
+    {
+        for _ in 0..number_of_cycles
+        {
+            for _ in 0..NUMBER_OF_PPU_DOTS_PER_CPU_CYCLE
+            {
+                self.ppu.step();
+            }
+
+            self.apu.step();
+        }
+
+        self.pending_mapper_cycle_count += number_of_cycles;
+    }
+
+    //the mapper is told about the elapsed cycles once per instruction instead of on every cycle, to avoid locking it every time.
+    //its counters must still be up to date before the CPU accesses its registers or polls its IRQ
+    fn notify_mapper_of_pending_cycles(self : &mut CPUBus)
+    {
+        if self.pending_mapper_cycle_count == 0 { return }
+
+        self.mapper.lock().notify_cpu_cycles(self.pending_mapper_cycle_count);
+        self.pending_mapper_cycle_count = 0;
+    }
+
+    //cycles during which the CPU was halted by the last instruction, for instance by an OAM DMA
+    pub fn take_stall_cycle_count(self : &mut CPUBus) -> u64
+    {
+        let stall_cycle_count = self.stall_cycle_count;
+        self.stall_cycle_count = 0;
+        return stall_cycle_count;
+    }
+
+    pub fn is_irq_pending(self : &mut CPUBus) -> bool
+    {
+        self.notify_mapper_of_pending_cycles();
+        return self.mapper.lock().is_irq_pending() || self.apu.is_irq_pending();
+    }
+
     pub fn get(self : &mut CPUBus, raw_address : address) -> byte
     {
-        let read_byte = if raw_address >= RAM_START_ADDRESS && raw_address <= RAM_END_ADDRESS
+        self.tick(1);
+        self.elapsed_cycle_count += 1;
+        let read_byte = self.read(raw_address);
+        self.last_read_byte = read_byte;
+        return read_byte;
+    }
+
+    pub fn put(self : &mut CPUBus, raw_address : address, value : byte)
+    {
+        self.tick(1);
+        self.elapsed_cycle_count += 1;
+        self.put_without_ticking(raw_address, value);
+    }
+
+    //used by the debugger, which must not alter the timing of the emulated program nor the open bus value
+    pub fn get_without_ticking(self : &mut CPUBus, raw_address : address) -> byte
+    {
+        return self.read(raw_address);
+    }
+
+    fn read(self : &mut CPUBus, raw_address : address) -> byte
+    {
+        return if raw_address >= RAM_START_ADDRESS && raw_address <= RAM_END_ADDRESS
         {
             self.ram.get(raw_address)
         }
-        else if (raw_address >= PPU_REGISTERS_START_ADDRESS && raw_address <= PPU_REGISTERS_END_ADDRESS) || raw_address == JOYSTICK_ADDRESS
+        else if raw_address >= PPU_REGISTERS_START_ADDRESS && raw_address <= PPU_REGISTERS_END_ADDRESS
+        {
+            let target = CPUToPPUCommTarget::from_address(self.convert_raw_address_to_ppu_register_address(raw_address));
+            let value = self.ppu.handle_read_command_from_cpu(target);
+            if self.logging_options.is_cpu_to_ppu_register_logging_enabled
+            {
+                println!("[PPU→CPU] {:#04X} {:?}", value, target);
+            }
+
+            value
+        }
+        else if raw_address == JOYSTICK_ADDRESS
         {
-            let channel_address = self.convert_raw_address_to_ppu_channel_address(raw_address);
-            self.channels.ppu_channels.read(channel_address)
+            self.input_subsystem.get_pressed_key()
         }
         else if raw_address >= APU_OPEN_BUS_ON_READ_START_ADDRESS && raw_address <= APU_OPEN_BUS_ON_READ_END_ADDRESS
         {
@@ -54,19 +163,24 @@ impl CPUBus
         }
         else if raw_address >= APU_REGISTERS_START_ADDRESS && raw_address <= APU_REGISTERS_END_ADDRESS
         {
-            self.channels.apu_channels.read(raw_address)
+            let target = CPUToAPUCommTarget::from_address(raw_address);
+            let value = self.apu.handle_read_command_from_cpu(target);
+            if self.logging_options.is_cpu_to_apu_register_logging_enabled
+            {
+                println!("[APU→CPU] {:#04X} {:?}", value, target);
+            }
+
+            value
         }
-        else if raw_address >= PROGRAM_ROM_START_ADDRESS && raw_address <= PROGRAM_ROM_END_ADDRESS
+        else if raw_address >= CARTRIDGE_START_ADDRESS
         {
-            self.program_rom.get(raw_address)
+            self.notify_mapper_of_pending_cycles();
+            self.mapper.lock().cpu_read(raw_address)
         }
         else {0};
-
-        self.last_read_byte = read_byte;
-        return read_byte;
     }
 
-    pub fn put(self : &mut CPUBus, raw_address : address, value : byte)
+    pub fn put_without_ticking(self : &mut CPUBus, raw_address : address, value : byte)
     {
         if raw_address >= RAM_START_ADDRESS && raw_address <= RAM_END_ADDRESS
         {
@@ -77,28 +191,66 @@ impl CPUBus
             let start_address = address_from_high_low(value, 0x00);
             let end_address = address_from_high_low(value, 0xFF);
             let values_to_copy = (start_address..=end_address)
-                .map(|address| self.get(address)).collect::<Vec<byte>>().into_boxed_slice();
-            let channel_address = self.convert_raw_address_to_ppu_channel_address(raw_address);
-            self.channels.ppu_channels.write(channel_address, values_to_copy);
+                .map(|address| self.get(address)).collect::<Vec<byte>>();
+            self.write_to_ppu(raw_address, &values_to_copy);
+            self.stall_cycle_count += OAM_DMA_DURATION_IN_CPU_CYCLES;
+        }
+        else if raw_address >= PPU_REGISTERS_START_ADDRESS && raw_address <= PPU_REGISTERS_END_ADDRESS
+        {
+            self.write_to_ppu(raw_address, &[value]);
         }
-        else if (raw_address >= PPU_REGISTERS_START_ADDRESS && raw_address <= PPU_REGISTERS_END_ADDRESS) || raw_address == JOYSTICK_ADDRESS
+        else if raw_address == JOYSTICK_ADDRESS
         {
-            let channel_address = self.convert_raw_address_to_ppu_channel_address(raw_address);
-            self.channels.ppu_channels.write(channel_address, Box::new([value]));
+            self.input_subsystem.set_strobe_enabled(value & 0b00000001 == 1);
         }
         else if raw_address >= APU_REGISTERS_START_ADDRESS && raw_address <= APU_REGISTERS_END_ADDRESS
         {
-            self.channels.apu_channels.write(raw_address, value);
+            let target = CPUToAPUCommTarget::from_address(raw_address);
+            if self.logging_options.is_cpu_to_apu_register_logging_enabled
+            {
+                println!("[CPU→APU] {:#04X} {:?}", value, target);
+            }
+
+            self.apu.handle_write_command_from_cpu(target, value);
+        }
+        else if raw_address >= CARTRIDGE_START_ADDRESS
+        {
+            self.notify_mapper_of_pending_cycles();
+            self.mapper.lock().cpu_write(raw_address, value);
         }
-        else if raw_address >= PROGRAM_ROM_START_ADDRESS && raw_address < PROGRAM_ROM_END_ADDRESS
+    }
+
+    fn write_to_ppu(&mut self, raw_address : address, values : &[byte])
+    {
+        let target = CPUToPPUCommTarget::from_address(self.convert_raw_address_to_ppu_register_address(raw_address));
+        if self.logging_options.is_cpu_to_ppu_register_logging_enabled
         {
-            self.program_rom.set(raw_address, value)
+            let first_value = values.first().cloned().unwrap_or_default();
+            println!("[CPU→PPU] {:#04X} {:?}", first_value, target);
         }
+
+        self.ppu.handle_write_command_from_cpu(target, values);
     }
 
-    fn convert_raw_address_to_ppu_channel_address(&self, raw_address : address) -> address
+    fn convert_raw_address_to_ppu_register_address(&self, raw_address : address) -> address
     {
-        return if raw_address == PPU_OAM_DMA_ADDRESS || raw_address == JOYSTICK_ADDRESS { raw_address }
+        return if raw_address == PPU_OAM_DMA_ADDRESS { raw_address }
         else { ((raw_address-PPU_REGISTERS_START_ADDRESS) % 8) + PPU_REGISTERS_START_ADDRESS };
     }
 }
+
+impl Snapshot for CPUBus
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        self.ram.save_snapshot(writer);
+        writer.put_byte(self.last_read_byte);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.ram.load_snapshot(reader).context(codeloc!())?;
+        self.last_read_byte = reader.get_byte().context(codeloc!())?;
+        return Ok(());
+    }
+}
@@ -1,6 +1,6 @@
-use std::thread;
-use std::time::Duration;
+use anyhow::Result;
 use crate::system::cpu::opcodes::Opcode;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
 
 #[allow(non_camel_case_types)]
 pub enum ExpectedDuration
@@ -14,14 +14,12 @@ pub enum ExpectedDuration
     _6,  //6 CPU clock cycles
     _7,  //7 CPU clock cycles
     _8,  //8 CPU clock cycles
-    bra, //branch: 1 CPU clock cycle + 1 if branch was taken + 1 if page boundary was crossed
+    bra, //branch: 2 CPU clock cycles + 1 if branch was taken + 1 if page boundary was crossed
 }
 
 pub struct CPUClock
 {
     cycle_count : u64,
-    cycle_count_threshold : u64,
-    sleep_duration : Duration,
     was_page_boundary_crossed : bool,
     was_branch_taken : bool,
 }
@@ -30,12 +28,9 @@ impl CPUClock
 {
     pub fn new() -> CPUClock
     {
-        //todo thresholds should not be hardcoded, thresholds should be determined based on hardware capabilities!
         return CPUClock
         {
             cycle_count: 0,
-            cycle_count_threshold: 15000,
-            sleep_duration: Duration::from_millis(1),
             was_page_boundary_crossed: false,
             was_branch_taken: false,
         };
@@ -57,7 +52,7 @@ impl CPUClock
         self.was_branch_taken = false;
     }
 
-    pub fn notify_cpu_cycle_stopped(&mut self, opcode : &Opcode)
+    pub fn notify_cpu_cycle_stopped(&mut self, opcode : &Opcode) -> u64
     {
         let current_cycle_count : u64 = match opcode.expected_duration
         {
@@ -70,15 +65,29 @@ impl CPUClock
             ExpectedDuration::_6  => 6,
             ExpectedDuration::_7  => 7,
             ExpectedDuration::_8  => 8,
-            ExpectedDuration::bra => 1+(self.was_page_boundary_crossed as u64)+(self.was_branch_taken as u64),
+            ExpectedDuration::bra => 2+(self.was_page_boundary_crossed as u64)+(self.was_branch_taken as u64),
         };
 
         self.cycle_count += current_cycle_count;
+        return current_cycle_count;
+    }
 
-        if self.cycle_count >= self.cycle_count_threshold
-        {
-            self.cycle_count = 0;
-            thread::sleep(self.sleep_duration);
-        }
+    pub fn notify_cpu_stalled(&mut self, number_of_cycles : u64)
+    {
+        self.cycle_count += number_of_cycles;
+    }
+}
+
+impl Snapshot for CPUClock
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        writer.put_u64(self.cycle_count);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.cycle_count = reader.get_u64()?;
+        return Ok(());
     }
 }
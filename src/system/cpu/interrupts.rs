@@ -2,6 +2,10 @@ use crate::system::{address, address_from_high_low};
 use crate::system::cpu::CPU;
 use crate::system::cpu::stack::CPUStack;
 
+const NMI_VECTOR : address = 0xFFFA;
+const RESET_VECTOR : address = 0xFFFC;
+const IRQ_VECTOR : address = 0xFFFE;
+
 pub struct CPUInterrupts {}
 impl CPUInterrupts
 {
@@ -17,8 +21,7 @@ impl CPUInterrupts
 
         cpu.flags.interrupt = true;
 
-        let interrupt_vector = (cpu.bus.program_rom.len()-2) as address;
-        CPUInterrupts::set_program_counter_from_vector(cpu, interrupt_vector);
+        CPUInterrupts::set_program_counter_from_vector(cpu, IRQ_VECTOR);
     }
 
     pub fn hardware_irq(cpu : &mut CPU)
@@ -34,8 +37,7 @@ impl CPUInterrupts
 
             cpu.flags.interrupt = true;
 
-            let interrupt_vector = (cpu.bus.program_rom.len()-2) as address;
-            CPUInterrupts::set_program_counter_from_vector(cpu, interrupt_vector);
+            CPUInterrupts::set_program_counter_from_vector(cpu, IRQ_VECTOR);
         }
     }
 
@@ -50,8 +52,7 @@ impl CPUInterrupts
 
         cpu.flags.interrupt = true;
 
-        let interrupt_vector = (cpu.bus.program_rom.len()-6) as address;
-        CPUInterrupts::set_program_counter_from_vector(cpu, interrupt_vector);
+        CPUInterrupts::set_program_counter_from_vector(cpu, NMI_VECTOR);
     }
 
     pub fn hardware_reset(cpu : &mut CPU)
@@ -64,16 +65,15 @@ impl CPUInterrupts
 
         cpu.flags.interrupt = true;
 
-        let interrupt_vector = (cpu.bus.program_rom.len()-4) as address;
-        CPUInterrupts::set_program_counter_from_vector(cpu, interrupt_vector);
+        CPUInterrupts::set_program_counter_from_vector(cpu, RESET_VECTOR);
     }
 
     fn set_program_counter_from_vector(cpu : &mut CPU, interrupt_vector : address)
     {
         if !cpu.are_interrupt_vectors_disabled
         {
-            let low = cpu.bus.program_rom.get(interrupt_vector);
-            let high = cpu.bus.program_rom.get(interrupt_vector+1);
+            let low = cpu.bus.get(interrupt_vector);
+            let high = cpu.bus.get(interrupt_vector+1);
             let interrupt_handler_address = address_from_high_low(high, low);
             cpu.program_counter = interrupt_handler_address;
         }
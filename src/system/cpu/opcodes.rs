@@ -13,8 +13,17 @@ pub struct Opcode
     pub addressing_mode : AddressingMode,
     pub lambda : fn(&mut CPU, &AddressingMode, address, byte) -> (),
     pub expected_duration : ExpectedDuration,
+    pub should_read_argument : bool,
 }
 
+//store and jump instructions never read their target address. reading it anyway
+//would trigger the side effects of PPU and APU registers, such as clearing the vblank flag
+const OPCODES_THAT_DO_NOT_READ_ARGUMENT : [&str; 10] =
+[
+    "sta", "stx", "sty", "jmp", "jsr",
+    "unofficial_aax", "unofficial_axa", "unofficial_sxa", "unofficial_sya", "unofficial_xas",
+];
+
 macro_rules! opcode
 {
     ($key : expr, $name : expr, $expected_duration : expr, $addressing_mode : expr) =>
@@ -25,6 +34,7 @@ macro_rules! opcode
             name: stringify!($name).to_uppercase(),
             expected_duration: $expected_duration,
             addressing_mode: $addressing_mode,
+            should_read_argument: !OPCODES_THAT_DO_NOT_READ_ARGUMENT.contains(&stringify!($name)),
         }
     }
 }
@@ -350,6 +360,7 @@ fn bit(cpu : &mut CPU, _mode : &AddressingMode, _address : address, value : byte
 fn branch(cpu : &mut CPU, signed_offset : byte)
 {
     let abs_offset = ((signed_offset as i8) as i16).abs() as address;
+    let old_program_counter = cpu.program_counter;
     if isneg(signed_offset)
     {
         cpu.program_counter = cpu.program_counter.wrapping_sub(abs_offset);
@@ -360,6 +371,11 @@ fn branch(cpu : &mut CPU, signed_offset : byte)
         cpu.program_counter = cpu.program_counter.wrapping_add(abs_offset);
         cpu.clock.notify_branch_taken();
     }
+
+    if old_program_counter & 0xFF00 != cpu.program_counter & 0xFF00
+    {
+        cpu.clock.notify_page_boundary_crossed();
+    }
 }
 
 fn bpl(cpu : &mut CPU, _mode : &AddressingMode, _address : address, offset : byte)
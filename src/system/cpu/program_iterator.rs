@@ -44,7 +44,7 @@ impl CPUProgramIterator
             AddressingMode::Absolute =>
             {
                 let address = CPUProgramIterator::next_address_from_rom(cpu);
-                let value = cpu.bus.get(address);
+                let value = CPUProgramIterator::read_argument(cpu, opcode, address);
                 return (address, value);
             }
 
@@ -52,12 +52,12 @@ impl CPUProgramIterator
             {
                 let base_address = CPUProgramIterator::next_address_from_rom(cpu);
                 let address = base_address.wrapping_add(cpu.X as address);
-                if cpu.program_counter/RAM_PAGE_SIZE != address/RAM_PAGE_SIZE
+                if base_address/RAM_PAGE_SIZE != address/RAM_PAGE_SIZE
                 {
                     cpu.clock.notify_page_boundary_crossed();
                 }
 
-                let value = cpu.bus.get(address);
+                let value = CPUProgramIterator::read_argument(cpu, opcode, address);
                 return (address, value);
             }
 
@@ -65,19 +65,19 @@ impl CPUProgramIterator
             {
                 let base_address = CPUProgramIterator::next_address_from_rom(cpu);
                 let address = base_address.wrapping_add(cpu.Y as address);
-                if cpu.program_counter/RAM_PAGE_SIZE != address/RAM_PAGE_SIZE
+                if base_address/RAM_PAGE_SIZE != address/RAM_PAGE_SIZE
                 {
                     cpu.clock.notify_page_boundary_crossed();
                 }
 
-                let value = cpu.bus.get(address);
+                let value = CPUProgramIterator::read_argument(cpu, opcode, address);
                 return (address, value);
             }
 
             AddressingMode::ZeroPage =>
             {
                 let address = CPUProgramIterator::next_byte_from_rom(cpu);
-                let value = cpu.bus.get(address as address);
+                let value = CPUProgramIterator::read_argument(cpu, opcode, address as address);
                 return (address as address, value);
             }
 
@@ -85,7 +85,7 @@ impl CPUProgramIterator
             {
                 let base_address = CPUProgramIterator::next_byte_from_rom(cpu);
                 let address = base_address.wrapping_add(cpu.X);
-                let value = cpu.bus.get(address as address);
+                let value = CPUProgramIterator::read_argument(cpu, opcode, address as address);
                 return (address as address, value);
             }
 
@@ -93,7 +93,7 @@ impl CPUProgramIterator
             {
                 let base_address = CPUProgramIterator::next_byte_from_rom(cpu);
                 let address = base_address.wrapping_add(cpu.Y);
-                let value = cpu.bus.get(address as address);
+                let value = CPUProgramIterator::read_argument(cpu, opcode, address as address);
                 return (address as address, value);
             }
 
@@ -105,7 +105,7 @@ impl CPUProgramIterator
                 let low = cpu.bus.get(low_address);
                 let high = cpu.bus.get(high_address);
                 let address = address_from_high_low(high, low);
-                let value = cpu.bus.get(address as address);
+                let value = CPUProgramIterator::read_argument(cpu, opcode, address as address);
                 return (address, value);
             }
 
@@ -116,12 +116,7 @@ impl CPUProgramIterator
                 let low = cpu.bus.get(base_address as address);
                 let high = cpu.bus.get(base_address.wrapping_add(1) as address);
                 let address = address_from_high_low(high, low);
-                if cpu.program_counter/RAM_PAGE_SIZE != address/RAM_PAGE_SIZE
-                {
-                    cpu.clock.notify_page_boundary_crossed();
-                }
-
-                let value = cpu.bus.get(address as address);
+                let value = CPUProgramIterator::read_argument(cpu, opcode, address as address);
                 return (address as address, value);
             }
 
@@ -132,12 +127,12 @@ impl CPUProgramIterator
                 let high = cpu.bus.get(base_base_address.wrapping_add(1) as address);
                 let base_address = address_from_high_low(high, low);
                 let address = base_address.wrapping_add(cpu.Y as address);
-                if cpu.program_counter/RAM_PAGE_SIZE != address/RAM_PAGE_SIZE
+                if base_address/RAM_PAGE_SIZE != address/RAM_PAGE_SIZE
                 {
                     cpu.clock.notify_page_boundary_crossed();
                 }
 
-                let value = cpu.bus.get(address as address);
+                let value = CPUProgramIterator::read_argument(cpu, opcode, address as address);
                 return (address as address, value);
             }
 
@@ -154,6 +149,11 @@ impl CPUProgramIterator
         };
     }
 
+    fn read_argument(cpu : &mut CPU, opcode : &Opcode, address : address) -> byte
+    {
+        return if opcode.should_read_argument { cpu.bus.get(address) } else { 0 };
+    }
+
     pub fn next_byte_from_rom(cpu : &mut CPU) -> byte
     {
         let value = cpu.bus.get(cpu.program_counter);
@@ -0,0 +1,104 @@
+use anyhow::Result;
+use crate::system::{address, byte};
+use crate::system::rom::header::RomHeader;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
+
+pub const PROGRAM_RAM_START_ADDRESS : address = 0x6000;
+pub const PROGRAM_RAM_END_ADDRESS : address = 0x7FFF;
+const TRAINER_START_ADDRESS : address = 0x7000;
+const MINIMUM_PROGRAM_RAM_SIZE_WITH_TRAINER : usize = 8*1024;
+
+pub struct ProgramRAM
+{
+    pub is_battery_backed : bool,
+    was_recently_changed : bool,
+    trainer : Box<[byte]>,
+    bytes : Box<[byte]>
+}
+
+impl ProgramRAM
+{
+    pub fn new(header : &RomHeader) -> ProgramRAM
+    {
+        //the trainer is mapped at $7000-$71FF, so it needs RAM even when the header declares none
+        let mut size = header.program_ram_size + header.program_nvram_size;
+        if header.has_trainer { size = size.max(MINIMUM_PROGRAM_RAM_SIZE_WITH_TRAINER); }
+
+        let bytes = vec![0; size].into_boxed_slice(); //usually 8kB
+        return ProgramRAM { is_battery_backed: header.has_battery, was_recently_changed: false, trainer: Box::new([]), bytes: bytes };
+    }
+
+    pub fn load_trainer(self : &mut ProgramRAM, trainer : &[byte])
+    {
+        self.trainer = trainer.into();
+        self.apply_trainer();
+    }
+
+    //the trainer is part of the ROM, so it must not mark the RAM as changed and end up in the .sav file
+    fn apply_trainer(self : &mut ProgramRAM)
+    {
+        if self.bytes.is_empty() { return }
+        let start_index = ((TRAINER_START_ADDRESS - PROGRAM_RAM_START_ADDRESS) as usize) % self.bytes.len();
+        let end_index = (start_index + self.trainer.len()).min(self.bytes.len());
+        self.bytes[start_index..end_index].copy_from_slice(&self.trainer[0..end_index-start_index]);
+    }
+
+    pub fn get(self : &ProgramRAM, raw_address : address) -> byte
+    {
+        if self.bytes.is_empty() { return 0 }
+        let address = ((raw_address - PROGRAM_RAM_START_ADDRESS) as usize) % self.bytes.len();
+        return self.bytes[address];
+    }
+
+    pub fn put(self : &mut ProgramRAM, raw_address : address, value : byte)
+    {
+        if self.bytes.is_empty() { return }
+        let address = ((raw_address - PROGRAM_RAM_START_ADDRESS) as usize) % self.bytes.len();
+        if self.bytes[address] != value
+        {
+            self.bytes[address] = value;
+            self.was_recently_changed = true;
+        }
+    }
+
+    pub fn as_bytes(self : &ProgramRAM) -> &[byte]
+    {
+        return &self.bytes;
+    }
+
+    pub fn load_bytes(self : &mut ProgramRAM, bytes : &[byte])
+    {
+        let length = bytes.len().min(self.bytes.len());
+        self.bytes[0..length].copy_from_slice(&bytes[0..length]);
+
+        //a .sav file must not overwrite the trainer code
+        self.apply_trainer();
+    }
+
+    pub fn mark_as_changed(self : &mut ProgramRAM)
+    {
+        self.was_recently_changed = true;
+    }
+
+    pub fn was_recently_changed(self : &mut ProgramRAM) -> bool
+    {
+        let was_recently_changed_prev = self.was_recently_changed;
+        self.was_recently_changed = false;
+        return was_recently_changed_prev;
+    }
+}
+
+impl Snapshot for ProgramRAM
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        writer.put_bytes(&self.bytes);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        reader.get_bytes_into(&mut self.bytes)?;
+        self.was_recently_changed = true;
+        return Ok(());
+    }
+}
@@ -1,89 +1,37 @@
-use std::collections::HashSet;
-use std::hash::{Hash, Hasher};
-use crate::system::{address, byte, mapper};
+use crate::system::{address, byte};
+
+pub const PROGRAM_ROM_START_ADDRESS : address = 0x8000;
 
 pub struct ProgramROM
 {
-    mapper : mapper,
-    mappings : HashSet<SimpleMapping>,
     bytes : Box<[byte]>,
-    pub program_start_address : address,
-    pub program_end_address : address,
-}
-
-type large_address = usize;
-
-#[derive(Eq, PartialEq)]
-struct SimpleMapping
-{
-    source_start_address : address,
-    destination_start_address : large_address,
-    length : address,
 }
 
-impl Hash for SimpleMapping
+impl ProgramROM
 {
-    fn hash<H : Hasher>(&self, state : &mut H)
+    pub fn new(bytes : &[byte]) -> ProgramROM
     {
-        state.write_u16(self.source_start_address);
+        return ProgramROM { bytes: bytes.to_owned().into_boxed_slice() };
     }
-}
 
-impl ProgramROM
-{
-    pub fn new(mapper : mapper, bytes : &[byte]) -> ProgramROM
+    pub fn number_of_banks(&self, bank_size : usize) -> usize
     {
-        let mut rom = ProgramROM
-        {
-            mapper: mapper, mappings: HashSet::new(),
-            bytes: bytes.to_owned().into_boxed_slice(),
-            program_start_address: 0x8000,
-            program_end_address: 0xFFFF,
-        };
-
-        if mapper==31
-        {
-            rom.set(0x5FFF, 0xFF);
-        }
-
-        return rom;
+        return (self.bytes.len() / bank_size).max(1);
     }
 
-    pub fn len(&self) -> usize { self.bytes.len() }
-
+    //linear mapping of $8000-$FFFF, mirrored if the ROM is smaller than 32kB
     pub fn get(&self, raw_address : address) -> byte
     {
-
-        if raw_address>=self.program_start_address && raw_address<=self.program_end_address
-        {
-            for mapping in &self.mappings
-            {
-                if mapping.source_start_address<=raw_address && raw_address<=mapping.source_start_address-1+mapping.length
-                {
-                    let mapped_address = ((raw_address - mapping.source_start_address) as large_address) + mapping.destination_start_address;
-                    return self.bytes[mapped_address % self.bytes.len()];
-                }
-            }
-
-            let mapped_address = raw_address - self.program_start_address;
-            return self.bytes[(mapped_address as usize) % self.bytes.len()];
-        }
-
-        return self.bytes[(raw_address as usize) % self.bytes.len()];
+        if self.bytes.is_empty() { return 0 }
+        let mapped_address = raw_address.wrapping_sub(PROGRAM_ROM_START_ADDRESS);
+        return self.bytes[(mapped_address as usize) % self.bytes.len()];
     }
 
-    pub fn set(&mut self, raw_address : address, value : byte)
+    pub fn get_from_bank(&self, bank_index : usize, bank_size : usize, offset : usize) -> byte
     {
-        if self.mapper==31 && raw_address>=0x5FF8 && raw_address<=0x5FFF
-        {
-            let bank_size = 4*1024 as address;
-            let number_of_banks_in_source = 8 as address;
-            let number_of_banks_in_destination = self.bytes.len() / (bank_size as large_address);
-            let bank_index = (value as large_address) % number_of_banks_in_destination;
-            let destination_start_address = (bank_size as large_address) * bank_index;
-            let source_start_address = self.program_start_address + ((raw_address & 0xF) % number_of_banks_in_source) * bank_size;
-            let mapping = SimpleMapping { source_start_address, destination_start_address, length:bank_size };
-            self.mappings.insert(mapping);
-        }
+        if self.bytes.is_empty() { return 0 }
+        let bank_index = bank_index % self.number_of_banks(bank_size);
+        let mapped_address = bank_index * bank_size + (offset % bank_size);
+        return self.bytes[mapped_address % self.bytes.len()];
     }
 }
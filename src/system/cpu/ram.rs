@@ -1,4 +1,6 @@
+use anyhow::Result;
 use crate::system::{address, byte};
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
 
 pub struct RAM
 {
@@ -24,3 +26,16 @@ impl RAM
         self.bytes[address] = value;
     }
 }
+
+impl Snapshot for RAM
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        writer.put_bytes(&self.bytes);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        return reader.get_bytes_into(&mut self.bytes);
+    }
+}
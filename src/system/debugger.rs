@@ -1,3 +1,4 @@
+use std::cell::RefCell;
 use std::collections::HashMap;
 use flume::{Receiver, Sender};
 use crate::system::{address, byte};
@@ -15,8 +16,8 @@ macro_rules! codeloc
 pub struct LoggingOptions
 {
     pub is_cpu_opcode_logging_enabled : bool,
-    pub is_cpu_to_ppu_channel_logging_enabled : bool,
-    pub is_cpu_to_apu_channel_logging_enabled : bool,
+    pub is_cpu_to_ppu_register_logging_enabled : bool,
+    pub is_cpu_to_apu_register_logging_enabled : bool,
     pub is_system_threads_shutdown_logging_enabled : bool,
 }
 
@@ -27,8 +28,8 @@ impl LoggingOptions
         return LoggingOptions
         {
             is_cpu_opcode_logging_enabled: false,
-            is_cpu_to_ppu_channel_logging_enabled: false,
-            is_cpu_to_apu_channel_logging_enabled: false,
+            is_cpu_to_ppu_register_logging_enabled: false,
+            is_cpu_to_apu_register_logging_enabled: false,
             is_system_threads_shutdown_logging_enabled: true,
         };
     }
@@ -78,6 +79,7 @@ pub struct CPUDebugger
     pub cpu_bus_watcher_targets : Vec<address>,
     pub cpu_bus_watcher : Option<Sender<RAMSnapshot>>,
     pub cpu_bus_setter : Option<Receiver<RAMSnapshot>>,
+    last_cpu_bus_watcher_values : RefCell<Option<Vec<byte>>>,
 }
 
 impl CPUDebugger
@@ -90,6 +92,7 @@ impl CPUDebugger
             cpu_bus_watcher_targets: Vec::new(),
             cpu_bus_watcher: None,
             cpu_bus_setter: None,
+            last_cpu_bus_watcher_values: RefCell::new(None),
         };
     }
 
@@ -100,11 +103,20 @@ impl CPUDebugger
             sender.send(CPUState::from(&*cpu)).unwrap_or_default();
         }
 
+        //the watched values are only sent when one of them changed, otherwise a long run would queue a snapshot per instruction
         if let Some(sender) = &self.cpu_bus_watcher
         {
-            sender.send(RAMSnapshot::new(self.cpu_bus_watcher_targets.iter()
-                .map(|address| (*address, cpu.bus.get(*address)))
-                .collect())).unwrap_or_default();
+            let values = self.cpu_bus_watcher_targets.iter()
+                .map(|address| cpu.bus.get_without_ticking(*address))
+                .collect::<Vec<byte>>();
+
+            let mut last_values = self.last_cpu_bus_watcher_values.borrow_mut();
+            if last_values.as_ref() != Some(&values)
+            {
+                sender.send(RAMSnapshot::new(self.cpu_bus_watcher_targets.iter().cloned()
+                    .zip(values.iter().cloned()).collect())).unwrap_or_default();
+                *last_values = Some(values);
+            }
         }
 
         if let Some(receiver) = &self.cpu_bus_setter
@@ -113,13 +125,14 @@ impl CPUDebugger
             {
                 for (address, value) in ram_snapshot.variables
                 {
-                    cpu.bus.put(address, value);
+                    cpu.bus.put_without_ticking(address, value);
                 }
             }
         }
     }
 }
 
+#[derive(Clone)]
 pub struct PPUDebugger
 {
     pub should_render_background : bool,
@@ -0,0 +1,78 @@
+use std::sync::{Arc, Mutex};
+use crate::system::ppu::framebuffer::Framebuffer;
+
+//receives every completed frame, as system palette indices
+pub trait FrameSink
+{
+    fn accept_frame(&self, framebuffer : &Framebuffer);
+}
+
+//receives the audio samples produced by the APU during each frame
+pub trait SampleSink
+{
+    fn get_sample_rate(&self) -> u32;
+    fn accept_samples(&self, samples : &[f64]);
+}
+
+//headless backend: frames are kept in memory instead of being drawn into an SDL window.
+//clones share the same storage, so a clone can be given to the system and the other one inspected
+#[derive(Clone)]
+pub struct InMemoryFrameSink
+{
+    frames : Arc<Mutex<Vec<Box<[u16]>>>>,
+}
+
+impl InMemoryFrameSink
+{
+    pub fn new() -> InMemoryFrameSink
+    {
+        return InMemoryFrameSink { frames: Arc::new(Mutex::new(Vec::new())) };
+    }
+
+    pub fn take_frames(&self) -> Vec<Box<[u16]>>
+    {
+        return std::mem::take(&mut *self.frames.lock().unwrap());
+    }
+}
+
+impl FrameSink for InMemoryFrameSink
+{
+    fn accept_frame(&self, framebuffer : &Framebuffer)
+    {
+        self.frames.lock().unwrap().push(framebuffer.as_slice().into());
+    }
+}
+
+//headless backend: samples are kept in memory instead of being played on a cpal output device
+#[derive(Clone)]
+pub struct InMemorySampleSink
+{
+    sample_rate : u32,
+    samples : Arc<Mutex<Vec<f64>>>,
+}
+
+impl InMemorySampleSink
+{
+    pub fn new(sample_rate : u32) -> InMemorySampleSink
+    {
+        return InMemorySampleSink { sample_rate, samples: Arc::new(Mutex::new(Vec::new())) };
+    }
+
+    pub fn take_samples(&self) -> Vec<f64>
+    {
+        return std::mem::take(&mut *self.samples.lock().unwrap());
+    }
+}
+
+impl SampleSink for InMemorySampleSink
+{
+    fn get_sample_rate(&self) -> u32
+    {
+        return self.sample_rate;
+    }
+
+    fn accept_samples(&self, samples : &[f64])
+    {
+        self.samples.lock().unwrap().extend_from_slice(samples);
+    }
+}
@@ -15,6 +15,7 @@ pub struct InputSubsystem
     emulated_joystick : Joystick,
     physical_joystick : Option<Device>,
     physical_keyboard_keymap : HashMap<Keycode, JoystickKeycode>,
+    save_state_slot : byte,
 }
 
 #[derive(Eq, PartialEq, Hash, Clone, Copy, Display, EnumIter)]
@@ -44,6 +45,7 @@ impl InputSubsystem
                 Keycode::A      => JoystickKeycode::Left,
                 Keycode::D      => JoystickKeycode::Right,
             },
+            save_state_slot: 0,
         };
     }
 
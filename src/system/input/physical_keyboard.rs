@@ -1,10 +1,12 @@
 use sdl2::keyboard::Keycode;
+use crate::system::byte;
+use crate::system::debugger::PPUDebugger;
 use crate::system::input::InputSubsystem;
-use crate::system::ppu::PPURunEnvironment;
+use crate::system::save_state::SaveStateRequest;
 
 impl InputSubsystem
 {
-    pub fn handle_physical_keyboard_keydown(&mut self, keycode : Keycode, env : &mut PPURunEnvironment)
+    pub fn handle_physical_keyboard_keydown(&mut self, keycode : Keycode, debugger : &mut PPUDebugger) -> Option<SaveStateRequest>
     {
         if let Some(joystick_keycode) = self.physical_keyboard_keymap.get(&keycode)
         {
@@ -14,15 +16,25 @@ impl InputSubsystem
         {
             match keycode
             {
-                Keycode::F5 => { env.debugger.should_render_background = false; }
-                Keycode::F6 => { env.debugger.should_render_sprites = false; }
-                Keycode::F7 => { env.debugger.should_debug_pattern_table = true; }
+                Keycode::F5 => { debugger.should_render_background = false; }
+                Keycode::F6 => { debugger.should_render_sprites = false; }
+                Keycode::F7 => { debugger.should_debug_pattern_table = true; }
+                Keycode::F1 => { return Some(SaveStateRequest::Save(self.save_state_slot)); }
+                Keycode::F2 => { return Some(SaveStateRequest::Load(self.save_state_slot)); }
+                Keycode::Num0 | Keycode::Num1 | Keycode::Num2 | Keycode::Num3 | Keycode::Num4 |
+                Keycode::Num5 | Keycode::Num6 | Keycode::Num7 | Keycode::Num8 | Keycode::Num9 =>
+                {
+                    self.save_state_slot = (keycode.into_i32() - Keycode::Num0.into_i32()) as byte;
+                    println!("[SYS] Selected save state slot {}", self.save_state_slot);
+                }
                 _ => {}
             }
         }
+
+        return None;
     }
 
-    pub fn handle_physical_keyboard_keyup(&mut self, keycode : Keycode, env : &mut PPURunEnvironment)
+    pub fn handle_physical_keyboard_keyup(&mut self, keycode : Keycode, debugger : &mut PPUDebugger)
     {
         if let Some(joystick_keycode) = self.physical_keyboard_keymap.get(&keycode)
         {
@@ -32,9 +44,9 @@ impl InputSubsystem
         {
             match keycode
             {
-                Keycode::F5 => { env.debugger.should_render_background = true; }
-                Keycode::F6 => { env.debugger.should_render_sprites = true; }
-                Keycode::F7 => { env.debugger.should_debug_pattern_table = false; }
+                Keycode::F5 => { debugger.should_render_background = true; }
+                Keycode::F6 => { debugger.should_render_sprites = true; }
+                Keycode::F7 => { debugger.should_debug_pattern_table = false; }
                 _ => {}
             }
         }
@@ -0,0 +1,198 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+use anyhow::{anyhow, Context, Result};
+use maplit2::hashmap;
+use crate::codeloc;
+use crate::system::{address, byte, mapper};
+use crate::system::apu::Synthesizer;
+use crate::system::cpu::program_ram::{PROGRAM_RAM_END_ADDRESS, PROGRAM_RAM_START_ADDRESS, ProgramRAM};
+use crate::system::cpu::program_rom::ProgramROM;
+use crate::system::mappers::axrom::AxROM;
+use crate::system::mappers::bnrom::BNROM;
+use crate::system::mappers::cnrom::CNROM;
+use crate::system::mappers::color_dreams::ColorDreams;
+use crate::system::mappers::fme7::FME7;
+use crate::system::mappers::gxrom::GxROM;
+use crate::system::mappers::mmc1::MMC1;
+use crate::system::mappers::mmc2::MMC2;
+use crate::system::mappers::mmc3::MMC3;
+use crate::system::mappers::namco163::Namco163;
+use crate::system::mappers::nina001::NINA001;
+use crate::system::mappers::nrom::NROM;
+use crate::system::mappers::nsf::NSF;
+use crate::system::mappers::uxrom::UxROM;
+use crate::system::mappers::vrc4::VRC4;
+use crate::system::mappers::vrc6::VRC6;
+use crate::system::mappers::vrc7::VRC7;
+use crate::system::ppu::character_rom::CharacterROM;
+use crate::system::ppu::mirroring::Mirroring;
+use crate::system::rom::ParsedROM;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
+
+mod axrom;
+mod bnrom;
+mod cnrom;
+mod color_dreams;
+mod fme7;
+mod gxrom;
+mod mmc1;
+mod mmc2;
+mod mmc3;
+mod namco163;
+mod namco163_audio;
+mod nina001;
+mod nrom;
+mod nsf;
+mod sunsoft5b_audio;
+mod uxrom;
+mod vrc_irq;
+mod vrc4;
+mod vrc6;
+mod vrc6_audio;
+mod vrc7;
+mod vrc7_audio;
+
+//https://www.nesdev.org/wiki/Mapper
+//bank registers, IRQ counters, PRG-RAM and CHR-RAM are part of save states, ROM contents are not
+pub trait Mapper : Send + Snapshot
+{
+    //CPU side, $4020-$FFFF: PRG-ROM banks, PRG-RAM and mapper registers
+    fn cpu_read(&mut self, raw_address : address) -> byte;
+    fn cpu_write(&mut self, raw_address : address, value : byte);
+
+    //PPU side, $0000-$1FFF: CHR-ROM or CHR-RAM banks
+    fn ppu_read(&self, raw_address : address) -> byte;
+    fn ppu_write(&mut self, raw_address : address, value : byte);
+
+    fn mirroring(&self) -> Mirroring;
+
+    //$6000-$7FFF work RAM, persisted into the .sav file on battery-backed boards
+    fn program_ram(&mut self) -> &mut ProgramRAM;
+
+    //called for every pattern / nametable fetch the PPU does while rendering
+    fn notify_ppu_address_fetch(&mut self, _raw_address : address) {}
+
+    //called after every CPU instruction, for mappers with cycle based IRQ counters
+    fn notify_cpu_cycles(&mut self, _number_of_cycles : u64) {}
+
+    fn is_irq_pending(&self) -> bool { false }
+
+    //sound chips on the cartridge, mixed by the APU together with its own channels
+    fn expansion_audio_channels(&self) -> Vec<&dyn Synthesizer> { Vec::new() }
+}
+
+type MapperConstructor = fn(ParsedROM) -> Box<dyn Mapper>;
+
+//the memories every board has, boards add their bank registers on top of it
+//mirroring starts as the header's nametable layout, boards with mirroring control overwrite it
+pub struct Cartridge
+{
+    pub program_rom : ProgramROM,
+    pub program_ram : ProgramRAM,
+    pub character_rom : CharacterROM,
+    pub mirroring : Mirroring,
+}
+
+impl Cartridge
+{
+    pub fn new(parsed_rom : ParsedROM) -> Cartridge
+    {
+        return Cartridge
+        {
+            program_rom: parsed_rom.program_rom,
+            program_ram: parsed_rom.program_ram,
+            character_rom: parsed_rom.character_rom,
+            mirroring: parsed_rom.header.mirroring,
+        };
+    }
+
+    pub fn is_program_ram_address(raw_address : address) -> bool
+    {
+        return raw_address >= PROGRAM_RAM_START_ADDRESS && raw_address <= PROGRAM_RAM_END_ADDRESS;
+    }
+}
+
+impl Snapshot for Cartridge
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        self.program_ram.save_snapshot(writer);
+        self.character_rom.save_snapshot(writer);
+        self.mirroring.save_snapshot(writer);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.program_ram.load_snapshot(reader).context(codeloc!())?;
+        self.character_rom.load_snapshot(reader).context(codeloc!())?;
+        self.mirroring.load_snapshot(reader).context(codeloc!())?;
+        return Ok(());
+    }
+}
+
+//mappers are shared between the CPU bus (PRG side) and the PPU bus (CHR side)
+#[derive(Clone)]
+pub struct SharedMapper
+{
+    mapper : Arc<Mutex<Box<dyn Mapper>>>,
+}
+
+impl SharedMapper
+{
+    pub fn new(mapper : Box<dyn Mapper>) -> SharedMapper
+    {
+        return SharedMapper { mapper: Arc::new(Mutex::new(mapper)) };
+    }
+
+    pub fn lock(&self) -> MutexGuard<'_, Box<dyn Mapper>>
+    {
+        return self.mapper.lock().unwrap();
+    }
+}
+
+pub struct MapperRegistry {}
+impl MapperRegistry
+{
+    pub fn build(parsed_rom : ParsedROM) -> Result<Box<dyn Mapper>>
+    {
+        //key = (iNES mapper number, NES 2.0 submapper), None matches any submapper
+        let constructors = hashmap!
+        {
+            (0 as mapper, None as Option<byte>) => NROM::build as MapperConstructor,
+            (1, None) => MMC1::build,
+            (2, None) => UxROM::build,
+            (3, None) => CNROM::build,
+            (4, None) => MMC3::build,
+            (7, None) => AxROM::build,
+            (9, None) => MMC2::build,
+            (10, None) => MMC2::build_mmc4,
+            (11, None) => ColorDreams::build,
+            (19, None) => Namco163::build,
+            (21, None) => VRC4::build_mapper21,
+            (22, None) => VRC4::build_mapper22,
+            (23, None) => VRC4::build_mapper23,
+            (23, Some(3)) => VRC4::build_mapper23_vrc2b,
+            (24, None) => VRC6::build_mapper24,
+            (25, None) => VRC4::build_mapper25,
+            (25, Some(3)) => VRC4::build_mapper25_vrc2c,
+            (26, None) => VRC6::build_mapper26,
+            (31, None) => NSF::build,
+            (34, None) => BNROM::build_mapper34,
+            (34, Some(1)) => NINA001::build,
+            (34, Some(2)) => BNROM::build,
+            (66, None) => GxROM::build,
+            (69, None) => FME7::build,
+            (85, None) => VRC7::build,
+        };
+
+        let mapper_number = parsed_rom.header.mapper;
+        let submapper_number = parsed_rom.header.submapper;
+
+        if let Some(constructor) = constructors.get(&(mapper_number, Some(submapper_number)))
+            .or_else(|| constructors.get(&(mapper_number, None)))
+        {
+            return Ok(constructor(parsed_rom));
+        }
+
+        return Err(anyhow!("Unsupported mapper {} (submapper {})!", mapper_number, submapper_number));
+    }
+}
@@ -0,0 +1,94 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::{address, byte};
+use crate::system::cpu::program_ram::ProgramRAM;
+use crate::system::cpu::program_rom::PROGRAM_ROM_START_ADDRESS;
+use crate::system::mappers::{Cartridge, Mapper};
+use crate::system::ppu::mirroring::Mirroring;
+use crate::system::rom::ParsedROM;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
+
+const PROGRAM_BANK_SIZE : usize = 32*1024;
+
+//https://www.nesdev.org/wiki/AxROM
+pub struct AxROM
+{
+    cartridge : Cartridge,
+    selected_program_bank : usize,
+}
+
+impl AxROM
+{
+    pub fn build(parsed_rom : ParsedROM) -> Box<dyn Mapper>
+    {
+        let mut cartridge = Cartridge::new(parsed_rom);
+        cartridge.mirroring = Mirroring::SingleScreenLower;
+
+        return Box::new(AxROM
+        {
+            cartridge: cartridge,
+            selected_program_bank: 0,
+        });
+    }
+}
+
+impl Mapper for AxROM
+{
+    fn cpu_read(&mut self, raw_address : address) -> byte
+    {
+        return if raw_address >= PROGRAM_ROM_START_ADDRESS
+            { self.cartridge.program_rom.get_from_bank(self.selected_program_bank, PROGRAM_BANK_SIZE, (raw_address - PROGRAM_ROM_START_ADDRESS) as usize) }
+        else if Cartridge::is_program_ram_address(raw_address) { self.cartridge.program_ram.get(raw_address) }
+        else { 0 };
+    }
+
+    fn cpu_write(&mut self, raw_address : address, value : byte)
+    {
+        if raw_address >= PROGRAM_ROM_START_ADDRESS
+        {
+            //xxxMxPPP: M selects the single screen nametable, P selects the 32kB PRG bank
+            self.selected_program_bank = (value & 0b00000111) as usize;
+            self.cartridge.mirroring = if value & 0b00010000 == 0 { Mirroring::SingleScreenLower } else { Mirroring::SingleScreenUpper };
+        }
+        else if Cartridge::is_program_ram_address(raw_address)
+        {
+            self.cartridge.program_ram.put(raw_address, value);
+        }
+    }
+
+    fn ppu_read(&self, raw_address : address) -> byte
+    {
+        return self.cartridge.character_rom.get(raw_address);
+    }
+
+    fn ppu_write(&mut self, raw_address : address, value : byte)
+    {
+        self.cartridge.character_rom.put(raw_address, value);
+    }
+
+    fn mirroring(&self) -> Mirroring
+    {
+        return self.cartridge.mirroring;
+    }
+
+    fn program_ram(&mut self) -> &mut ProgramRAM
+    {
+        return &mut self.cartridge.program_ram;
+    }
+}
+
+impl Snapshot for AxROM
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        self.cartridge.save_snapshot(writer);
+        writer.put_usize(self.selected_program_bank);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.cartridge.load_snapshot(reader).context(codeloc!())?;
+        self.selected_program_bank = reader.get_usize().context(codeloc!())?;
+        return Ok(());
+    }
+}
@@ -0,0 +1,229 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::{address, byte};
+use crate::system::apu::Synthesizer;
+use crate::system::cpu::program_ram::{PROGRAM_RAM_START_ADDRESS, ProgramRAM};
+use crate::system::cpu::program_rom::PROGRAM_ROM_START_ADDRESS;
+use crate::system::mappers::{Cartridge, Mapper};
+use crate::system::mappers::sunsoft5b_audio::Sunsoft5BAudio;
+use crate::system::ppu::mirroring::Mirroring;
+use crate::system::rom::ParsedROM;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
+
+const COMMAND_REGISTER_START_ADDRESS : address = 0x8000;
+const PARAMETER_REGISTER_START_ADDRESS : address = 0xA000;
+const AUDIO_REGISTER_SELECT_START_ADDRESS : address = 0xC000;
+const AUDIO_REGISTER_DATA_START_ADDRESS : address = 0xE000;
+
+const PROGRAM_BANK_SIZE : usize = 8*1024;
+const NUMBER_OF_PROGRAM_BANKS : usize = 3;
+const CHARACTER_BANK_SIZE : usize = 1*1024;
+const NUMBER_OF_CHARACTER_BANKS : usize = 8;
+
+//https://www.nesdev.org/wiki/Sunsoft_FME-7
+//the 5B variant (Gimmick!) is the same mapper with an extra sound chip
+pub struct FME7
+{
+    cartridge : Cartridge,
+    command : byte,
+    program_banks : [usize; NUMBER_OF_PROGRAM_BANKS],
+    program_ram_window_bank : usize,
+    is_program_ram_selected : bool,
+    is_program_ram_enabled : bool,
+    character_banks : [usize; NUMBER_OF_CHARACTER_BANKS],
+    is_irq_enabled : bool,
+    is_irq_counter_enabled : bool,
+    irq_counter : u16,
+    is_irq_pending : bool,
+    audio : Sunsoft5BAudio,
+}
+
+impl FME7
+{
+    pub fn build(parsed_rom : ParsedROM) -> Box<dyn Mapper>
+    {
+        return Box::new(FME7
+        {
+            cartridge: Cartridge::new(parsed_rom),
+            command: 0,
+            program_banks: [0, 1, 2],
+            program_ram_window_bank: 0,
+            is_program_ram_selected: false,
+            is_program_ram_enabled: false,
+            character_banks: [0; NUMBER_OF_CHARACTER_BANKS],
+            is_irq_enabled: false,
+            is_irq_counter_enabled: false,
+            irq_counter: 0,
+            is_irq_pending: false,
+            audio: Sunsoft5BAudio::new(),
+        });
+    }
+
+    fn write_parameter(&mut self, value : byte)
+    {
+        match self.command
+        {
+            0x0..=0x7 =>
+            {
+                self.character_banks[self.command as usize] = value as usize;
+            }
+            0x8 =>
+            {
+                //$6000-$7FFF shows either a PRG-ROM bank or the PRG-RAM
+                self.program_ram_window_bank = (value & 0b00111111) as usize;
+                self.is_program_ram_selected = value & 0b01000000 != 0;
+                self.is_program_ram_enabled = value & 0b10000000 != 0;
+            }
+            0x9..=0xB => { self.program_banks[(self.command - 0x9) as usize] = (value & 0b00111111) as usize; }
+            0xC =>
+            {
+                self.cartridge.mirroring = match value & 0b00000011
+                {
+                    0 => Mirroring::Vertical,
+                    1 => Mirroring::Horizontal,
+                    2 => Mirroring::SingleScreenLower,
+                    _ => Mirroring::SingleScreenUpper,
+                };
+            }
+            0xD =>
+            {
+                self.is_irq_enabled = value & 0b00000001 != 0;
+                self.is_irq_counter_enabled = value & 0b10000000 != 0;
+                self.is_irq_pending = false;
+            }
+            0xE => { self.irq_counter = (self.irq_counter & 0xFF00) | (value as u16); }
+            _ => { self.irq_counter = (self.irq_counter & 0x00FF) | ((value as u16) << 8); }
+        }
+    }
+}
+
+impl Mapper for FME7
+{
+    fn cpu_read(&mut self, raw_address : address) -> byte
+    {
+        if raw_address >= PROGRAM_ROM_START_ADDRESS
+        {
+            let relative_address = (raw_address - PROGRAM_ROM_START_ADDRESS) as usize;
+            let window_index = relative_address / PROGRAM_BANK_SIZE;
+            let bank_index = if window_index < NUMBER_OF_PROGRAM_BANKS { self.program_banks[window_index] }
+                else { self.cartridge.program_rom.number_of_banks(PROGRAM_BANK_SIZE) - 1 };
+            return self.cartridge.program_rom.get_from_bank(bank_index, PROGRAM_BANK_SIZE, relative_address);
+        }
+
+        if Cartridge::is_program_ram_address(raw_address)
+        {
+            let relative_address = (raw_address - PROGRAM_RAM_START_ADDRESS) as usize;
+            return if !self.is_program_ram_selected { self.cartridge.program_rom.get_from_bank(self.program_ram_window_bank, PROGRAM_BANK_SIZE, relative_address) }
+            else if self.is_program_ram_enabled { self.cartridge.program_ram.get(raw_address) }
+            else { 0 };
+        }
+
+        return 0;
+    }
+
+    fn cpu_write(&mut self, raw_address : address, value : byte)
+    {
+        if raw_address >= AUDIO_REGISTER_DATA_START_ADDRESS
+        {
+            self.audio.write_to_selected_register(value);
+        }
+        else if raw_address >= AUDIO_REGISTER_SELECT_START_ADDRESS
+        {
+            self.audio.select_register(value);
+        }
+        else if raw_address >= PARAMETER_REGISTER_START_ADDRESS
+        {
+            self.write_parameter(value);
+        }
+        else if raw_address >= COMMAND_REGISTER_START_ADDRESS
+        {
+            self.command = value & 0x0F;
+        }
+        else if Cartridge::is_program_ram_address(raw_address)
+            && self.is_program_ram_selected && self.is_program_ram_enabled
+        {
+            self.cartridge.program_ram.put(raw_address, value);
+        }
+    }
+
+    fn ppu_read(&self, raw_address : address) -> byte
+    {
+        let bank_index = self.character_banks[((raw_address as usize) / CHARACTER_BANK_SIZE) % NUMBER_OF_CHARACTER_BANKS];
+        return self.cartridge.character_rom.get_from_bank(bank_index, CHARACTER_BANK_SIZE, raw_address as usize);
+    }
+
+    fn ppu_write(&mut self, raw_address : address, value : byte)
+    {
+        let bank_index = self.character_banks[((raw_address as usize) / CHARACTER_BANK_SIZE) % NUMBER_OF_CHARACTER_BANKS];
+        self.cartridge.character_rom.put_to_bank(bank_index, CHARACTER_BANK_SIZE, raw_address as usize, value);
+    }
+
+    fn mirroring(&self) -> Mirroring
+    {
+        return self.cartridge.mirroring;
+    }
+
+    fn program_ram(&mut self) -> &mut ProgramRAM
+    {
+        return &mut self.cartridge.program_ram;
+    }
+
+    fn notify_cpu_cycles(&mut self, number_of_cycles : u64)
+    {
+        if !self.is_irq_counter_enabled { return }
+
+        //16 bit down counter decremented every CPU cycle, the IRQ fires when it wraps from $0000 to $FFFF
+        let (irq_counter, did_wrap) = self.irq_counter.overflowing_sub(number_of_cycles.min(0xFFFF) as u16);
+        self.irq_counter = irq_counter;
+        if did_wrap && self.is_irq_enabled
+        {
+            self.is_irq_pending = true;
+        }
+    }
+
+    fn is_irq_pending(&self) -> bool
+    {
+        return self.is_irq_pending;
+    }
+
+    fn expansion_audio_channels(&self) -> Vec<&dyn Synthesizer>
+    {
+        return vec![&self.audio];
+    }
+}
+
+impl Snapshot for FME7
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        self.cartridge.save_snapshot(writer);
+        writer.put_byte(self.command);
+        writer.put_usizes(&self.program_banks);
+        writer.put_usize(self.program_ram_window_bank);
+        writer.put_bool(self.is_program_ram_selected);
+        writer.put_bool(self.is_program_ram_enabled);
+        writer.put_usizes(&self.character_banks);
+        writer.put_bool(self.is_irq_enabled);
+        writer.put_bool(self.is_irq_counter_enabled);
+        writer.put_u16(self.irq_counter);
+        writer.put_bool(self.is_irq_pending);
+        self.audio.save_snapshot(writer);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.cartridge.load_snapshot(reader).context(codeloc!())?;
+        self.command = reader.get_byte().context(codeloc!())?;
+        reader.get_usizes_into(&mut self.program_banks).context(codeloc!())?;
+        self.program_ram_window_bank = reader.get_usize().context(codeloc!())?;
+        self.is_program_ram_selected = reader.get_bool().context(codeloc!())?;
+        self.is_program_ram_enabled = reader.get_bool().context(codeloc!())?;
+        reader.get_usizes_into(&mut self.character_banks).context(codeloc!())?;
+        self.is_irq_enabled = reader.get_bool().context(codeloc!())?;
+        self.is_irq_counter_enabled = reader.get_bool().context(codeloc!())?;
+        self.irq_counter = reader.get_u16().context(codeloc!())?;
+        self.is_irq_pending = reader.get_bool().context(codeloc!())?;
+        self.audio.load_snapshot(reader).context(codeloc!())?;
+        return Ok(());
+    }
+}
@@ -0,0 +1,96 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::{address, byte};
+use crate::system::cpu::program_ram::ProgramRAM;
+use crate::system::cpu::program_rom::PROGRAM_ROM_START_ADDRESS;
+use crate::system::mappers::{Cartridge, Mapper};
+use crate::system::ppu::mirroring::Mirroring;
+use crate::system::rom::ParsedROM;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
+
+const PROGRAM_BANK_SIZE : usize = 32*1024;
+const CHARACTER_BANK_SIZE : usize = 8*1024;
+
+//https://www.nesdev.org/wiki/GxROM
+pub struct GxROM
+{
+    cartridge : Cartridge,
+    selected_program_bank : usize,
+    selected_character_bank : usize,
+}
+
+impl GxROM
+{
+    pub fn build(parsed_rom : ParsedROM) -> Box<dyn Mapper>
+    {
+        return Box::new(GxROM
+        {
+            cartridge: Cartridge::new(parsed_rom),
+            selected_program_bank: 0,
+            selected_character_bank: 0,
+        });
+    }
+}
+
+impl Mapper for GxROM
+{
+    fn cpu_read(&mut self, raw_address : address) -> byte
+    {
+        return if raw_address >= PROGRAM_ROM_START_ADDRESS
+            { self.cartridge.program_rom.get_from_bank(self.selected_program_bank, PROGRAM_BANK_SIZE, (raw_address - PROGRAM_ROM_START_ADDRESS) as usize) }
+        else if Cartridge::is_program_ram_address(raw_address) { self.cartridge.program_ram.get(raw_address) }
+        else { 0 };
+    }
+
+    fn cpu_write(&mut self, raw_address : address, value : byte)
+    {
+        if raw_address >= PROGRAM_ROM_START_ADDRESS
+        {
+            //xxPPxxCC: P selects the 32kB PRG bank, C selects the 8kB CHR bank
+            self.selected_program_bank = ((value & 0b00110000) >> 4) as usize;
+            self.selected_character_bank = (value & 0b00000011) as usize;
+        }
+        else if Cartridge::is_program_ram_address(raw_address)
+        {
+            self.cartridge.program_ram.put(raw_address, value);
+        }
+    }
+
+    fn ppu_read(&self, raw_address : address) -> byte
+    {
+        return self.cartridge.character_rom.get_from_bank(self.selected_character_bank, CHARACTER_BANK_SIZE, raw_address as usize);
+    }
+
+    fn ppu_write(&mut self, raw_address : address, value : byte)
+    {
+        self.cartridge.character_rom.put_to_bank(self.selected_character_bank, CHARACTER_BANK_SIZE, raw_address as usize, value);
+    }
+
+    fn mirroring(&self) -> Mirroring
+    {
+        return self.cartridge.mirroring;
+    }
+
+    fn program_ram(&mut self) -> &mut ProgramRAM
+    {
+        return &mut self.cartridge.program_ram;
+    }
+}
+
+impl Snapshot for GxROM
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        self.cartridge.save_snapshot(writer);
+        writer.put_usize(self.selected_program_bank);
+        writer.put_usize(self.selected_character_bank);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.cartridge.load_snapshot(reader).context(codeloc!())?;
+        self.selected_program_bank = reader.get_usize().context(codeloc!())?;
+        self.selected_character_bank = reader.get_usize().context(codeloc!())?;
+        return Ok(());
+    }
+}
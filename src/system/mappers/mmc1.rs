@@ -0,0 +1,204 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::{address, byte};
+use crate::system::cpu::program_ram::ProgramRAM;
+use crate::system::cpu::program_rom::PROGRAM_ROM_START_ADDRESS;
+use crate::system::mappers::{Cartridge, Mapper};
+use crate::system::ppu::mirroring::Mirroring;
+use crate::system::rom::ParsedROM;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
+
+const CONTROL_REGISTER_START_ADDRESS : address = 0x8000;
+const CHARACTER_BANK0_REGISTER_START_ADDRESS : address = 0xA000;
+const CHARACTER_BANK1_REGISTER_START_ADDRESS : address = 0xC000;
+const PROGRAM_BANK_REGISTER_START_ADDRESS : address = 0xE000;
+
+const PROGRAM_BANK_SIZE : usize = 16*1024;
+const CHARACTER_BANK_SIZE : usize = 4*1024;
+const NUMBER_OF_PROGRAM_BANKS_IN_OUTER_BANK : usize = 16; //256kB, SUROM and SXROM boards have two of them
+
+const SHIFT_REGISTER_INITIAL_VALUE : byte = 0b00010000;
+
+//https://www.nesdev.org/wiki/MMC1
+pub struct MMC1
+{
+    cartridge : Cartridge,
+    shift_register : byte,
+    control_register : byte,
+    character_bank0_register : byte,
+    character_bank1_register : byte,
+    program_bank_register : byte,
+}
+
+impl MMC1
+{
+    pub fn build(parsed_rom : ParsedROM) -> Box<dyn Mapper>
+    {
+        return Box::new(MMC1
+        {
+            cartridge: Cartridge::new(parsed_rom),
+            shift_register: SHIFT_REGISTER_INITIAL_VALUE,
+            control_register: 0b01100, //power on with the last PRG bank fixed at $C000
+            character_bank0_register: 0,
+            character_bank1_register: 0,
+            program_bank_register: 0,
+        });
+    }
+
+    fn write_to_shift_register(&mut self, raw_address : address, value : byte)
+    {
+        if value & 0b10000000 != 0
+        {
+            self.shift_register = SHIFT_REGISTER_INITIAL_VALUE;
+            self.control_register |= 0b01100;
+            return;
+        }
+
+        //the register is loaded serially, LSB first, one bit per write. the fifth write commits it
+        let is_shift_register_full = self.shift_register & 0b00000001 != 0;
+        self.shift_register = (self.shift_register >> 1) | ((value & 0b00000001) << 4);
+
+        if is_shift_register_full
+        {
+            let register_value = self.shift_register;
+            self.shift_register = SHIFT_REGISTER_INITIAL_VALUE;
+
+            if raw_address >= PROGRAM_BANK_REGISTER_START_ADDRESS
+            {
+                self.program_bank_register = register_value;
+            }
+            else if raw_address >= CHARACTER_BANK1_REGISTER_START_ADDRESS
+            {
+                self.character_bank1_register = register_value;
+            }
+            else if raw_address >= CHARACTER_BANK0_REGISTER_START_ADDRESS
+            {
+                self.character_bank0_register = register_value;
+            }
+            else if raw_address >= CONTROL_REGISTER_START_ADDRESS
+            {
+                self.control_register = register_value;
+            }
+        }
+    }
+
+    fn is_program_ram_enabled(&self) -> bool
+    {
+        return self.program_bank_register & 0b00010000 == 0;
+    }
+
+    fn get_program_bank_index(&self, relative_address : usize) -> usize
+    {
+        let number_of_banks = self.cartridge.program_rom.number_of_banks(PROGRAM_BANK_SIZE);
+        let selected_bank = (self.program_bank_register & 0b00001111) as usize;
+
+        //on 512kB boards, bit 4 of the CHR bank register selects the 256kB outer PRG bank
+        let outer_bank = if number_of_banks > NUMBER_OF_PROGRAM_BANKS_IN_OUTER_BANK
+            { ((self.character_bank0_register & 0b00010000) >> 4) as usize * NUMBER_OF_PROGRAM_BANKS_IN_OUTER_BANK }
+        else { 0 };
+        let last_bank_in_outer_bank = (number_of_banks.min(NUMBER_OF_PROGRAM_BANKS_IN_OUTER_BANK)) - 1;
+
+        let is_upper_window = relative_address >= PROGRAM_BANK_SIZE;
+        let bank_index = match (self.control_register & 0b01100) >> 2
+        {
+            0 | 1 => (selected_bank & 0b11110) + if is_upper_window { 1 } else { 0 }, //switch 32kB at $8000
+            2 => if is_upper_window { selected_bank } else { 0 }, //fix first bank at $8000, switch 16kB at $C000
+            _ => if is_upper_window { last_bank_in_outer_bank } else { selected_bank }, //fix last bank at $C000, switch 16kB at $8000
+        };
+
+        return outer_bank + bank_index;
+    }
+
+    fn get_character_bank_index(&self, raw_address : address) -> usize
+    {
+        let is_upper_window = (raw_address as usize) >= CHARACTER_BANK_SIZE;
+        return if self.control_register & 0b10000 == 0
+        {
+            //switch 8kB at a time, the low bit of the bank number is ignored
+            ((self.character_bank0_register & 0b11110) as usize) + if is_upper_window { 1 } else { 0 }
+        }
+        else if is_upper_window { self.character_bank1_register as usize }
+        else { self.character_bank0_register as usize };
+    }
+}
+
+impl Mapper for MMC1
+{
+    fn cpu_read(&mut self, raw_address : address) -> byte
+    {
+        if raw_address >= PROGRAM_ROM_START_ADDRESS
+        {
+            let relative_address = (raw_address - PROGRAM_ROM_START_ADDRESS) as usize;
+            let bank_index = self.get_program_bank_index(relative_address);
+            return self.cartridge.program_rom.get_from_bank(bank_index, PROGRAM_BANK_SIZE, relative_address);
+        }
+
+        return if Cartridge::is_program_ram_address(raw_address) && self.is_program_ram_enabled()
+            { self.cartridge.program_ram.get(raw_address) }
+        else { 0 };
+    }
+
+    fn cpu_write(&mut self, raw_address : address, value : byte)
+    {
+        if raw_address >= PROGRAM_ROM_START_ADDRESS
+        {
+            self.write_to_shift_register(raw_address, value);
+        }
+        else if Cartridge::is_program_ram_address(raw_address) && self.is_program_ram_enabled()
+        {
+            self.cartridge.program_ram.put(raw_address, value);
+        }
+    }
+
+    fn ppu_read(&self, raw_address : address) -> byte
+    {
+        let bank_index = self.get_character_bank_index(raw_address);
+        return self.cartridge.character_rom.get_from_bank(bank_index, CHARACTER_BANK_SIZE, raw_address as usize);
+    }
+
+    fn ppu_write(&mut self, raw_address : address, value : byte)
+    {
+        let bank_index = self.get_character_bank_index(raw_address);
+        self.cartridge.character_rom.put_to_bank(bank_index, CHARACTER_BANK_SIZE, raw_address as usize, value);
+    }
+
+    fn mirroring(&self) -> Mirroring
+    {
+        return match self.control_register & 0b00011
+        {
+            0 => Mirroring::SingleScreenLower,
+            1 => Mirroring::SingleScreenUpper,
+            2 => Mirroring::Vertical,
+            _ => Mirroring::Horizontal,
+        };
+    }
+
+    fn program_ram(&mut self) -> &mut ProgramRAM
+    {
+        return &mut self.cartridge.program_ram;
+    }
+}
+
+impl Snapshot for MMC1
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        self.cartridge.save_snapshot(writer);
+        writer.put_byte(self.shift_register);
+        writer.put_byte(self.control_register);
+        writer.put_byte(self.character_bank0_register);
+        writer.put_byte(self.character_bank1_register);
+        writer.put_byte(self.program_bank_register);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.cartridge.load_snapshot(reader).context(codeloc!())?;
+        self.shift_register = reader.get_byte().context(codeloc!())?;
+        self.control_register = reader.get_byte().context(codeloc!())?;
+        self.character_bank0_register = reader.get_byte().context(codeloc!())?;
+        self.character_bank1_register = reader.get_byte().context(codeloc!())?;
+        self.program_bank_register = reader.get_byte().context(codeloc!())?;
+        return Ok(());
+    }
+}
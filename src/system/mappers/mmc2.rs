@@ -0,0 +1,195 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::{address, byte};
+use crate::system::cpu::program_ram::ProgramRAM;
+use crate::system::cpu::program_rom::PROGRAM_ROM_START_ADDRESS;
+use crate::system::mappers::{Cartridge, Mapper};
+use crate::system::ppu::mirroring::Mirroring;
+use crate::system::rom::ParsedROM;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
+
+const PROGRAM_BANK_REGISTER_START_ADDRESS : address = 0xA000;
+const CHARACTER_BANK0_FD_REGISTER_START_ADDRESS : address = 0xB000;
+const CHARACTER_BANK0_FE_REGISTER_START_ADDRESS : address = 0xC000;
+const CHARACTER_BANK1_FD_REGISTER_START_ADDRESS : address = 0xD000;
+const CHARACTER_BANK1_FE_REGISTER_START_ADDRESS : address = 0xE000;
+const MIRRORING_REGISTER_START_ADDRESS : address = 0xF000;
+
+const CHARACTER_BANK_SIZE : usize = 4*1024;
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Latch { FD, FE }
+
+//https://www.nesdev.org/wiki/MMC2
+//https://www.nesdev.org/wiki/MMC4
+//the two chips differ only in PRG bank size and in how precisely the $0FD8/$0FE8 latch triggers are decoded
+pub struct MMC2
+{
+    cartridge : Cartridge,
+    is_mmc4 : bool,
+    selected_program_bank : usize,
+    character_banks : [[usize; 2]; 2], //[pattern table][latch]
+    latches : [Latch; 2],
+}
+
+impl MMC2
+{
+    pub fn build(parsed_rom : ParsedROM) -> Box<dyn Mapper>
+    {
+        return Box::new(MMC2::new_with_variant(parsed_rom, false));
+    }
+
+    pub fn build_mmc4(parsed_rom : ParsedROM) -> Box<dyn Mapper>
+    {
+        return Box::new(MMC2::new_with_variant(parsed_rom, true));
+    }
+
+    fn new_with_variant(parsed_rom : ParsedROM, is_mmc4 : bool) -> MMC2
+    {
+        return MMC2
+        {
+            cartridge: Cartridge::new(parsed_rom),
+            is_mmc4: is_mmc4,
+            selected_program_bank: 0,
+            character_banks: [[0; 2]; 2],
+            latches: [Latch::FE, Latch::FE],
+        };
+    }
+
+    fn program_bank_size(&self) -> usize
+    {
+        return if self.is_mmc4 { 16*1024 } else { 8*1024 };
+    }
+
+    fn get_program_bank_index(&self, relative_address : usize) -> usize
+    {
+        //the first window is switchable, the rest of $8000-$FFFF is fixed to the last banks
+        let bank_size = self.program_bank_size();
+        let window_index = relative_address / bank_size;
+        let number_of_windows = (32*1024) / bank_size;
+        let number_of_banks = self.cartridge.program_rom.number_of_banks(bank_size);
+
+        return if window_index == 0 { self.selected_program_bank }
+        else { number_of_banks.saturating_sub(number_of_windows - window_index) };
+    }
+
+    fn get_character_bank_index(&self, raw_address : address) -> usize
+    {
+        let pattern_table_index = ((raw_address as usize) / CHARACTER_BANK_SIZE) % 2;
+        let latch_index = if self.latches[pattern_table_index] == Latch::FD { 0 } else { 1 };
+        return self.character_banks[pattern_table_index][latch_index];
+    }
+
+    fn set_latch(&mut self, pattern_table_index : usize, latch : Latch)
+    {
+        self.latches[pattern_table_index] = latch;
+    }
+}
+
+impl Mapper for MMC2
+{
+    fn cpu_read(&mut self, raw_address : address) -> byte
+    {
+        if raw_address >= PROGRAM_ROM_START_ADDRESS
+        {
+            let relative_address = (raw_address - PROGRAM_ROM_START_ADDRESS) as usize;
+            let bank_index = self.get_program_bank_index(relative_address);
+            return self.cartridge.program_rom.get_from_bank(bank_index, self.program_bank_size(), relative_address);
+        }
+
+        return if Cartridge::is_program_ram_address(raw_address) { self.cartridge.program_ram.get(raw_address) }
+        else { 0 };
+    }
+
+    fn cpu_write(&mut self, raw_address : address, value : byte)
+    {
+        if raw_address >= MIRRORING_REGISTER_START_ADDRESS
+        {
+            self.cartridge.mirroring = if value & 0b00000001 == 0 { Mirroring::Vertical } else { Mirroring::Horizontal };
+        }
+        else if raw_address >= CHARACTER_BANK1_FE_REGISTER_START_ADDRESS
+        {
+            self.character_banks[1][1] = (value & 0b00011111) as usize;
+        }
+        else if raw_address >= CHARACTER_BANK1_FD_REGISTER_START_ADDRESS
+        {
+            self.character_banks[1][0] = (value & 0b00011111) as usize;
+        }
+        else if raw_address >= CHARACTER_BANK0_FE_REGISTER_START_ADDRESS
+        {
+            self.character_banks[0][1] = (value & 0b00011111) as usize;
+        }
+        else if raw_address >= CHARACTER_BANK0_FD_REGISTER_START_ADDRESS
+        {
+            self.character_banks[0][0] = (value & 0b00011111) as usize;
+        }
+        else if raw_address >= PROGRAM_BANK_REGISTER_START_ADDRESS
+        {
+            self.selected_program_bank = (value & 0b00001111) as usize;
+        }
+        else if Cartridge::is_program_ram_address(raw_address)
+        {
+            self.cartridge.program_ram.put(raw_address, value);
+        }
+    }
+
+    fn ppu_read(&self, raw_address : address) -> byte
+    {
+        let bank_index = self.get_character_bank_index(raw_address);
+        return self.cartridge.character_rom.get_from_bank(bank_index, CHARACTER_BANK_SIZE, raw_address as usize);
+    }
+
+    fn ppu_write(&mut self, raw_address : address, value : byte)
+    {
+        let bank_index = self.get_character_bank_index(raw_address);
+        self.cartridge.character_rom.put_to_bank(bank_index, CHARACTER_BANK_SIZE, raw_address as usize, value);
+    }
+
+    fn mirroring(&self) -> Mirroring
+    {
+        return self.cartridge.mirroring;
+    }
+
+    fn program_ram(&mut self) -> &mut ProgramRAM
+    {
+        return &mut self.cartridge.program_ram;
+    }
+
+    fn notify_ppu_address_fetch(&mut self, raw_address : address)
+    {
+        //the latch flips after the fetch, so tile $FD/$FE itself is still drawn from the previous bank.
+        //MMC2 decodes only $0FD8 and $0FE8 in the left pattern table, MMC4 decodes the whole tile row range
+        match raw_address
+        {
+            0x0FD8 => self.set_latch(0, Latch::FD),
+            0x0FE8 => self.set_latch(0, Latch::FE),
+            0x0FD9..=0x0FDF if self.is_mmc4 => self.set_latch(0, Latch::FD),
+            0x0FE9..=0x0FEF if self.is_mmc4 => self.set_latch(0, Latch::FE),
+            0x1FD8..=0x1FDF => self.set_latch(1, Latch::FD),
+            0x1FE8..=0x1FEF => self.set_latch(1, Latch::FE),
+            _ => {}
+        }
+    }
+}
+
+impl Snapshot for MMC2
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        self.cartridge.save_snapshot(writer);
+        writer.put_usize(self.selected_program_bank);
+        writer.put_usizes(&self.character_banks[0]);
+        writer.put_usizes(&self.character_banks[1]);
+        for latch in &self.latches { writer.put_bool(*latch == Latch::FD); }
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.cartridge.load_snapshot(reader).context(codeloc!())?;
+        self.selected_program_bank = reader.get_usize().context(codeloc!())?;
+        reader.get_usizes_into(&mut self.character_banks[0]).context(codeloc!())?;
+        reader.get_usizes_into(&mut self.character_banks[1]).context(codeloc!())?;
+        for latch in self.latches.iter_mut() { *latch = if reader.get_bool().context(codeloc!())? { Latch::FD } else { Latch::FE }; }
+        return Ok(());
+    }
+}
@@ -0,0 +1,265 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::{address, byte};
+use crate::system::cpu::program_ram::ProgramRAM;
+use crate::system::cpu::program_rom::PROGRAM_ROM_START_ADDRESS;
+use crate::system::mappers::{Cartridge, Mapper};
+use crate::system::ppu::mirroring::Mirroring;
+use crate::system::rom::ParsedROM;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
+
+const BANK_SELECT_REGISTER_START_ADDRESS : address = 0x8000;
+const MIRRORING_REGISTER_START_ADDRESS : address = 0xA000;
+const IRQ_LATCH_REGISTER_START_ADDRESS : address = 0xC000;
+const IRQ_DISABLE_REGISTER_START_ADDRESS : address = 0xE000;
+
+const PROGRAM_BANK_SIZE : usize = 8*1024;
+const CHARACTER_BANK_SIZE : usize = 1*1024;
+const NUMBER_OF_BANK_REGISTERS : usize = 8;
+
+const PPU_A12_MASK : address = 0x1000;
+const MINIMUM_NUMBER_OF_A12_LOW_FETCHES_BEFORE_RISE : usize = 3; //M2 based filter of the real chip
+
+//https://www.nesdev.org/wiki/MMC3
+pub struct MMC3
+{
+    cartridge : Cartridge,
+    bank_select_register : byte,
+    bank_registers : [byte; NUMBER_OF_BANK_REGISTERS],
+    is_program_ram_enabled : bool,
+    is_program_ram_write_protected : bool,
+    irq_latch : byte,
+    irq_counter : byte,
+    should_reload_irq_counter : bool,
+    is_irq_enabled : bool,
+    is_irq_pending : bool,
+    number_of_consecutive_a12_low_fetches : usize,
+}
+
+impl MMC3
+{
+    pub fn build(parsed_rom : ParsedROM) -> Box<dyn Mapper>
+    {
+        return Box::new(MMC3
+        {
+            cartridge: Cartridge::new(parsed_rom),
+            bank_select_register: 0,
+            bank_registers: [0, 2, 4, 5, 6, 7, 0, 1],
+            is_program_ram_enabled: true,
+            is_program_ram_write_protected: false,
+            irq_latch: 0,
+            irq_counter: 0,
+            should_reload_irq_counter: false,
+            is_irq_enabled: false,
+            is_irq_pending: false,
+            number_of_consecutive_a12_low_fetches: 0,
+        });
+    }
+
+    fn get_program_bank_index(&self, relative_address : usize) -> usize
+    {
+        let second_last_bank = self.cartridge.program_rom.number_of_banks(PROGRAM_BANK_SIZE).saturating_sub(2);
+        let last_bank = self.cartridge.program_rom.number_of_banks(PROGRAM_BANK_SIZE) - 1;
+        let r6 = (self.bank_registers[6] & 0b00111111) as usize;
+        let r7 = (self.bank_registers[7] & 0b00111111) as usize;
+
+        let is_program_rom_bank_mode_swapped = self.bank_select_register & 0b01000000 != 0;
+        return match (relative_address / PROGRAM_BANK_SIZE, is_program_rom_bank_mode_swapped)
+        {
+            (0, false) => r6,               //$8000-$9FFF
+            (0, true) => second_last_bank,
+            (1, _) => r7,                   //$A000-$BFFF
+            (2, false) => second_last_bank, //$C000-$DFFF
+            (2, true) => r6,
+            _ => last_bank,                 //$E000-$FFFF
+        };
+    }
+
+    fn get_character_bank_index(&self, raw_address : address) -> usize
+    {
+        //two 2kB banks and four 1kB banks, A12 inversion swaps $0000-$0FFF with $1000-$1FFF
+        let is_character_a12_inverted = self.bank_select_register & 0b10000000 != 0;
+        let raw_address = if is_character_a12_inverted { raw_address ^ PPU_A12_MASK } else { raw_address };
+
+        let window_index = (raw_address as usize) / CHARACTER_BANK_SIZE;
+        return match window_index
+        {
+            0 => (self.bank_registers[0] & 0b11111110) as usize,
+            1 => (self.bank_registers[0] | 0b00000001) as usize,
+            2 => (self.bank_registers[1] & 0b11111110) as usize,
+            3 => (self.bank_registers[1] | 0b00000001) as usize,
+            _ => self.bank_registers[window_index - 2] as usize,
+        };
+    }
+
+    fn write_to_register(&mut self, raw_address : address, value : byte)
+    {
+        let is_even = raw_address & 0b00000001 == 0;
+        if raw_address >= IRQ_DISABLE_REGISTER_START_ADDRESS
+        {
+            self.is_irq_enabled = !is_even;
+            if is_even { self.is_irq_pending = false; } //disabling also acknowledges a pending IRQ
+        }
+        else if raw_address >= IRQ_LATCH_REGISTER_START_ADDRESS
+        {
+            if is_even { self.irq_latch = value; }
+            else
+            {
+                self.irq_counter = 0;
+                self.should_reload_irq_counter = true;
+            }
+        }
+        else if raw_address >= MIRRORING_REGISTER_START_ADDRESS
+        {
+            if is_even
+            {
+                if self.cartridge.mirroring != Mirroring::FourScreen
+                {
+                    self.cartridge.mirroring = if value & 0b00000001 == 0 { Mirroring::Vertical } else { Mirroring::Horizontal };
+                }
+            }
+            else
+            {
+                self.is_program_ram_enabled = value & 0b10000000 != 0;
+                self.is_program_ram_write_protected = value & 0b01000000 != 0;
+            }
+        }
+        else if raw_address >= BANK_SELECT_REGISTER_START_ADDRESS
+        {
+            if is_even { self.bank_select_register = value; }
+            else
+            {
+                let register_index = (self.bank_select_register & 0b00000111) as usize;
+                self.bank_registers[register_index] = value;
+            }
+
+        }
+    }
+
+    fn clock_irq_counter(&mut self)
+    {
+        if self.irq_counter == 0 || self.should_reload_irq_counter
+        {
+            self.irq_counter = self.irq_latch;
+            self.should_reload_irq_counter = false;
+        }
+        else
+        {
+            self.irq_counter -= 1;
+        }
+
+        if self.irq_counter == 0 && self.is_irq_enabled
+        {
+            self.is_irq_pending = true;
+        }
+    }
+}
+
+impl Mapper for MMC3
+{
+    fn cpu_read(&mut self, raw_address : address) -> byte
+    {
+        if raw_address >= PROGRAM_ROM_START_ADDRESS
+        {
+            let relative_address = (raw_address - PROGRAM_ROM_START_ADDRESS) as usize;
+            let bank_index = self.get_program_bank_index(relative_address);
+            return self.cartridge.program_rom.get_from_bank(bank_index, PROGRAM_BANK_SIZE, relative_address);
+        }
+
+        return if Cartridge::is_program_ram_address(raw_address) && self.is_program_ram_enabled
+            { self.cartridge.program_ram.get(raw_address) }
+        else { 0 };
+    }
+
+    fn cpu_write(&mut self, raw_address : address, value : byte)
+    {
+        if raw_address >= PROGRAM_ROM_START_ADDRESS
+        {
+            self.write_to_register(raw_address, value);
+        }
+        else if Cartridge::is_program_ram_address(raw_address)
+            && self.is_program_ram_enabled && !self.is_program_ram_write_protected
+        {
+            self.cartridge.program_ram.put(raw_address, value);
+        }
+    }
+
+    fn ppu_read(&self, raw_address : address) -> byte
+    {
+        let bank_index = self.get_character_bank_index(raw_address);
+        return self.cartridge.character_rom.get_from_bank(bank_index, CHARACTER_BANK_SIZE, raw_address as usize);
+    }
+
+    fn ppu_write(&mut self, raw_address : address, value : byte)
+    {
+        let bank_index = self.get_character_bank_index(raw_address);
+        self.cartridge.character_rom.put_to_bank(bank_index, CHARACTER_BANK_SIZE, raw_address as usize, value);
+    }
+
+    fn mirroring(&self) -> Mirroring
+    {
+        return self.cartridge.mirroring;
+    }
+
+    fn program_ram(&mut self) -> &mut ProgramRAM
+    {
+        return &mut self.cartridge.program_ram;
+    }
+
+    fn notify_ppu_address_fetch(&mut self, raw_address : address)
+    {
+        //the IRQ counter is clocked on rising edges of PPU A12, short low pulses are filtered out
+        if raw_address & PPU_A12_MASK == 0
+        {
+            self.number_of_consecutive_a12_low_fetches += 1;
+        }
+        else
+        {
+            if self.number_of_consecutive_a12_low_fetches >= MINIMUM_NUMBER_OF_A12_LOW_FETCHES_BEFORE_RISE
+            {
+                self.clock_irq_counter();
+            }
+
+            self.number_of_consecutive_a12_low_fetches = 0;
+        }
+    }
+
+    fn is_irq_pending(&self) -> bool
+    {
+        return self.is_irq_pending;
+    }
+}
+
+impl Snapshot for MMC3
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        self.cartridge.save_snapshot(writer);
+        writer.put_byte(self.bank_select_register);
+        writer.put_bytes(&self.bank_registers);
+        writer.put_bool(self.is_program_ram_enabled);
+        writer.put_bool(self.is_program_ram_write_protected);
+        writer.put_byte(self.irq_latch);
+        writer.put_byte(self.irq_counter);
+        writer.put_bool(self.should_reload_irq_counter);
+        writer.put_bool(self.is_irq_enabled);
+        writer.put_bool(self.is_irq_pending);
+        writer.put_usize(self.number_of_consecutive_a12_low_fetches);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.cartridge.load_snapshot(reader).context(codeloc!())?;
+        self.bank_select_register = reader.get_byte().context(codeloc!())?;
+        reader.get_bytes_into(&mut self.bank_registers).context(codeloc!())?;
+        self.is_program_ram_enabled = reader.get_bool().context(codeloc!())?;
+        self.is_program_ram_write_protected = reader.get_bool().context(codeloc!())?;
+        self.irq_latch = reader.get_byte().context(codeloc!())?;
+        self.irq_counter = reader.get_byte().context(codeloc!())?;
+        self.should_reload_irq_counter = reader.get_bool().context(codeloc!())?;
+        self.is_irq_enabled = reader.get_bool().context(codeloc!())?;
+        self.is_irq_pending = reader.get_bool().context(codeloc!())?;
+        self.number_of_consecutive_a12_low_fetches = reader.get_usize().context(codeloc!())?;
+        return Ok(());
+    }
+}
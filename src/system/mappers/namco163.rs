@@ -0,0 +1,223 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::{address, byte};
+use crate::system::apu::Synthesizer;
+use crate::system::cpu::program_ram::ProgramRAM;
+use crate::system::cpu::program_rom::PROGRAM_ROM_START_ADDRESS;
+use crate::system::mappers::{Cartridge, Mapper};
+use crate::system::mappers::namco163_audio::Namco163Audio;
+use crate::system::ppu::mirroring::Mirroring;
+use crate::system::rom::ParsedROM;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
+
+const AUDIO_DATA_PORT_START_ADDRESS : address = 0x4800;
+const AUDIO_DATA_PORT_END_ADDRESS : address = 0x4FFF;
+const IRQ_COUNTER_LOW_START_ADDRESS : address = 0x5000;
+const IRQ_COUNTER_LOW_END_ADDRESS : address = 0x57FF;
+const IRQ_COUNTER_HIGH_START_ADDRESS : address = 0x5800;
+const IRQ_COUNTER_HIGH_END_ADDRESS : address = 0x5FFF;
+const CHARACTER_BANK_REGISTERS_START_ADDRESS : address = 0x8000;
+const NAMETABLE_REGISTERS_START_ADDRESS : address = 0xC000;
+const PROGRAM_BANK_REGISTERS_START_ADDRESS : address = 0xE000;
+const AUDIO_ADDRESS_PORT_START_ADDRESS : address = 0xF800;
+const REGISTER_SIZE : address = 0x800;
+
+const PROGRAM_BANK_SIZE : usize = 8*1024;
+const NUMBER_OF_PROGRAM_BANKS : usize = 3;
+const CHARACTER_BANK_SIZE : usize = 1*1024;
+const NUMBER_OF_CHARACTER_BANKS : usize = 8;
+const NUMBER_OF_NAMETABLE_REGISTERS : usize = 4;
+const FIRST_CIRAM_BANK_NUMBER : byte = 0xE0;
+const MAXIMUM_IRQ_COUNTER_VALUE : u16 = 0x7FFF;
+
+//https://www.nesdev.org/wiki/INES_Mapper_019
+//nametables can only be mapped onto CIRAM, pointing them at CHR-ROM pages is not supported
+pub struct Namco163
+{
+    cartridge : Cartridge,
+    program_banks : [usize; NUMBER_OF_PROGRAM_BANKS],
+    character_banks : [usize; NUMBER_OF_CHARACTER_BANKS],
+    nametable_banks : [byte; NUMBER_OF_NAMETABLE_REGISTERS],
+    is_irq_enabled : bool,
+    irq_counter : u16,
+    is_irq_pending : bool,
+    audio : Namco163Audio,
+}
+
+impl Namco163
+{
+    pub fn build(parsed_rom : ParsedROM) -> Box<dyn Mapper>
+    {
+        return Box::new(Namco163
+        {
+            cartridge: Cartridge::new(parsed_rom),
+            program_banks: [0, 1, 2],
+            character_banks: [0; NUMBER_OF_CHARACTER_BANKS],
+            nametable_banks: [0; NUMBER_OF_NAMETABLE_REGISTERS],
+            is_irq_enabled: false,
+            irq_counter: 0,
+            is_irq_pending: false,
+            audio: Namco163Audio::new(),
+        });
+    }
+
+    fn write_to_register(&mut self, raw_address : address, value : byte)
+    {
+        if raw_address >= AUDIO_ADDRESS_PORT_START_ADDRESS
+        {
+            self.audio.set_ram_address(value);
+        }
+        else if raw_address >= PROGRAM_BANK_REGISTERS_START_ADDRESS
+        {
+            let register_index = ((raw_address - PROGRAM_BANK_REGISTERS_START_ADDRESS) / REGISTER_SIZE) as usize;
+            self.program_banks[register_index] = (value & 0b00111111) as usize;
+            if register_index == 0 { self.audio.is_muted = value & 0b01000000 != 0; }
+        }
+        else if raw_address >= NAMETABLE_REGISTERS_START_ADDRESS
+        {
+            let register_index = ((raw_address - NAMETABLE_REGISTERS_START_ADDRESS) / REGISTER_SIZE) as usize;
+            self.nametable_banks[register_index] = value;
+        }
+        else if raw_address >= CHARACTER_BANK_REGISTERS_START_ADDRESS
+        {
+            let register_index = ((raw_address - CHARACTER_BANK_REGISTERS_START_ADDRESS) / REGISTER_SIZE) as usize;
+            self.character_banks[register_index] = value as usize;
+        }
+    }
+}
+
+impl Mapper for Namco163
+{
+    fn cpu_read(&mut self, raw_address : address) -> byte
+    {
+        if raw_address >= PROGRAM_ROM_START_ADDRESS
+        {
+            let relative_address = (raw_address - PROGRAM_ROM_START_ADDRESS) as usize;
+            let window_index = relative_address / PROGRAM_BANK_SIZE;
+            let bank_index = if window_index < NUMBER_OF_PROGRAM_BANKS { self.program_banks[window_index] }
+                else { self.cartridge.program_rom.number_of_banks(PROGRAM_BANK_SIZE) - 1 };
+            return self.cartridge.program_rom.get_from_bank(bank_index, PROGRAM_BANK_SIZE, relative_address);
+        }
+
+        return if Cartridge::is_program_ram_address(raw_address) { self.cartridge.program_ram.get(raw_address) }
+        else if raw_address >= IRQ_COUNTER_HIGH_START_ADDRESS && raw_address <= IRQ_COUNTER_HIGH_END_ADDRESS
+            { ((self.is_irq_enabled as byte) << 7) | ((self.irq_counter >> 8) as byte) }
+        else if raw_address >= IRQ_COUNTER_LOW_START_ADDRESS && raw_address <= IRQ_COUNTER_LOW_END_ADDRESS { self.irq_counter as byte }
+        else if raw_address >= AUDIO_DATA_PORT_START_ADDRESS && raw_address <= AUDIO_DATA_PORT_END_ADDRESS { self.audio.read_ram() }
+        else { 0 };
+    }
+
+    fn cpu_write(&mut self, raw_address : address, value : byte)
+    {
+        if raw_address >= PROGRAM_ROM_START_ADDRESS
+        {
+            self.write_to_register(raw_address, value);
+        }
+        else if Cartridge::is_program_ram_address(raw_address)
+        {
+            self.cartridge.program_ram.put(raw_address, value);
+        }
+        else if raw_address >= IRQ_COUNTER_HIGH_START_ADDRESS && raw_address <= IRQ_COUNTER_HIGH_END_ADDRESS
+        {
+            self.is_irq_enabled = value & 0b10000000 != 0;
+            self.irq_counter = (self.irq_counter & 0x00FF) | (((value & 0b01111111) as u16) << 8);
+            self.is_irq_pending = false;
+        }
+        else if raw_address >= IRQ_COUNTER_LOW_START_ADDRESS && raw_address <= IRQ_COUNTER_LOW_END_ADDRESS
+        {
+            self.irq_counter = (self.irq_counter & 0xFF00) | (value as u16);
+            self.is_irq_pending = false;
+        }
+        else if raw_address >= AUDIO_DATA_PORT_START_ADDRESS && raw_address <= AUDIO_DATA_PORT_END_ADDRESS
+        {
+            self.audio.write_ram(value);
+        }
+    }
+
+    fn ppu_read(&self, raw_address : address) -> byte
+    {
+        let bank_index = self.character_banks[((raw_address as usize) / CHARACTER_BANK_SIZE) % NUMBER_OF_CHARACTER_BANKS];
+        return self.cartridge.character_rom.get_from_bank(bank_index, CHARACTER_BANK_SIZE, raw_address as usize);
+    }
+
+    fn ppu_write(&mut self, raw_address : address, value : byte)
+    {
+        let bank_index = self.character_banks[((raw_address as usize) / CHARACTER_BANK_SIZE) % NUMBER_OF_CHARACTER_BANKS];
+        self.cartridge.character_rom.put_to_bank(bank_index, CHARACTER_BANK_SIZE, raw_address as usize, value);
+    }
+
+    fn mirroring(&self) -> Mirroring
+    {
+        if self.nametable_banks.iter().any(|bank| *bank < FIRST_CIRAM_BANK_NUMBER)
+        {
+            return self.cartridge.mirroring;
+        }
+
+        let pages = self.nametable_banks.map(|bank| bank & 0b00000001);
+        return match pages
+        {
+            [0, 1, 0, 1] => Mirroring::Vertical,
+            [0, 0, 1, 1] => Mirroring::Horizontal,
+            [0, 0, 0, 0] => Mirroring::SingleScreenLower,
+            [1, 1, 1, 1] => Mirroring::SingleScreenUpper,
+            _ => self.cartridge.mirroring,
+        };
+    }
+
+    fn program_ram(&mut self) -> &mut ProgramRAM
+    {
+        return &mut self.cartridge.program_ram;
+    }
+
+    fn notify_cpu_cycles(&mut self, number_of_cycles : u64)
+    {
+        self.audio.notify_cpu_cycles(number_of_cycles);
+
+        //15 bit up counter, stops and raises the IRQ at $7FFF
+        if !self.is_irq_enabled || self.irq_counter >= MAXIMUM_IRQ_COUNTER_VALUE { return }
+
+        self.irq_counter = (self.irq_counter as u64 + number_of_cycles).min(MAXIMUM_IRQ_COUNTER_VALUE as u64) as u16;
+        if self.irq_counter == MAXIMUM_IRQ_COUNTER_VALUE
+        {
+            self.is_irq_pending = true;
+        }
+    }
+
+    fn is_irq_pending(&self) -> bool
+    {
+        return self.is_irq_pending;
+    }
+
+    fn expansion_audio_channels(&self) -> Vec<&dyn Synthesizer>
+    {
+        return vec![&self.audio];
+    }
+}
+
+impl Snapshot for Namco163
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        self.cartridge.save_snapshot(writer);
+        writer.put_usizes(&self.program_banks);
+        writer.put_usizes(&self.character_banks);
+        writer.put_bytes(&self.nametable_banks);
+        writer.put_bool(self.is_irq_enabled);
+        writer.put_u16(self.irq_counter);
+        writer.put_bool(self.is_irq_pending);
+        self.audio.save_snapshot(writer);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.cartridge.load_snapshot(reader).context(codeloc!())?;
+        reader.get_usizes_into(&mut self.program_banks).context(codeloc!())?;
+        reader.get_usizes_into(&mut self.character_banks).context(codeloc!())?;
+        reader.get_bytes_into(&mut self.nametable_banks).context(codeloc!())?;
+        self.is_irq_enabled = reader.get_bool().context(codeloc!())?;
+        self.irq_counter = reader.get_u16().context(codeloc!())?;
+        self.is_irq_pending = reader.get_bool().context(codeloc!())?;
+        self.audio.load_snapshot(reader).context(codeloc!())?;
+        return Ok(());
+    }
+}
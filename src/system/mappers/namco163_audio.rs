@@ -0,0 +1,167 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::apu::Synthesizer;
+use crate::system::byte;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
+
+const INTERNAL_RAM_SIZE : usize = 128;
+const CHANNEL_REGISTERS_START_ADDRESS : usize = 0x40;
+const CHANNEL_REGISTERS_SIZE : usize = 8;
+const MAXIMUM_NUMBER_OF_CHANNELS : usize = 8;
+const CPU_CYCLES_PER_CHANNEL_UPDATE : byte = 15;
+
+//https://www.nesdev.org/wiki/Namco_163_audio
+//up to 8 wavetable channels, the waveforms and the channel registers share a 128 byte internal RAM.
+//the chip serves one channel every 15 CPU cycles, so enabling more channels lowers each one's rate.
+//the 24 bit phase of each channel lives in its registers, the update adds the frequency to it
+pub struct Namco163Audio
+{
+    ram : [byte; INTERNAL_RAM_SIZE],
+    ram_address : byte,
+    should_auto_increment_ram_address : bool,
+    pub is_muted : bool,
+    update_cycle_counter : byte,
+    current_channel_index : usize,
+}
+
+impl Namco163Audio
+{
+    pub fn new() -> Namco163Audio
+    {
+        return Namco163Audio
+        {
+            ram: [0; INTERNAL_RAM_SIZE],
+            ram_address: 0,
+            should_auto_increment_ram_address: false,
+            is_muted: false,
+            update_cycle_counter: 0,
+            current_channel_index: MAXIMUM_NUMBER_OF_CHANNELS-1,
+        };
+    }
+
+    pub fn set_ram_address(&mut self, value : byte)
+    {
+        self.should_auto_increment_ram_address = value & 0b10000000 != 0;
+        self.ram_address = value & 0b01111111;
+    }
+
+    pub fn read_ram(&mut self) -> byte
+    {
+        let value = self.ram[self.ram_address as usize];
+        self.increment_ram_address();
+        return value;
+    }
+
+    pub fn write_ram(&mut self, value : byte)
+    {
+        self.ram[self.ram_address as usize] = value;
+        self.increment_ram_address();
+    }
+
+    fn increment_ram_address(&mut self)
+    {
+        if self.should_auto_increment_ram_address
+        {
+            self.ram_address = (self.ram_address + 1) & 0b01111111;
+        }
+    }
+
+    fn number_of_enabled_channels(&self) -> usize
+    {
+        return (((self.ram[INTERNAL_RAM_SIZE-1] & 0b01110000) >> 4) as usize) + 1;
+    }
+
+    pub fn notify_cpu_cycles(&mut self, number_of_cycles : u64)
+    {
+        for _ in 0..number_of_cycles
+        {
+            self.update_cycle_counter += 1;
+            if self.update_cycle_counter < CPU_CYCLES_PER_CHANNEL_UPDATE { continue }
+            self.update_cycle_counter = 0;
+
+            //channels are served from 7 down to the lowest enabled one, then it wraps around
+            self.update_channel(self.current_channel_index);
+            let lowest_enabled_channel_index = MAXIMUM_NUMBER_OF_CHANNELS - self.number_of_enabled_channels();
+            self.current_channel_index = if self.current_channel_index <= lowest_enabled_channel_index { MAXIMUM_NUMBER_OF_CHANNELS-1 }
+                                         else { self.current_channel_index - 1 };
+        }
+    }
+
+    fn update_channel(&mut self, channel_index : usize)
+    {
+        let registers = CHANNEL_REGISTERS_START_ADDRESS + channel_index * CHANNEL_REGISTERS_SIZE;
+        let frequency_number = (self.ram[registers] as u32)
+            | ((self.ram[registers+2] as u32) << 8)
+            | (((self.ram[registers+4] & 0b00000011) as u32) << 16);
+        let wave_length = 256 - ((self.ram[registers+4] & 0b11111100) as u32);
+
+        //the phase is a 16.8 fixed point sample position, it wraps at the end of the waveform
+        let phase = (self.channel_phase(channel_index) + frequency_number) % (wave_length << 16);
+        self.ram[registers+1] = (phase & 0xFF) as byte;
+        self.ram[registers+3] = ((phase >> 8) & 0xFF) as byte;
+        self.ram[registers+5] = ((phase >> 16) & 0xFF) as byte;
+    }
+
+    fn channel_phase(&self, channel_index : usize) -> u32
+    {
+        let registers = CHANNEL_REGISTERS_START_ADDRESS + channel_index * CHANNEL_REGISTERS_SIZE;
+        return (self.ram[registers+1] as u32)
+            | ((self.ram[registers+3] as u32) << 8)
+            | ((self.ram[registers+5] as u32) << 16);
+    }
+
+    fn synthesize_channel(&self, channel_index : usize) -> f64
+    {
+        //channel 0 registers are at $40-$47, channel 1 at $48-$4F and so on up to channel 7 at $78-$7F
+        let registers = CHANNEL_REGISTERS_START_ADDRESS + channel_index * CHANNEL_REGISTERS_SIZE;
+        let wave_address = self.ram[registers+6] as usize;
+        let volume = (self.ram[registers+7] & 0x0F) as f64;
+
+        //4 bit samples, the low nibble of each byte comes first
+        let sample_index = (wave_address + ((self.channel_phase(channel_index) >> 16) as usize)) % (INTERNAL_RAM_SIZE*2);
+        let sample_byte = self.ram[sample_index / 2];
+        let sample = if sample_index % 2 == 0 { sample_byte & 0x0F } else { sample_byte >> 4 };
+
+        return ((sample as f64) - 8.0) * volume / (8.0 * 15.0);
+    }
+}
+
+impl Synthesizer for Namco163Audio
+{
+    fn synthesize(&self, _waveform_index : f64) -> f64
+    {
+        if self.is_muted { return 0.0 }
+
+        let number_of_enabled_channels = self.number_of_enabled_channels();
+        let sum = (MAXIMUM_NUMBER_OF_CHANNELS - number_of_enabled_channels..MAXIMUM_NUMBER_OF_CHANNELS)
+            .map(|channel_index| self.synthesize_channel(channel_index))
+            .sum::<f64>();
+
+        //channels are time multiplexed, so the loudness of each one drops as more are enabled
+        return 0.5 * sum / (number_of_enabled_channels as f64);
+    }
+}
+
+impl Snapshot for Namco163Audio
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        writer.put_bytes(&self.ram);
+        writer.put_byte(self.ram_address);
+        writer.put_bool(self.should_auto_increment_ram_address);
+        writer.put_bool(self.is_muted);
+        writer.put_byte(self.update_cycle_counter);
+        writer.put_byte(self.current_channel_index as byte);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        reader.get_bytes_into(&mut self.ram).context(codeloc!())?;
+        self.ram_address = reader.get_byte().context(codeloc!())?;
+        self.should_auto_increment_ram_address = reader.get_bool().context(codeloc!())?;
+        self.is_muted = reader.get_bool().context(codeloc!())?;
+        self.update_cycle_counter = reader.get_byte().context(codeloc!())?;
+        self.current_channel_index = (reader.get_byte().context(codeloc!())? as usize) % MAXIMUM_NUMBER_OF_CHANNELS;
+        return Ok(());
+    }
+}
@@ -0,0 +1,107 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::{address, byte};
+use crate::system::cpu::program_ram::ProgramRAM;
+use crate::system::cpu::program_rom::PROGRAM_ROM_START_ADDRESS;
+use crate::system::mappers::{Cartridge, Mapper};
+use crate::system::ppu::mirroring::Mirroring;
+use crate::system::rom::ParsedROM;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
+
+const PROGRAM_BANK_SIZE : usize = 32*1024;
+const CHARACTER_BANK_SIZE : usize = 4*1024;
+const PROGRAM_BANK_REGISTER_ADDRESS : address = 0x7FFD;
+const CHARACTER_BANK0_REGISTER_ADDRESS : address = 0x7FFE;
+const CHARACTER_BANK1_REGISTER_ADDRESS : address = 0x7FFF;
+
+//https://www.nesdev.org/wiki/INES_Mapper_034
+//NINA-001 variant of mapper 34: the bank registers overlay the last bytes of PRG-RAM,
+//writes go both to the register and to the RAM underneath
+pub struct NINA001
+{
+    cartridge : Cartridge,
+    selected_program_bank : usize,
+    selected_character_banks : [usize; 2],
+}
+
+impl NINA001
+{
+    pub fn build(parsed_rom : ParsedROM) -> Box<dyn Mapper>
+    {
+        return Box::new(NINA001
+        {
+            cartridge: Cartridge::new(parsed_rom),
+            selected_program_bank: 0,
+            selected_character_banks: [0, 1],
+        });
+    }
+}
+
+impl Mapper for NINA001
+{
+    fn cpu_read(&mut self, raw_address : address) -> byte
+    {
+        return if raw_address >= PROGRAM_ROM_START_ADDRESS
+            { self.cartridge.program_rom.get_from_bank(self.selected_program_bank, PROGRAM_BANK_SIZE, (raw_address - PROGRAM_ROM_START_ADDRESS) as usize) }
+        else if Cartridge::is_program_ram_address(raw_address) { self.cartridge.program_ram.get(raw_address) }
+        else { 0 };
+    }
+
+    fn cpu_write(&mut self, raw_address : address, value : byte)
+    {
+        match raw_address
+        {
+            PROGRAM_BANK_REGISTER_ADDRESS => self.selected_program_bank = (value & 0b00000001) as usize,
+            CHARACTER_BANK0_REGISTER_ADDRESS => self.selected_character_banks[0] = (value & 0b00001111) as usize,
+            CHARACTER_BANK1_REGISTER_ADDRESS => self.selected_character_banks[1] = (value & 0b00001111) as usize,
+            _ => {}
+        }
+
+        if Cartridge::is_program_ram_address(raw_address)
+        {
+            self.cartridge.program_ram.put(raw_address, value);
+        }
+    }
+
+    fn ppu_read(&self, raw_address : address) -> byte
+    {
+        let bank_index = self.selected_character_banks[(raw_address as usize) / CHARACTER_BANK_SIZE];
+        return self.cartridge.character_rom.get_from_bank(bank_index, CHARACTER_BANK_SIZE, (raw_address as usize) % CHARACTER_BANK_SIZE);
+    }
+
+    fn ppu_write(&mut self, raw_address : address, value : byte)
+    {
+        let bank_index = self.selected_character_banks[(raw_address as usize) / CHARACTER_BANK_SIZE];
+        self.cartridge.character_rom.put_to_bank(bank_index, CHARACTER_BANK_SIZE, (raw_address as usize) % CHARACTER_BANK_SIZE, value);
+    }
+
+    fn mirroring(&self) -> Mirroring
+    {
+        return self.cartridge.mirroring;
+    }
+
+    fn program_ram(&mut self) -> &mut ProgramRAM
+    {
+        return &mut self.cartridge.program_ram;
+    }
+}
+
+impl Snapshot for NINA001
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        self.cartridge.save_snapshot(writer);
+        writer.put_usize(self.selected_program_bank);
+        writer.put_usize(self.selected_character_banks[0]);
+        writer.put_usize(self.selected_character_banks[1]);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.cartridge.load_snapshot(reader).context(codeloc!())?;
+        self.selected_program_bank = reader.get_usize().context(codeloc!())?;
+        self.selected_character_banks[0] = reader.get_usize().context(codeloc!())?;
+        self.selected_character_banks[1] = reader.get_usize().context(codeloc!())?;
+        return Ok(());
+    }
+}
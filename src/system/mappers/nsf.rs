@@ -0,0 +1,105 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::{address, byte};
+use crate::system::cpu::program_ram::ProgramRAM;
+use crate::system::cpu::program_rom::PROGRAM_ROM_START_ADDRESS;
+use crate::system::mappers::{Cartridge, Mapper};
+use crate::system::ppu::mirroring::Mirroring;
+use crate::system::rom::ParsedROM;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
+
+//the board decodes only A12-A15 and A0-A2, so $5FF8-$5FFF is mirrored across $5000-$5FFF
+const BANK_SELECT_START_ADDRESS : address = 0x5000;
+const BANK_SELECT_END_ADDRESS : address = 0x5FFF;
+const BANK_SELECT_ADDRESS_MASK : address = 0x0007;
+const BANK_SIZE : usize = 4*1024;
+const NUMBER_OF_BANK_WINDOWS : usize = 8;
+
+//https://www.nesdev.org/wiki/INES_Mapper_031
+//NSF-style 4kB PRG banking, used by homebrew music compilations such as 2A03 Puritans
+pub struct NSF
+{
+    cartridge : Cartridge,
+    banks : [usize; NUMBER_OF_BANK_WINDOWS],
+}
+
+impl NSF
+{
+    pub fn build(parsed_rom : ParsedROM) -> Box<dyn Mapper>
+    {
+        let mut banks = [0usize; NUMBER_OF_BANK_WINDOWS];
+        for (index, bank) in banks.iter_mut().enumerate() { *bank = index; }
+        banks[NUMBER_OF_BANK_WINDOWS-1] = 0xFF; //$F000-$FFFF starts on the last bank
+
+        return Box::new(NSF
+        {
+            cartridge: Cartridge::new(parsed_rom),
+            banks: banks,
+        });
+    }
+}
+
+impl Mapper for NSF
+{
+    fn cpu_read(&mut self, raw_address : address) -> byte
+    {
+        if raw_address >= PROGRAM_ROM_START_ADDRESS
+        {
+            let relative_address = (raw_address - PROGRAM_ROM_START_ADDRESS) as usize;
+            let bank = self.banks[relative_address / BANK_SIZE];
+            return self.cartridge.program_rom.get_from_bank(bank, BANK_SIZE, relative_address);
+        }
+
+        return if Cartridge::is_program_ram_address(raw_address) { self.cartridge.program_ram.get(raw_address) }
+        else { 0 };
+    }
+
+    fn cpu_write(&mut self, raw_address : address, value : byte)
+    {
+        if raw_address >= BANK_SELECT_START_ADDRESS && raw_address <= BANK_SELECT_END_ADDRESS
+        {
+            let window_index = (raw_address & BANK_SELECT_ADDRESS_MASK) as usize;
+            self.banks[window_index] = value as usize;
+        }
+        else if Cartridge::is_program_ram_address(raw_address)
+        {
+            self.cartridge.program_ram.put(raw_address, value);
+        }
+    }
+
+    fn ppu_read(&self, raw_address : address) -> byte
+    {
+        return self.cartridge.character_rom.get(raw_address);
+    }
+
+    fn ppu_write(&mut self, raw_address : address, value : byte)
+    {
+        self.cartridge.character_rom.put(raw_address, value);
+    }
+
+    fn mirroring(&self) -> Mirroring
+    {
+        return self.cartridge.mirroring;
+    }
+
+    fn program_ram(&mut self) -> &mut ProgramRAM
+    {
+        return &mut self.cartridge.program_ram;
+    }
+}
+
+impl Snapshot for NSF
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        self.cartridge.save_snapshot(writer);
+        writer.put_usizes(&self.banks);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.cartridge.load_snapshot(reader).context(codeloc!())?;
+        reader.get_usizes_into(&mut self.banks).context(codeloc!())?;
+        return Ok(());
+    }
+}
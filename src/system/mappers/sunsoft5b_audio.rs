@@ -0,0 +1,96 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::apu::clock::CPU_FREQUENCY;
+use crate::system::apu::Synthesizer;
+use crate::system::byte;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
+
+const NUMBER_OF_REGISTERS : usize = 16;
+const NUMBER_OF_TONE_CHANNELS : usize = 3;
+
+//https://www.nesdev.org/wiki/Sunsoft_5B_audio
+//a YM2149F (AY-3-8910 family) clone clocked at half the CPU rate: three square tone channels and a shared noise generator.
+//the hardware envelope generator is not emulated, envelope mode channels play at full volume
+pub struct Sunsoft5BAudio
+{
+    selected_register : byte,
+    registers : [byte; NUMBER_OF_REGISTERS],
+}
+
+impl Sunsoft5BAudio
+{
+    pub fn new() -> Sunsoft5BAudio
+    {
+        return Sunsoft5BAudio { selected_register:0, registers:[0; NUMBER_OF_REGISTERS] };
+    }
+
+    pub fn select_register(&mut self, value : byte)
+    {
+        self.selected_register = value & 0x0F;
+    }
+
+    pub fn write_to_selected_register(&mut self, value : byte)
+    {
+        self.registers[self.selected_register as usize] = value;
+    }
+
+    fn synthesize_tone_channel(&self, channel_index : usize, noise : bool, waveform_index : f64) -> f64
+    {
+        let mixer = self.registers[7];
+        let is_tone_enabled = (mixer >> channel_index) & 0b00000001 == 0;
+        let is_noise_enabled = (mixer >> (channel_index + 3)) & 0b00000001 == 0;
+
+        let period = ((self.registers[channel_index*2] as u16) | (((self.registers[channel_index*2+1] & 0x0F) as u16) << 8)).max(1);
+        let frequency = CPU_FREQUENCY / (32.0 * (period as f64));
+        let tone = (frequency * waveform_index).fract() < 0.5;
+
+        //a disabled tone or noise generator leaves its input of the AND gate high
+        let is_high = (tone || !is_tone_enabled) && (noise || !is_noise_enabled);
+        if !is_high { return 0.0 }
+
+        let volume_register = self.registers[8 + channel_index];
+        let volume = if volume_register & 0b00010000 != 0 { 15 } else { volume_register & 0x0F };
+
+        //logarithmic DAC, 3dB per volume step
+        return if volume == 0 { 0.0 } else { f64::powf(10.0, -3.0 * ((15 - volume) as f64) / 20.0) };
+    }
+
+    fn synthesize_noise(&self, waveform_index : f64) -> bool
+    {
+        let period = (self.registers[6] & 0b00011111).max(1);
+        let frequency = CPU_FREQUENCY / (32.0 * (period as f64));
+        let step = (frequency * waveform_index) as u32;
+
+        //cheap hash instead of the 17 bit LFSR, both sound like white noise
+        let hash = step.wrapping_mul(0x9E3779B1).rotate_left(13);
+        return hash & 0b00000001 != 0;
+    }
+}
+
+impl Synthesizer for Sunsoft5BAudio
+{
+    fn synthesize(&self, waveform_index : f64) -> f64
+    {
+        let noise = self.synthesize_noise(waveform_index);
+        let sum = (0..NUMBER_OF_TONE_CHANNELS)
+            .map(|channel_index| self.synthesize_tone_channel(channel_index, noise, waveform_index))
+            .sum::<f64>();
+        return 0.15 * sum;
+    }
+}
+
+impl Snapshot for Sunsoft5BAudio
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        writer.put_byte(self.selected_register);
+        writer.put_bytes(&self.registers);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.selected_register = reader.get_byte().context(codeloc!())?;
+        reader.get_bytes_into(&mut self.registers).context(codeloc!())?;
+        return Ok(());
+    }
+}
@@ -0,0 +1,96 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::{address, byte};
+use crate::system::cpu::program_ram::ProgramRAM;
+use crate::system::cpu::program_rom::PROGRAM_ROM_START_ADDRESS;
+use crate::system::mappers::{Cartridge, Mapper};
+use crate::system::ppu::mirroring::Mirroring;
+use crate::system::rom::ParsedROM;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
+
+const PROGRAM_BANK_SIZE : usize = 16*1024;
+
+//https://www.nesdev.org/wiki/UxROM
+pub struct UxROM
+{
+    cartridge : Cartridge,
+    selected_program_bank : usize,
+}
+
+impl UxROM
+{
+    pub fn build(parsed_rom : ParsedROM) -> Box<dyn Mapper>
+    {
+        return Box::new(UxROM
+        {
+            cartridge: Cartridge::new(parsed_rom),
+            selected_program_bank: 0,
+        });
+    }
+}
+
+impl Mapper for UxROM
+{
+    fn cpu_read(&mut self, raw_address : address) -> byte
+    {
+        if raw_address >= PROGRAM_ROM_START_ADDRESS
+        {
+            //$8000-$BFFF is switchable, $C000-$FFFF is fixed to the last bank
+            let relative_address = (raw_address - PROGRAM_ROM_START_ADDRESS) as usize;
+            let bank_index = if relative_address < PROGRAM_BANK_SIZE { self.selected_program_bank }
+                else { self.cartridge.program_rom.number_of_banks(PROGRAM_BANK_SIZE) - 1 };
+            return self.cartridge.program_rom.get_from_bank(bank_index, PROGRAM_BANK_SIZE, relative_address);
+        }
+
+        return if Cartridge::is_program_ram_address(raw_address) { self.cartridge.program_ram.get(raw_address) }
+        else { 0 };
+    }
+
+    fn cpu_write(&mut self, raw_address : address, value : byte)
+    {
+        if raw_address >= PROGRAM_ROM_START_ADDRESS
+        {
+            self.selected_program_bank = value as usize;
+        }
+        else if Cartridge::is_program_ram_address(raw_address)
+        {
+            self.cartridge.program_ram.put(raw_address, value);
+        }
+    }
+
+    fn ppu_read(&self, raw_address : address) -> byte
+    {
+        return self.cartridge.character_rom.get(raw_address);
+    }
+
+    fn ppu_write(&mut self, raw_address : address, value : byte)
+    {
+        self.cartridge.character_rom.put(raw_address, value);
+    }
+
+    fn mirroring(&self) -> Mirroring
+    {
+        return self.cartridge.mirroring;
+    }
+
+    fn program_ram(&mut self) -> &mut ProgramRAM
+    {
+        return &mut self.cartridge.program_ram;
+    }
+}
+
+impl Snapshot for UxROM
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        self.cartridge.save_snapshot(writer);
+        writer.put_usize(self.selected_program_bank);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.cartridge.load_snapshot(reader).context(codeloc!())?;
+        self.selected_program_bank = reader.get_usize().context(codeloc!())?;
+        return Ok(());
+    }
+}
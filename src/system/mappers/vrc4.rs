@@ -0,0 +1,241 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::{address, byte};
+use crate::system::cpu::program_ram::ProgramRAM;
+use crate::system::cpu::program_rom::PROGRAM_ROM_START_ADDRESS;
+use crate::system::mappers::{Cartridge, Mapper};
+use crate::system::mappers::vrc_irq::VRCIRQCounter;
+use crate::system::ppu::mirroring::Mirroring;
+use crate::system::rom::ParsedROM;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
+
+const PROGRAM_BANK_SIZE : usize = 8*1024;
+const CHARACTER_BANK_SIZE : usize = 1*1024;
+const NUMBER_OF_CHARACTER_BANKS : usize = 8;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum VRCVariant
+{
+    VRC2a, VRC2bc, VRC4,
+}
+
+//https://www.nesdev.org/wiki/VRC2_and_VRC4
+//the boards wire different CPU address lines to the chip's register select pins,
+//each mapper number ORs together the lines used by its board variants, the NES 2.0 submapper selects a single one
+pub struct VRC4
+{
+    cartridge : Cartridge,
+    variant : VRCVariant,
+    register_select_pin0_mask : address,
+    register_select_pin1_mask : address,
+    program_banks : [usize; 2],
+    is_program_rom_bank_mode_swapped : bool,
+    character_banks : [usize; NUMBER_OF_CHARACTER_BANKS],
+    irq : VRCIRQCounter,
+}
+
+impl VRC4
+{
+    pub fn build_mapper21(parsed_rom : ParsedROM) -> Box<dyn Mapper>
+    {
+        return Box::new(VRC4::new(parsed_rom, VRCVariant::VRC4, 0x02|0x40, 0x04|0x80)); //VRC4a, VRC4c
+    }
+
+    pub fn build_mapper22(parsed_rom : ParsedROM) -> Box<dyn Mapper>
+    {
+        return Box::new(VRC4::new(parsed_rom, VRCVariant::VRC2a, 0x02, 0x01)); //VRC2a
+    }
+
+    pub fn build_mapper23(parsed_rom : ParsedROM) -> Box<dyn Mapper>
+    {
+        return Box::new(VRC4::new(parsed_rom, VRCVariant::VRC4, 0x01|0x04, 0x02|0x08)); //VRC4e, VRC4f
+    }
+
+    pub fn build_mapper23_vrc2b(parsed_rom : ParsedROM) -> Box<dyn Mapper>
+    {
+        return Box::new(VRC4::new(parsed_rom, VRCVariant::VRC2bc, 0x01, 0x02));
+    }
+
+    pub fn build_mapper25(parsed_rom : ParsedROM) -> Box<dyn Mapper>
+    {
+        return Box::new(VRC4::new(parsed_rom, VRCVariant::VRC4, 0x02|0x08, 0x01|0x04)); //VRC4b, VRC4d
+    }
+
+    pub fn build_mapper25_vrc2c(parsed_rom : ParsedROM) -> Box<dyn Mapper>
+    {
+        return Box::new(VRC4::new(parsed_rom, VRCVariant::VRC2bc, 0x02, 0x01));
+    }
+
+    fn new(parsed_rom : ParsedROM, variant : VRCVariant, register_select_pin0_mask : address, register_select_pin1_mask : address) -> VRC4
+    {
+        return VRC4
+        {
+            cartridge: Cartridge::new(parsed_rom),
+            variant: variant,
+            register_select_pin0_mask: register_select_pin0_mask,
+            register_select_pin1_mask: register_select_pin1_mask,
+            program_banks: [0, 1],
+            is_program_rom_bank_mode_swapped: false,
+            character_banks: [0; NUMBER_OF_CHARACTER_BANKS],
+            irq: VRCIRQCounter::new(),
+        };
+    }
+
+    fn get_register_index(&self, raw_address : address) -> address
+    {
+        let pin0 = (raw_address & self.register_select_pin0_mask != 0) as address;
+        let pin1 = (raw_address & self.register_select_pin1_mask != 0) as address;
+        return (pin1 << 1) | pin0;
+    }
+
+    fn get_program_bank_index(&self, relative_address : usize) -> usize
+    {
+        let second_last_bank = self.cartridge.program_rom.number_of_banks(PROGRAM_BANK_SIZE).saturating_sub(2);
+        let last_bank = self.cartridge.program_rom.number_of_banks(PROGRAM_BANK_SIZE) - 1;
+
+        return match (relative_address / PROGRAM_BANK_SIZE, self.is_program_rom_bank_mode_swapped)
+        {
+            (0, false) => self.program_banks[0],
+            (0, true) => second_last_bank,
+            (1, _) => self.program_banks[1],
+            (2, false) => second_last_bank,
+            (2, true) => self.program_banks[0],
+            _ => last_bank,
+        };
+    }
+
+    fn get_character_bank_index(&self, raw_address : address) -> usize
+    {
+        let bank_index = self.character_banks[((raw_address as usize) / CHARACTER_BANK_SIZE) % NUMBER_OF_CHARACTER_BANKS];
+        //VRC2a ignores the lowest bit of the CHR bank number
+        return if self.variant == VRCVariant::VRC2a { bank_index >> 1 } else { bank_index };
+    }
+
+    fn is_vrc2(&self) -> bool
+    {
+        return self.variant != VRCVariant::VRC4;
+    }
+
+    fn write_to_register(&mut self, raw_address : address, value : byte)
+    {
+        let register_index = self.get_register_index(raw_address);
+        let is_vrc2 = self.is_vrc2();
+        match (raw_address & 0xF000, register_index)
+        {
+            (0x8000, _) => { self.program_banks[0] = (value & 0b00011111) as usize; }
+            (0x9000, _) if is_vrc2 =>
+            {
+                self.cartridge.mirroring = if value & 0b00000001 == 0 { Mirroring::Vertical } else { Mirroring::Horizontal };
+            }
+            (0x9000, 0) | (0x9000, 1) =>
+            {
+                self.cartridge.mirroring = match value & 0b00000011
+                {
+                    0 => Mirroring::Vertical,
+                    1 => Mirroring::Horizontal,
+                    2 => Mirroring::SingleScreenLower,
+                    _ => Mirroring::SingleScreenUpper,
+                };
+            }
+            (0x9000, _) if !is_vrc2 => { self.is_program_rom_bank_mode_swapped = value & 0b00000010 != 0; }
+            (0xA000, _) => { self.program_banks[1] = (value & 0b00011111) as usize; }
+            (0xB000..=0xE000, _) =>
+            {
+                //each CHR bank number is written as two nibbles, low nibble at even registers
+                let bank_register_index = (((raw_address & 0xF000) - 0xB000) >> 12) as usize * 2 + (register_index >> 1) as usize;
+                //VRC2 has only 4 bits for the high nibble, VRC4 has 5
+                let high_nibble_mask = if is_vrc2 { 0x0F } else { 0x1F };
+                let bank = self.character_banks[bank_register_index];
+                self.character_banks[bank_register_index] = if register_index & 1 == 0 { (bank & 0x1F0) | (value & 0x0F) as usize }
+                    else { (bank & 0x00F) | (((value & high_nibble_mask) as usize) << 4) };
+            }
+            (0xF000, 0) if !is_vrc2 => { self.irq.set_latch_low_nibble(value); }
+            (0xF000, 1) if !is_vrc2 => { self.irq.set_latch_high_nibble(value); }
+            (0xF000, 2) if !is_vrc2 => { self.irq.set_control(value); }
+            (0xF000, 3) if !is_vrc2 => { self.irq.acknowledge(); }
+            _ => {}
+        }
+    }
+}
+
+impl Mapper for VRC4
+{
+    fn cpu_read(&mut self, raw_address : address) -> byte
+    {
+        if raw_address >= PROGRAM_ROM_START_ADDRESS
+        {
+            let relative_address = (raw_address - PROGRAM_ROM_START_ADDRESS) as usize;
+            let bank_index = self.get_program_bank_index(relative_address);
+            return self.cartridge.program_rom.get_from_bank(bank_index, PROGRAM_BANK_SIZE, relative_address);
+        }
+
+        return if Cartridge::is_program_ram_address(raw_address) { self.cartridge.program_ram.get(raw_address) }
+        else { 0 };
+    }
+
+    fn cpu_write(&mut self, raw_address : address, value : byte)
+    {
+        if raw_address >= PROGRAM_ROM_START_ADDRESS
+        {
+            self.write_to_register(raw_address, value);
+        }
+        else if Cartridge::is_program_ram_address(raw_address)
+        {
+            self.cartridge.program_ram.put(raw_address, value);
+        }
+    }
+
+    fn ppu_read(&self, raw_address : address) -> byte
+    {
+        let bank_index = self.get_character_bank_index(raw_address);
+        return self.cartridge.character_rom.get_from_bank(bank_index, CHARACTER_BANK_SIZE, raw_address as usize);
+    }
+
+    fn ppu_write(&mut self, raw_address : address, value : byte)
+    {
+        let bank_index = self.get_character_bank_index(raw_address);
+        self.cartridge.character_rom.put_to_bank(bank_index, CHARACTER_BANK_SIZE, raw_address as usize, value);
+    }
+
+    fn mirroring(&self) -> Mirroring
+    {
+        return self.cartridge.mirroring;
+    }
+
+    fn program_ram(&mut self) -> &mut ProgramRAM
+    {
+        return &mut self.cartridge.program_ram;
+    }
+
+    fn notify_cpu_cycles(&mut self, number_of_cycles : u64)
+    {
+        self.irq.notify_cpu_cycles(number_of_cycles);
+    }
+
+    fn is_irq_pending(&self) -> bool
+    {
+        return self.irq.is_pending();
+    }
+}
+
+impl Snapshot for VRC4
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        self.cartridge.save_snapshot(writer);
+        writer.put_usizes(&self.program_banks);
+        writer.put_bool(self.is_program_rom_bank_mode_swapped);
+        writer.put_usizes(&self.character_banks);
+        self.irq.save_snapshot(writer);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.cartridge.load_snapshot(reader).context(codeloc!())?;
+        reader.get_usizes_into(&mut self.program_banks).context(codeloc!())?;
+        self.is_program_rom_bank_mode_swapped = reader.get_bool().context(codeloc!())?;
+        reader.get_usizes_into(&mut self.character_banks).context(codeloc!())?;
+        self.irq.load_snapshot(reader).context(codeloc!())?;
+        return Ok(());
+    }
+}
@@ -0,0 +1,202 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::{address, byte};
+use crate::system::apu::Synthesizer;
+use crate::system::cpu::program_ram::ProgramRAM;
+use crate::system::cpu::program_rom::PROGRAM_ROM_START_ADDRESS;
+use crate::system::mappers::{Cartridge, Mapper};
+use crate::system::mappers::vrc6_audio::VRC6Audio;
+use crate::system::mappers::vrc_irq::VRCIRQCounter;
+use crate::system::ppu::mirroring::Mirroring;
+use crate::system::rom::ParsedROM;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
+
+const PROGRAM_16K_BANK_SIZE : usize = 16*1024;
+const PROGRAM_8K_BANK_SIZE : usize = 8*1024;
+const CHARACTER_BANK_SIZE : usize = 1*1024;
+const NUMBER_OF_CHARACTER_BANKS : usize = 8;
+
+//https://www.nesdev.org/wiki/VRC6
+pub struct VRC6
+{
+    cartridge : Cartridge,
+    are_register_select_pins_swapped : bool,
+    program_16k_bank : usize,
+    program_8k_bank : usize,
+    character_banks : [usize; NUMBER_OF_CHARACTER_BANKS],
+    is_program_ram_enabled : bool,
+    irq : VRCIRQCounter,
+    audio : VRC6Audio,
+}
+
+impl VRC6
+{
+    pub fn build_mapper24(parsed_rom : ParsedROM) -> Box<dyn Mapper>
+    {
+        return Box::new(VRC6::new(parsed_rom, false)); //VRC6a
+    }
+
+    pub fn build_mapper26(parsed_rom : ParsedROM) -> Box<dyn Mapper>
+    {
+        return Box::new(VRC6::new(parsed_rom, true)); //VRC6b, A0 and A1 swapped
+    }
+
+    fn new(parsed_rom : ParsedROM, are_register_select_pins_swapped : bool) -> VRC6
+    {
+        return VRC6
+        {
+            cartridge: Cartridge::new(parsed_rom),
+            are_register_select_pins_swapped: are_register_select_pins_swapped,
+            program_16k_bank: 0,
+            program_8k_bank: 0,
+            character_banks: [0; NUMBER_OF_CHARACTER_BANKS],
+            is_program_ram_enabled: false,
+            irq: VRCIRQCounter::new(),
+            audio: VRC6Audio::new(),
+        };
+    }
+
+    fn write_to_register(&mut self, raw_address : address, value : byte)
+    {
+        let register_index = if self.are_register_select_pins_swapped
+            { ((raw_address & 0b01) << 1) | ((raw_address & 0b10) >> 1) }
+        else { raw_address & 0b11 };
+
+        match (raw_address & 0xF000, register_index)
+        {
+            (0x8000, _) => { self.program_16k_bank = (value & 0b00001111) as usize; }
+            (0x9000, 0) => { self.audio.pulse1_synth.set_control(value); }
+            (0x9000, 1) => { self.audio.pulse1_synth.set_period_low(value); }
+            (0x9000, 2) => { self.audio.pulse1_synth.set_period_high(value); }
+            (0x9000, _) => { self.audio.is_halted = value & 0b00000001 != 0; }
+            (0xA000, 0) => { self.audio.pulse2_synth.set_control(value); }
+            (0xA000, 1) => { self.audio.pulse2_synth.set_period_low(value); }
+            (0xA000, 2) => { self.audio.pulse2_synth.set_period_high(value); }
+            (0xB000, 0) => { self.audio.sawtooth_synth.set_accumulator_rate(value); }
+            (0xB000, 1) => { self.audio.sawtooth_synth.set_period_low(value); }
+            (0xB000, 2) => { self.audio.sawtooth_synth.set_period_high(value); }
+            (0xB000, _) =>
+            {
+                //PPU banking style register, only the 1kB CHR banks mode used by the commercial games is supported
+                self.cartridge.mirroring = match (value & 0b00001100) >> 2
+                {
+                    0 => Mirroring::Vertical,
+                    1 => Mirroring::Horizontal,
+                    2 => Mirroring::SingleScreenLower,
+                    _ => Mirroring::SingleScreenUpper,
+                };
+                self.is_program_ram_enabled = value & 0b10000000 != 0;
+            }
+            (0xC000, _) => { self.program_8k_bank = (value & 0b00011111) as usize; }
+            (0xD000, _) | (0xE000, _) =>
+            {
+                let bank_register_index = if raw_address & 0xF000 == 0xD000 { 0 } else { 4 } + register_index as usize;
+                self.character_banks[bank_register_index] = value as usize;
+            }
+            (0xF000, 0) => { self.irq.set_latch(value); }
+            (0xF000, 1) => { self.irq.set_control(value); }
+            (0xF000, 2) => { self.irq.acknowledge(); }
+            _ => {}
+        }
+    }
+}
+
+impl Mapper for VRC6
+{
+    fn cpu_read(&mut self, raw_address : address) -> byte
+    {
+        if raw_address >= PROGRAM_ROM_START_ADDRESS
+        {
+            let relative_address = (raw_address - PROGRAM_ROM_START_ADDRESS) as usize;
+            return if relative_address < PROGRAM_16K_BANK_SIZE
+                { self.cartridge.program_rom.get_from_bank(self.program_16k_bank, PROGRAM_16K_BANK_SIZE, relative_address) }
+            else if relative_address < PROGRAM_16K_BANK_SIZE + PROGRAM_8K_BANK_SIZE
+                { self.cartridge.program_rom.get_from_bank(self.program_8k_bank, PROGRAM_8K_BANK_SIZE, relative_address) }
+            else
+            {
+                let last_bank = self.cartridge.program_rom.number_of_banks(PROGRAM_8K_BANK_SIZE) - 1;
+                self.cartridge.program_rom.get_from_bank(last_bank, PROGRAM_8K_BANK_SIZE, relative_address)
+            };
+        }
+
+        return if Cartridge::is_program_ram_address(raw_address) && self.is_program_ram_enabled
+            { self.cartridge.program_ram.get(raw_address) }
+        else { 0 };
+    }
+
+    fn cpu_write(&mut self, raw_address : address, value : byte)
+    {
+        if raw_address >= PROGRAM_ROM_START_ADDRESS
+        {
+            self.write_to_register(raw_address, value);
+        }
+        else if Cartridge::is_program_ram_address(raw_address) && self.is_program_ram_enabled
+        {
+            self.cartridge.program_ram.put(raw_address, value);
+        }
+    }
+
+    fn ppu_read(&self, raw_address : address) -> byte
+    {
+        let bank_index = self.character_banks[((raw_address as usize) / CHARACTER_BANK_SIZE) % NUMBER_OF_CHARACTER_BANKS];
+        return self.cartridge.character_rom.get_from_bank(bank_index, CHARACTER_BANK_SIZE, raw_address as usize);
+    }
+
+    fn ppu_write(&mut self, raw_address : address, value : byte)
+    {
+        let bank_index = self.character_banks[((raw_address as usize) / CHARACTER_BANK_SIZE) % NUMBER_OF_CHARACTER_BANKS];
+        self.cartridge.character_rom.put_to_bank(bank_index, CHARACTER_BANK_SIZE, raw_address as usize, value);
+    }
+
+    fn mirroring(&self) -> Mirroring
+    {
+        return self.cartridge.mirroring;
+    }
+
+    fn program_ram(&mut self) -> &mut ProgramRAM
+    {
+        return &mut self.cartridge.program_ram;
+    }
+
+    fn notify_cpu_cycles(&mut self, number_of_cycles : u64)
+    {
+        self.irq.notify_cpu_cycles(number_of_cycles);
+        self.audio.notify_cpu_cycles(number_of_cycles);
+    }
+
+    fn is_irq_pending(&self) -> bool
+    {
+        return self.irq.is_pending();
+    }
+
+    fn expansion_audio_channels(&self) -> Vec<&dyn Synthesizer>
+    {
+        return vec![&self.audio];
+    }
+}
+
+impl Snapshot for VRC6
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        self.cartridge.save_snapshot(writer);
+        writer.put_usize(self.program_16k_bank);
+        writer.put_usize(self.program_8k_bank);
+        writer.put_usizes(&self.character_banks);
+        writer.put_bool(self.is_program_ram_enabled);
+        self.irq.save_snapshot(writer);
+        self.audio.save_snapshot(writer);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.cartridge.load_snapshot(reader).context(codeloc!())?;
+        self.program_16k_bank = reader.get_usize().context(codeloc!())?;
+        self.program_8k_bank = reader.get_usize().context(codeloc!())?;
+        reader.get_usizes_into(&mut self.character_banks).context(codeloc!())?;
+        self.is_program_ram_enabled = reader.get_bool().context(codeloc!())?;
+        self.irq.load_snapshot(reader).context(codeloc!())?;
+        self.audio.load_snapshot(reader).context(codeloc!())?;
+        return Ok(());
+    }
+}
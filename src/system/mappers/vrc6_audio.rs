@@ -0,0 +1,263 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::apu::Synthesizer;
+use crate::system::byte;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
+
+const NUMBER_OF_PULSE_STEPS : byte = 16;
+const NUMBER_OF_SAWTOOTH_STEPS : byte = 14;
+
+//https://www.nesdev.org/wiki/VRC6_audio
+//every channel has a 12 bit divider clocked by the CPU, its output steps the channel's sequencer,
+//so the waveform keeps its phase across period writes instead of being recomputed from the sample time
+pub struct VRC6PulseSynth
+{
+    is_digitized_mode_enabled : bool,
+    duty_cycle : byte,
+    volume : byte,
+    period : u16,
+    is_enabled : bool,
+    divider : u16,
+    step : byte,
+}
+
+impl VRC6PulseSynth
+{
+    pub fn new() -> VRC6PulseSynth
+    {
+        return VRC6PulseSynth { is_digitized_mode_enabled:false, duty_cycle:0, volume:0, period:0, is_enabled:false, divider:0, step:0 };
+    }
+
+    pub fn set_control(self : &mut VRC6PulseSynth, value : byte)
+    {
+        self.is_digitized_mode_enabled = (value & 0b10000000) >> 7 == 1;
+        self.duty_cycle                = (value & 0b01110000) >> 4;
+        self.volume                    = (value & 0b00001111) >> 0;
+    }
+
+    pub fn set_period_low(self : &mut VRC6PulseSynth, value : byte)
+    {
+        self.period = (self.period & 0x0F00) | (value as u16);
+    }
+
+    pub fn set_period_high(self : &mut VRC6PulseSynth, value : byte)
+    {
+        self.is_enabled = (value & 0b10000000) >> 7 == 1;
+        self.period = (self.period & 0x00FF) | (((value & 0x0F) as u16) << 8);
+
+        //disabling the channel resets the duty sequencer
+        if !self.is_enabled { self.step = 0; }
+    }
+
+    fn clock(self : &mut VRC6PulseSynth)
+    {
+        if !self.is_enabled { return }
+
+        if self.divider == 0
+        {
+            self.divider = self.period;
+            self.step = (self.step + 1) % NUMBER_OF_PULSE_STEPS;
+        }
+        else { self.divider -= 1; }
+    }
+}
+
+impl Synthesizer for VRC6PulseSynth
+{
+    fn synthesize(&self, _waveform_index : f64) -> f64
+    {
+        if !self.is_enabled { return 0.0 }
+
+        //16 step sequencer, the output is high for the first duty_cycle+1 steps
+        let is_high = self.is_digitized_mode_enabled || self.step <= self.duty_cycle;
+        return if is_high { (self.volume as f64) / 15.0 } else { 0.0 };
+    }
+}
+
+pub struct VRC6SawtoothSynth
+{
+    accumulator_rate : byte,
+    period : u16,
+    is_enabled : bool,
+    divider : u16,
+    step : byte,
+    accumulator : byte,
+}
+
+impl VRC6SawtoothSynth
+{
+    pub fn new() -> VRC6SawtoothSynth
+    {
+        return VRC6SawtoothSynth { accumulator_rate:0, period:0, is_enabled:false, divider:0, step:0, accumulator:0 };
+    }
+
+    pub fn set_accumulator_rate(self : &mut VRC6SawtoothSynth, value : byte)
+    {
+        self.accumulator_rate = value & 0b00111111;
+    }
+
+    pub fn set_period_low(self : &mut VRC6SawtoothSynth, value : byte)
+    {
+        self.period = (self.period & 0x0F00) | (value as u16);
+    }
+
+    pub fn set_period_high(self : &mut VRC6SawtoothSynth, value : byte)
+    {
+        self.is_enabled = (value & 0b10000000) >> 7 == 1;
+        self.period = (self.period & 0x00FF) | (((value & 0x0F) as u16) << 8);
+
+        //disabling the channel resets the accumulator
+        if !self.is_enabled
+        {
+            self.step = 0;
+            self.accumulator = 0;
+        }
+    }
+
+    fn clock(self : &mut VRC6SawtoothSynth)
+    {
+        if !self.is_enabled { return }
+
+        if self.divider > 0
+        {
+            self.divider -= 1;
+            return;
+        }
+
+        //the rate is added on every second divider clock, the 14th clock resets the accumulator
+        self.divider = self.period;
+        self.step = (self.step + 1) % NUMBER_OF_SAWTOOTH_STEPS;
+        if self.step == 0 { self.accumulator = 0; }
+        else if self.step % 2 == 0 { self.accumulator = self.accumulator.wrapping_add(self.accumulator_rate); }
+    }
+}
+
+impl Synthesizer for VRC6SawtoothSynth
+{
+    fn synthesize(&self, _waveform_index : f64) -> f64
+    {
+        if !self.is_enabled { return 0.0 }
+
+        //the top 5 bits of the accumulator are output
+        return ((self.accumulator >> 3) as f64) / 31.0;
+    }
+}
+
+pub struct VRC6Audio
+{
+    pub pulse1_synth : VRC6PulseSynth,
+    pub pulse2_synth : VRC6PulseSynth,
+    pub sawtooth_synth : VRC6SawtoothSynth,
+    pub is_halted : bool,
+}
+
+impl VRC6Audio
+{
+    pub fn new() -> VRC6Audio
+    {
+        return VRC6Audio
+        {
+            pulse1_synth: VRC6PulseSynth::new(),
+            pulse2_synth: VRC6PulseSynth::new(),
+            sawtooth_synth: VRC6SawtoothSynth::new(),
+            is_halted: false,
+        };
+    }
+
+    pub fn notify_cpu_cycles(&mut self, number_of_cycles : u64)
+    {
+        //the halt bit stops all three dividers
+        if self.is_halted { return }
+
+        for _ in 0..number_of_cycles
+        {
+            self.pulse1_synth.clock();
+            self.pulse2_synth.clock();
+            self.sawtooth_synth.clock();
+        }
+    }
+}
+
+impl Synthesizer for VRC6Audio
+{
+    fn synthesize(&self, waveform_index : f64) -> f64
+    {
+        if self.is_halted { return 0.0 }
+
+        //pulses peak at 15, the sawtooth at 31, same scale as the 2A03 pulses
+        let pulse1 = self.pulse1_synth.synthesize(waveform_index) * 15.0;
+        let pulse2 = self.pulse2_synth.synthesize(waveform_index) * 15.0;
+        let sawtooth = self.sawtooth_synth.synthesize(waveform_index) * 31.0;
+        return 0.00752 * (pulse1 + pulse2 + sawtooth);
+    }
+}
+
+impl Snapshot for VRC6PulseSynth
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        writer.put_bool(self.is_digitized_mode_enabled);
+        writer.put_byte(self.duty_cycle);
+        writer.put_byte(self.volume);
+        writer.put_u16(self.period);
+        writer.put_bool(self.is_enabled);
+        writer.put_u16(self.divider);
+        writer.put_byte(self.step);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.is_digitized_mode_enabled = reader.get_bool().context(codeloc!())?;
+        self.duty_cycle = reader.get_byte().context(codeloc!())?;
+        self.volume = reader.get_byte().context(codeloc!())?;
+        self.period = reader.get_u16().context(codeloc!())?;
+        self.is_enabled = reader.get_bool().context(codeloc!())?;
+        self.divider = reader.get_u16().context(codeloc!())?;
+        self.step = reader.get_byte().context(codeloc!())?;
+        return Ok(());
+    }
+}
+
+impl Snapshot for VRC6SawtoothSynth
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        writer.put_byte(self.accumulator_rate);
+        writer.put_u16(self.period);
+        writer.put_bool(self.is_enabled);
+        writer.put_u16(self.divider);
+        writer.put_byte(self.step);
+        writer.put_byte(self.accumulator);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.accumulator_rate = reader.get_byte().context(codeloc!())?;
+        self.period = reader.get_u16().context(codeloc!())?;
+        self.is_enabled = reader.get_bool().context(codeloc!())?;
+        self.divider = reader.get_u16().context(codeloc!())?;
+        self.step = reader.get_byte().context(codeloc!())?;
+        self.accumulator = reader.get_byte().context(codeloc!())?;
+        return Ok(());
+    }
+}
+
+impl Snapshot for VRC6Audio
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        self.pulse1_synth.save_snapshot(writer);
+        self.pulse2_synth.save_snapshot(writer);
+        self.sawtooth_synth.save_snapshot(writer);
+        writer.put_bool(self.is_halted);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.pulse1_synth.load_snapshot(reader).context(codeloc!())?;
+        self.pulse2_synth.load_snapshot(reader).context(codeloc!())?;
+        self.sawtooth_synth.load_snapshot(reader).context(codeloc!())?;
+        self.is_halted = reader.get_bool().context(codeloc!())?;
+        return Ok(());
+    }
+}
@@ -0,0 +1,175 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::{address, byte};
+use crate::system::apu::Synthesizer;
+use crate::system::cpu::program_ram::ProgramRAM;
+use crate::system::cpu::program_rom::PROGRAM_ROM_START_ADDRESS;
+use crate::system::mappers::{Cartridge, Mapper};
+use crate::system::mappers::vrc7_audio::VRC7Audio;
+use crate::system::mappers::vrc_irq::VRCIRQCounter;
+use crate::system::ppu::mirroring::Mirroring;
+use crate::system::rom::ParsedROM;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
+
+const AUDIO_REGISTER_SELECT_ADDRESS : address = 0x9010;
+const AUDIO_REGISTER_DATA_ADDRESS : address = 0x9030;
+
+const PROGRAM_BANK_SIZE : usize = 8*1024;
+const NUMBER_OF_PROGRAM_BANKS : usize = 3;
+const CHARACTER_BANK_SIZE : usize = 1*1024;
+const NUMBER_OF_CHARACTER_BANKS : usize = 8;
+
+//https://www.nesdev.org/wiki/VRC7
+//VRC7a boards (Lagrange Point) select registers with A4, VRC7b boards (Tiny Toon Adventures 2) with A3
+pub struct VRC7
+{
+    cartridge : Cartridge,
+    program_banks : [usize; NUMBER_OF_PROGRAM_BANKS],
+    character_banks : [usize; NUMBER_OF_CHARACTER_BANKS],
+    is_program_ram_enabled : bool,
+    irq : VRCIRQCounter,
+    audio : VRC7Audio,
+}
+
+impl VRC7
+{
+    pub fn build(parsed_rom : ParsedROM) -> Box<dyn Mapper>
+    {
+        return Box::new(VRC7
+        {
+            cartridge: Cartridge::new(parsed_rom),
+            program_banks: [0, 1, 2],
+            character_banks: [0; NUMBER_OF_CHARACTER_BANKS],
+            is_program_ram_enabled: false,
+            irq: VRCIRQCounter::new(),
+            audio: VRC7Audio::new(),
+        });
+    }
+
+    fn write_to_register(&mut self, raw_address : address, value : byte)
+    {
+        if raw_address == AUDIO_REGISTER_SELECT_ADDRESS { self.audio.select_register(value); return }
+        if raw_address == AUDIO_REGISTER_DATA_ADDRESS { self.audio.write_to_selected_register(value); return }
+
+        let register_index = (raw_address & 0b00011000 != 0) as usize;
+        match (raw_address & 0xF000, register_index)
+        {
+            (0x8000, _) => { self.program_banks[register_index] = (value & 0b00111111) as usize; }
+            (0x9000, 0) => { self.program_banks[2] = (value & 0b00111111) as usize; }
+            (0xA000..=0xD000, _) =>
+            {
+                let bank_register_index = (((raw_address & 0xF000) - 0xA000) >> 12) as usize * 2 + register_index;
+                self.character_banks[bank_register_index] = value as usize;
+            }
+            (0xE000, 0) =>
+            {
+                self.cartridge.mirroring = match value & 0b00000011
+                {
+                    0 => Mirroring::Vertical,
+                    1 => Mirroring::Horizontal,
+                    2 => Mirroring::SingleScreenLower,
+                    _ => Mirroring::SingleScreenUpper,
+                };
+                self.audio.is_muted = value & 0b01000000 != 0;
+                self.is_program_ram_enabled = value & 0b10000000 != 0;
+            }
+            (0xE000, _) => { self.irq.set_latch(value); }
+            (0xF000, 0) => { self.irq.set_control(value); }
+            (0xF000, _) => { self.irq.acknowledge(); }
+            _ => {}
+        }
+    }
+}
+
+impl Mapper for VRC7
+{
+    fn cpu_read(&mut self, raw_address : address) -> byte
+    {
+        if raw_address >= PROGRAM_ROM_START_ADDRESS
+        {
+            let relative_address = (raw_address - PROGRAM_ROM_START_ADDRESS) as usize;
+            let window_index = relative_address / PROGRAM_BANK_SIZE;
+            let bank_index = if window_index < NUMBER_OF_PROGRAM_BANKS { self.program_banks[window_index] }
+                else { self.cartridge.program_rom.number_of_banks(PROGRAM_BANK_SIZE) - 1 };
+            return self.cartridge.program_rom.get_from_bank(bank_index, PROGRAM_BANK_SIZE, relative_address);
+        }
+
+        return if Cartridge::is_program_ram_address(raw_address) && self.is_program_ram_enabled
+            { self.cartridge.program_ram.get(raw_address) }
+        else { 0 };
+    }
+
+    fn cpu_write(&mut self, raw_address : address, value : byte)
+    {
+        if raw_address >= PROGRAM_ROM_START_ADDRESS
+        {
+            self.write_to_register(raw_address, value);
+        }
+        else if Cartridge::is_program_ram_address(raw_address) && self.is_program_ram_enabled
+        {
+            self.cartridge.program_ram.put(raw_address, value);
+        }
+    }
+
+    fn ppu_read(&self, raw_address : address) -> byte
+    {
+        let bank_index = self.character_banks[((raw_address as usize) / CHARACTER_BANK_SIZE) % NUMBER_OF_CHARACTER_BANKS];
+        return self.cartridge.character_rom.get_from_bank(bank_index, CHARACTER_BANK_SIZE, raw_address as usize);
+    }
+
+    fn ppu_write(&mut self, raw_address : address, value : byte)
+    {
+        let bank_index = self.character_banks[((raw_address as usize) / CHARACTER_BANK_SIZE) % NUMBER_OF_CHARACTER_BANKS];
+        self.cartridge.character_rom.put_to_bank(bank_index, CHARACTER_BANK_SIZE, raw_address as usize, value);
+    }
+
+    fn mirroring(&self) -> Mirroring
+    {
+        return self.cartridge.mirroring;
+    }
+
+    fn program_ram(&mut self) -> &mut ProgramRAM
+    {
+        return &mut self.cartridge.program_ram;
+    }
+
+    fn notify_cpu_cycles(&mut self, number_of_cycles : u64)
+    {
+        self.irq.notify_cpu_cycles(number_of_cycles);
+        self.audio.notify_cpu_cycles(number_of_cycles);
+    }
+
+    fn is_irq_pending(&self) -> bool
+    {
+        return self.irq.is_pending();
+    }
+
+    fn expansion_audio_channels(&self) -> Vec<&dyn Synthesizer>
+    {
+        return vec![&self.audio];
+    }
+}
+
+impl Snapshot for VRC7
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        self.cartridge.save_snapshot(writer);
+        writer.put_usizes(&self.program_banks);
+        writer.put_usizes(&self.character_banks);
+        writer.put_bool(self.is_program_ram_enabled);
+        self.irq.save_snapshot(writer);
+        self.audio.save_snapshot(writer);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.cartridge.load_snapshot(reader).context(codeloc!())?;
+        reader.get_usizes_into(&mut self.program_banks).context(codeloc!())?;
+        reader.get_usizes_into(&mut self.character_banks).context(codeloc!())?;
+        self.is_program_ram_enabled = reader.get_bool().context(codeloc!())?;
+        self.irq.load_snapshot(reader).context(codeloc!())?;
+        self.audio.load_snapshot(reader).context(codeloc!())?;
+        return Ok(());
+    }
+}
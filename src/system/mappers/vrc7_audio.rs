@@ -0,0 +1,390 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::apu::Synthesizer;
+use crate::system::byte;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
+
+const OPLL_SAMPLE_RATE : f64 = 49716.0; //3.58MHz / 72
+const NUMBER_OF_CHANNELS : usize = 6;
+const PATCH_SIZE : usize = 8;
+const MULTIPLIERS : [f64; 16] = [0.5, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 10.0, 12.0, 12.0, 15.0, 15.0];
+const CPU_CYCLES_PER_ENVELOPE_STEP : u64 = 36; //the envelopes are clocked once per OPLL sample
+const MAXIMUM_ATTENUATION_IN_DECIBELS : f64 = 48.0;
+const ATTACK_TIME_AT_RATE_1_IN_SECONDS : f64 = 2.826; //from silence to full volume, each next rate halves it
+const DECAY_TIME_AT_RATE_1_IN_SECONDS : f64 = 19.64; //from full volume to silence, each next rate halves it
+const SUSTAIN_ON_RELEASE_RATE : byte = 5;
+
+//https://www.nesdev.org/wiki/VRC7_audio
+//instrument 0 is the custom patch from registers $00-$07, 1-15 are built into the chip
+const BUILT_IN_PATCHES : [[byte; PATCH_SIZE]; 15] =
+[
+    [0x03, 0x21, 0x05, 0x06, 0xE8, 0x81, 0x42, 0x27], //buzzy bell
+    [0x13, 0x41, 0x14, 0x0D, 0xD8, 0xF6, 0x23, 0x12], //guitar
+    [0x11, 0x11, 0x08, 0x08, 0xFA, 0xB2, 0x20, 0x12], //wurly
+    [0x31, 0x61, 0x0C, 0x07, 0xA8, 0x64, 0x61, 0x27], //flute
+    [0x32, 0x21, 0x1E, 0x06, 0xE1, 0x76, 0x01, 0x28], //clarinet
+    [0x02, 0x01, 0x06, 0x00, 0xA3, 0xE2, 0xF4, 0xF4], //synth
+    [0x21, 0x61, 0x1D, 0x07, 0x82, 0x81, 0x11, 0x07], //trumpet
+    [0x23, 0x21, 0x22, 0x17, 0xA2, 0x72, 0x01, 0x17], //organ
+    [0x35, 0x11, 0x25, 0x00, 0x40, 0x73, 0x72, 0x01], //bells
+    [0xB5, 0x01, 0x0F, 0x0F, 0xA8, 0xA5, 0x51, 0x02], //vibes
+    [0x17, 0xC1, 0x24, 0x07, 0xF8, 0xF8, 0x22, 0x12], //vibraphone
+    [0x71, 0x23, 0x11, 0x06, 0x65, 0x74, 0x18, 0x16], //tutti
+    [0x01, 0x02, 0xD3, 0x05, 0xC9, 0x95, 0x03, 0x02], //fretless
+    [0x61, 0x63, 0x0C, 0x00, 0x94, 0xC0, 0x33, 0xF6], //synth bass
+    [0x21, 0x72, 0x0D, 0x00, 0xC1, 0xD5, 0x56, 0x06], //sweep
+];
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum EnvelopePhase
+{
+    Attack, Decay, Sustain, Release,
+}
+
+struct EnvelopeRates
+{
+    attack_rate : byte,
+    decay_rate : byte,
+    sustain_level : byte,
+    release_rate : byte,
+    key_off_release_rate : byte,
+    is_sustained : bool,
+}
+
+//https://www.nesdev.org/wiki/VRC7_audio#Envelope
+//attack lowers the attenuation down to 0dB, decay raises it up to the sustain level, then the envelope
+//holds it (sustained patches) or keeps decaying with the release rate (percussive patches) until key off
+struct VRC7Envelope
+{
+    phase : EnvelopePhase,
+    attenuation_in_decibels : f64,
+}
+
+impl VRC7Envelope
+{
+    fn new() -> VRC7Envelope
+    {
+        return VRC7Envelope { phase:EnvelopePhase::Release, attenuation_in_decibels:MAXIMUM_ATTENUATION_IN_DECIBELS };
+    }
+
+    fn key_on(&mut self)
+    {
+        self.phase = EnvelopePhase::Attack;
+    }
+
+    fn key_off(&mut self)
+    {
+        self.phase = EnvelopePhase::Release;
+    }
+
+    fn is_silent(&self) -> bool
+    {
+        return self.phase == EnvelopePhase::Release && self.attenuation_in_decibels >= MAXIMUM_ATTENUATION_IN_DECIBELS;
+    }
+
+    fn get_decay_step(rate : byte) -> f64
+    {
+        if rate == 0 { return 0.0 }
+        let decay_time_in_seconds = DECAY_TIME_AT_RATE_1_IN_SECONDS / ((1u32 << (rate - 1)) as f64);
+        return MAXIMUM_ATTENUATION_IN_DECIBELS / (decay_time_in_seconds * OPLL_SAMPLE_RATE);
+    }
+
+    fn step(&mut self, rates : &EnvelopeRates)
+    {
+        match self.phase
+        {
+            EnvelopePhase::Attack =>
+            {
+                if rates.attack_rate == 15 { self.attenuation_in_decibels = 0.0; }
+                else if rates.attack_rate > 0
+                {
+                    let attack_time_in_seconds = ATTACK_TIME_AT_RATE_1_IN_SECONDS / ((1u32 << (rates.attack_rate - 1)) as f64);
+                    self.attenuation_in_decibels -= MAXIMUM_ATTENUATION_IN_DECIBELS / (attack_time_in_seconds * OPLL_SAMPLE_RATE);
+                }
+
+                if self.attenuation_in_decibels <= 0.0
+                {
+                    self.attenuation_in_decibels = 0.0;
+                    self.phase = EnvelopePhase::Decay;
+                }
+            }
+            EnvelopePhase::Decay =>
+            {
+                let sustain_level_in_decibels = 3.0 * (rates.sustain_level as f64);
+                self.attenuation_in_decibels += VRC7Envelope::get_decay_step(rates.decay_rate);
+                if self.attenuation_in_decibels >= sustain_level_in_decibels
+                {
+                    self.attenuation_in_decibels = sustain_level_in_decibels;
+                    self.phase = EnvelopePhase::Sustain;
+                }
+            }
+            EnvelopePhase::Sustain if rates.is_sustained => {}
+            EnvelopePhase::Sustain => { self.attenuation_in_decibels += VRC7Envelope::get_decay_step(rates.release_rate); }
+            EnvelopePhase::Release => { self.attenuation_in_decibels += VRC7Envelope::get_decay_step(rates.key_off_release_rate); }
+        }
+
+        self.attenuation_in_decibels = self.attenuation_in_decibels.min(MAXIMUM_ATTENUATION_IN_DECIBELS);
+    }
+}
+
+//two operator FM channel: a modulator sine wave bends the phase of a carrier sine wave,
+//each operator has its own envelope
+pub struct VRC7FMSynth
+{
+    frequency_number : u16,
+    octave : byte,
+    is_key_on : bool,
+    is_sustain_on : bool,
+    instrument : byte,
+    volume : byte,
+    patch : [byte; PATCH_SIZE],
+    modulator_envelope : VRC7Envelope,
+    carrier_envelope : VRC7Envelope,
+}
+
+impl VRC7FMSynth
+{
+    pub fn new() -> VRC7FMSynth
+    {
+        return VRC7FMSynth
+        {
+            frequency_number: 0,
+            octave: 0,
+            is_key_on: false,
+            is_sustain_on: false,
+            instrument: 0,
+            volume: 0,
+            patch: [0; PATCH_SIZE],
+            modulator_envelope: VRC7Envelope::new(),
+            carrier_envelope: VRC7Envelope::new(),
+        };
+    }
+
+    pub fn set_frequency_low(self : &mut VRC7FMSynth, value : byte)
+    {
+        self.frequency_number = (self.frequency_number & 0x100) | (value as u16);
+    }
+
+    pub fn set_frequency_high(self : &mut VRC7FMSynth, value : byte)
+    {
+        let was_key_on = self.is_key_on;
+        self.is_sustain_on    = (value & 0b00100000) >> 5 == 1;
+        self.is_key_on        = (value & 0b00010000) >> 4 == 1;
+        self.octave           = (value & 0b00001110) >> 1;
+        self.frequency_number = (self.frequency_number & 0x0FF) | (((value & 0b00000001) as u16) << 8);
+
+        if !was_key_on && self.is_key_on
+        {
+            self.modulator_envelope.key_on();
+            self.carrier_envelope.key_on();
+        }
+        else if was_key_on && !self.is_key_on
+        {
+            self.modulator_envelope.key_off();
+            self.carrier_envelope.key_off();
+        }
+    }
+
+    pub fn set_instrument_and_volume(self : &mut VRC7FMSynth, value : byte, custom_patch : &[byte; PATCH_SIZE])
+    {
+        self.instrument = (value & 0b11110000) >> 4;
+        self.volume     = (value & 0b00001111) >> 0;
+        self.refresh_patch(custom_patch);
+    }
+
+    pub fn refresh_patch(self : &mut VRC7FMSynth, custom_patch : &[byte; PATCH_SIZE])
+    {
+        self.patch = if self.instrument == 0 { *custom_patch }
+            else { BUILT_IN_PATCHES[(self.instrument - 1) as usize] };
+    }
+
+    //operator 0 is the modulator, operator 1 is the carrier
+    fn get_envelope_rates(&self, operator_index : usize) -> EnvelopeRates
+    {
+        let release_rate = self.patch[6+operator_index] & 0x0F;
+        return EnvelopeRates
+        {
+            attack_rate: self.patch[4+operator_index] >> 4,
+            decay_rate: self.patch[4+operator_index] & 0x0F,
+            sustain_level: self.patch[6+operator_index] >> 4,
+            release_rate: release_rate,
+            key_off_release_rate: if self.is_sustain_on { SUSTAIN_ON_RELEASE_RATE } else { release_rate },
+            is_sustained: self.patch[operator_index] & 0b00100000 != 0,
+        };
+    }
+
+    pub fn step_envelopes(self : &mut VRC7FMSynth)
+    {
+        let modulator_rates = self.get_envelope_rates(0);
+        let carrier_rates = self.get_envelope_rates(1);
+        self.modulator_envelope.step(&modulator_rates);
+        self.carrier_envelope.step(&carrier_rates);
+    }
+}
+
+impl Synthesizer for VRC7FMSynth
+{
+    fn synthesize(&self, waveform_index : f64) -> f64
+    {
+        if self.carrier_envelope.is_silent() { return 0.0 }
+
+        let two_pi = std::f64::consts::TAU;
+        let frequency = (self.frequency_number as f64) * OPLL_SAMPLE_RATE / ((1u32 << (19 - self.octave as u32)) as f64);
+
+        let modulator_frequency = frequency * MULTIPLIERS[(self.patch[0] & 0x0F) as usize];
+        let modulator_attenuation_in_decibels = 0.75 * ((self.patch[2] & 0b00111111) as f64) + self.modulator_envelope.attenuation_in_decibels;
+        let modulator_amplitude = f64::powf(10.0, -modulator_attenuation_in_decibels / 20.0);
+        let mut modulator = f64::sin(two_pi * modulator_frequency * waveform_index);
+        if self.patch[3] & 0b00001000 != 0 { modulator = modulator.max(0.0); } //half-wave rectified modulator
+
+        let carrier_frequency = frequency * MULTIPLIERS[(self.patch[1] & 0x0F) as usize];
+        let carrier_attenuation_in_decibels = 3.0 * (self.volume as f64) + self.carrier_envelope.attenuation_in_decibels;
+        let carrier_amplitude = f64::powf(10.0, -carrier_attenuation_in_decibels / 20.0);
+        let mut carrier = f64::sin(two_pi * carrier_frequency * waveform_index + two_pi * modulator_amplitude * modulator);
+        if self.patch[3] & 0b00010000 != 0 { carrier = carrier.max(0.0); } //half-wave rectified carrier
+
+        return carrier_amplitude * carrier;
+    }
+}
+
+pub struct VRC7Audio
+{
+    selected_register : byte,
+    custom_patch : [byte; PATCH_SIZE],
+    fm_synths : [VRC7FMSynth; NUMBER_OF_CHANNELS],
+    envelope_cpu_cycle_count : u64,
+    pub is_muted : bool,
+}
+
+impl VRC7Audio
+{
+    pub fn new() -> VRC7Audio
+    {
+        return VRC7Audio
+        {
+            selected_register: 0,
+            custom_patch: [0; PATCH_SIZE],
+            fm_synths: std::array::from_fn(|_| VRC7FMSynth::new()),
+            envelope_cpu_cycle_count: 0,
+            is_muted: false,
+        };
+    }
+
+    pub fn select_register(&mut self, value : byte)
+    {
+        self.selected_register = value;
+    }
+
+    pub fn write_to_selected_register(&mut self, value : byte)
+    {
+        let register = self.selected_register;
+        let channel_index = (register & 0x0F) as usize;
+        match register & 0xF0
+        {
+            0x00 if (register as usize) < PATCH_SIZE =>
+            {
+                self.custom_patch[register as usize] = value;
+                for fm_synth in self.fm_synths.iter_mut() { fm_synth.refresh_patch(&self.custom_patch); }
+            }
+            0x10 if channel_index < NUMBER_OF_CHANNELS => { self.fm_synths[channel_index].set_frequency_low(value); }
+            0x20 if channel_index < NUMBER_OF_CHANNELS => { self.fm_synths[channel_index].set_frequency_high(value); }
+            0x30 if channel_index < NUMBER_OF_CHANNELS => { self.fm_synths[channel_index].set_instrument_and_volume(value, &self.custom_patch); }
+            _ => {}
+        }
+    }
+
+    pub fn notify_cpu_cycles(&mut self, number_of_cycles : u64)
+    {
+        self.envelope_cpu_cycle_count += number_of_cycles;
+        while self.envelope_cpu_cycle_count >= CPU_CYCLES_PER_ENVELOPE_STEP
+        {
+            self.envelope_cpu_cycle_count -= CPU_CYCLES_PER_ENVELOPE_STEP;
+            for fm_synth in self.fm_synths.iter_mut() { fm_synth.step_envelopes(); }
+        }
+    }
+}
+
+impl Synthesizer for VRC7Audio
+{
+    fn synthesize(&self, waveform_index : f64) -> f64
+    {
+        if self.is_muted { return 0.0 }
+
+        let sum = self.fm_synths.iter().map(|fm_synth| fm_synth.synthesize(waveform_index)).sum::<f64>();
+        return 0.1 * sum;
+    }
+}
+
+impl Snapshot for VRC7Envelope
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        writer.put_byte(self.phase as byte);
+        writer.put_u64(self.attenuation_in_decibels.to_bits());
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.phase = match reader.get_byte().context(codeloc!())?
+        {
+            0 => EnvelopePhase::Attack,
+            1 => EnvelopePhase::Decay,
+            2 => EnvelopePhase::Sustain,
+            _ => EnvelopePhase::Release,
+        };
+        self.attenuation_in_decibels = f64::from_bits(reader.get_u64().context(codeloc!())?);
+        return Ok(());
+    }
+}
+
+impl Snapshot for VRC7FMSynth
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        writer.put_u16(self.frequency_number);
+        writer.put_byte(self.octave);
+        writer.put_bool(self.is_key_on);
+        writer.put_bool(self.is_sustain_on);
+        writer.put_byte(self.instrument);
+        writer.put_byte(self.volume);
+        writer.put_bytes(&self.patch);
+        self.modulator_envelope.save_snapshot(writer);
+        self.carrier_envelope.save_snapshot(writer);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.frequency_number = reader.get_u16().context(codeloc!())?;
+        self.octave = reader.get_byte().context(codeloc!())?;
+        self.is_key_on = reader.get_bool().context(codeloc!())?;
+        self.is_sustain_on = reader.get_bool().context(codeloc!())?;
+        self.instrument = reader.get_byte().context(codeloc!())?;
+        self.volume = reader.get_byte().context(codeloc!())?;
+        reader.get_bytes_into(&mut self.patch).context(codeloc!())?;
+        self.modulator_envelope.load_snapshot(reader).context(codeloc!())?;
+        self.carrier_envelope.load_snapshot(reader).context(codeloc!())?;
+        return Ok(());
+    }
+}
+
+impl Snapshot for VRC7Audio
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        writer.put_byte(self.selected_register);
+        writer.put_bytes(&self.custom_patch);
+        for fm_synth in &self.fm_synths { fm_synth.save_snapshot(writer); }
+        writer.put_u64(self.envelope_cpu_cycle_count);
+        writer.put_bool(self.is_muted);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.selected_register = reader.get_byte().context(codeloc!())?;
+        reader.get_bytes_into(&mut self.custom_patch).context(codeloc!())?;
+        for fm_synth in self.fm_synths.iter_mut() { fm_synth.load_snapshot(reader).context(codeloc!())?; }
+        self.envelope_cpu_cycle_count = reader.get_u64().context(codeloc!())?;
+        self.is_muted = reader.get_bool().context(codeloc!())?;
+        return Ok(());
+    }
+}
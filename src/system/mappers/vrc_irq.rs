@@ -0,0 +1,139 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::byte;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
+
+const PRESCALER_PERIOD : i16 = 341;
+const PRESCALER_DECREMENT_PER_CPU_CYCLE : i16 = 3;
+
+//https://www.nesdev.org/wiki/VRC_IRQ
+//shared by VRC4, VRC6 and VRC7: an 8 bit up-counter clocked either every CPU cycle
+//or, through a 341/3 prescaler, once every scanline's worth of CPU cycles
+pub struct VRCIRQCounter
+{
+    latch : byte,
+    counter : byte,
+    prescaler : i16,
+    is_enabled : bool,
+    should_enable_after_acknowledge : bool,
+    is_cycle_mode_enabled : bool,
+    is_pending : bool,
+}
+
+impl VRCIRQCounter
+{
+    pub fn new() -> VRCIRQCounter
+    {
+        return VRCIRQCounter
+        {
+            latch: 0,
+            counter: 0,
+            prescaler: PRESCALER_PERIOD,
+            is_enabled: false,
+            should_enable_after_acknowledge: false,
+            is_cycle_mode_enabled: false,
+            is_pending: false,
+        };
+    }
+
+    pub fn set_latch(&mut self, value : byte)
+    {
+        self.latch = value;
+    }
+
+    pub fn set_latch_low_nibble(&mut self, value : byte)
+    {
+        self.latch = (self.latch & 0xF0) | (value & 0x0F);
+    }
+
+    pub fn set_latch_high_nibble(&mut self, value : byte)
+    {
+        self.latch = (self.latch & 0x0F) | ((value & 0x0F) << 4);
+    }
+
+    pub fn set_control(&mut self, value : byte)
+    {
+        self.should_enable_after_acknowledge = value & 0b00000001 != 0;
+        self.is_enabled = value & 0b00000010 != 0;
+        self.is_cycle_mode_enabled = value & 0b00000100 != 0;
+        self.is_pending = false;
+
+        if self.is_enabled
+        {
+            self.counter = self.latch;
+            self.prescaler = PRESCALER_PERIOD;
+        }
+    }
+
+    pub fn acknowledge(&mut self)
+    {
+        self.is_pending = false;
+        self.is_enabled = self.should_enable_after_acknowledge;
+    }
+
+    pub fn notify_cpu_cycles(&mut self, number_of_cycles : u64)
+    {
+        if !self.is_enabled { return }
+
+        for _ in 0..number_of_cycles
+        {
+            if self.is_cycle_mode_enabled
+            {
+                self.clock();
+            }
+            else
+            {
+                self.prescaler -= PRESCALER_DECREMENT_PER_CPU_CYCLE;
+                if self.prescaler <= 0
+                {
+                    self.prescaler += PRESCALER_PERIOD;
+                    self.clock();
+                }
+            }
+        }
+    }
+
+    fn clock(&mut self)
+    {
+        if self.counter == 0xFF
+        {
+            self.counter = self.latch;
+            self.is_pending = true;
+        }
+        else
+        {
+            self.counter += 1;
+        }
+    }
+
+    pub fn is_pending(&self) -> bool
+    {
+        return self.is_pending;
+    }
+}
+
+impl Snapshot for VRCIRQCounter
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        writer.put_byte(self.latch);
+        writer.put_byte(self.counter);
+        writer.put_i16(self.prescaler);
+        writer.put_bool(self.is_enabled);
+        writer.put_bool(self.should_enable_after_acknowledge);
+        writer.put_bool(self.is_cycle_mode_enabled);
+        writer.put_bool(self.is_pending);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.latch = reader.get_byte().context(codeloc!())?;
+        self.counter = reader.get_byte().context(codeloc!())?;
+        self.prescaler = reader.get_i16().context(codeloc!())?;
+        self.is_enabled = reader.get_bool().context(codeloc!())?;
+        self.should_enable_after_acknowledge = reader.get_bool().context(codeloc!())?;
+        self.is_cycle_mode_enabled = reader.get_bool().context(codeloc!())?;
+        self.is_pending = reader.get_bool().context(codeloc!())?;
+        return Ok(());
+    }
+}
@@ -1,162 +1,162 @@
-use anyhow::{anyhow, Context, Result};
-use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
-use itertools::Itertools;
-use sdl2::event::{Event, WindowEvent};
+use anyhow::{Context, Result};
 use crate::codeloc;
-use crate::system::address;
-use crate::system::debugger::{LoggingOptions, PPUDebugger};
-use crate::system::input::InputSubsystem;
+use crate::system::{address, byte, color};
+use crate::system::debugger::PPUDebugger;
+use crate::system::mappers::SharedMapper;
 use crate::system::ppu::bus::PPUBus;
-use crate::system::ppu::character_rom::CharacterROM;
 use crate::system::ppu::clock::PPUClock;
-use crate::system::ppu::flags::bus_pointer_latch::PPUBusPointerLatch;
 use crate::system::ppu::flags::control_flags::PPUControlFlags;
 use crate::system::ppu::flags::mask_flags::PPUMaskFlags;
-use crate::system::ppu::flags::scroll_flags::PPUScrollFlags;
+use crate::system::ppu::flags::scroll_registers::PPUScrollRegisters;
 use crate::system::ppu::flags::status_flags::PPUStatusFlags;
-use crate::system::ppu::metrics::WindowMetrics;
+use crate::system::ppu::framebuffer::Framebuffer;
 use crate::system::ppu::oam::PPUOAM;
-use crate::system::ppu::pattern_tables::PatternTables;
-use crate::system::ppu::rendering::PPURenderingPipeline;
-use crate::system::ppu_channels::PPUToCPUChannels;
+use crate::system::ppu::renderer::PPURenderer;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
 
 pub mod character_rom;
 pub mod bus;
 pub mod oam;
+pub mod mirroring;
 mod palette;
-mod pattern_tables;
+pub mod pattern_tables;
 mod flags;
 mod vram;
-mod metrics;
-mod communication;
-mod rendering;
+pub mod metrics;
+pub mod communication;
 mod clock;
+mod renderer;
+pub mod framebuffer;
+pub mod system_palette;
 
 pub struct PPU
 {
     pub status_flags : PPUStatusFlags,
     pub control_flags : PPUControlFlags,
     pub mask_flags : PPUMaskFlags,
-    pub scroll : PPUScrollFlags,
+    pub scroll : PPUScrollRegisters,
     pub bus : PPUBus,
     pub oam : PPUOAM,
-    pub input_subsystem : InputSubsystem,
-    pub cpu_channels : PPUToCPUChannels,
-    pub window_metrics : WindowMetrics,
     pub clock : PPUClock,
-    bus_pointer : PPUBusPointerLatch,
-    oam_pointer : address,
-}
-
-pub struct PPURunEnvironment
-{
+    pub framebuffer : Framebuffer,
     pub debugger : PPUDebugger,
-    pub logging_options : LoggingOptions,
-    pub is_shutting_down : Arc<AtomicBool>,
-    pub should_disable_video : bool,
-    pub window_title : String,
+    renderer : PPURenderer,
+    oam_pointer : address,
+    read_buffer : byte,
+    is_nmi_pending : bool,
+    is_frame_ready : bool,
+    should_suppress_vblank_flag : bool,
+    should_disable_sprite_limit : bool,
 }
 
 impl PPU
 {
-    pub fn new(character_rom : CharacterROM, channels : PPUToCPUChannels) -> PPU
+    pub fn new(mapper : SharedMapper, system_colors : Box<[color]>, debugger : PPUDebugger, should_disable_sprite_limit : bool) -> PPU
     {
-        let character_rom_hash = character_rom.hash();
-
         return PPU
         {
             status_flags: PPUStatusFlags::new(),
             control_flags: PPUControlFlags::new(),
             mask_flags: PPUMaskFlags::new(),
-            scroll: PPUScrollFlags::new(&character_rom_hash),
-            bus: PPUBus::new(character_rom),
+            scroll: PPUScrollRegisters::new(),
+            bus: PPUBus::new(mapper, system_colors),
             oam: PPUOAM::new(),
-            input_subsystem: InputSubsystem::new(),
-            cpu_channels: channels,
-            window_metrics: WindowMetrics::new(),
-            clock: PPUClock::new(&character_rom_hash),
-            bus_pointer: PPUBusPointerLatch::new(),
+            clock: PPUClock::new(),
+            framebuffer: Framebuffer::new(),
+            debugger: debugger,
+            renderer: PPURenderer::new(),
             oam_pointer: 0,
+            read_buffer: 0,
+            is_nmi_pending: false,
+            is_frame_ready: false,
+            should_suppress_vblank_flag: false,
+            should_disable_sprite_limit: should_disable_sprite_limit,
         };
     }
 
-    pub fn run(self : &mut PPU, mut env : PPURunEnvironment) -> Result<()>
+    //advances the PPU by one dot, called three times per CPU cycle by the CPU bus
+    pub fn step(self : &mut PPU)
     {
-        if env.should_disable_video { return Ok(()) }
         let ppu = self;
 
-        let sdl = sdl2::init().map_err(|msg|anyhow!(msg)).context(codeloc!())?;
-        let video_subsystem = sdl.video().map_err(|msg|anyhow!(msg)).context(codeloc!())?;
-
-        let window = video_subsystem.window(env.window_title.as_str(),
-            ppu.window_metrics.get_window_width(), ppu.window_metrics.get_window_height())
-            .position_centered().resizable().opengl().build().context(codeloc!())?;
-
-        let (opengl_driver_index, _) = sdl2::render::drivers().find_position(|d| d.name=="opengl").unwrap();
-        let mut canvas = window.into_canvas().index(opengl_driver_index as u32).accelerated().build().context(codeloc!())?;
-
-        let texture_creator = canvas.texture_creator();
-        let mut pattern_tables = PatternTables::new(&texture_creator).context(codeloc!())?;
-
-        loop
+        let ppu_clock_tick_result = ppu.clock.tick(ppu.is_rendering_enabled());
+        if ppu_clock_tick_result.should_render_dot()
         {
-            if env.is_shutting_down.load(Ordering::Relaxed) { return Ok(()) }
+            ppu.render_dot(ppu_clock_tick_result.scanline_number, ppu_clock_tick_result.dot);
+        }
 
-            let ppu_clock_tick_result = ppu.clock.tick();
-            if ppu_clock_tick_result.should_notify_visible_scanline_reached()
+        if ppu_clock_tick_result.should_notify_vblank_started()
+        {
+            ppu.is_frame_ready = true;
+            if !ppu.should_suppress_vblank_flag
             {
-                //todo implement sprite zero hit algorithm
-                if ppu.mask_flags.should_show_sprites && ppu.mask_flags.should_show_background
+                ppu.status_flags.has_vblank_started = true;
+                if ppu.is_nmi_signal_active()
                 {
-                    ppu.status_flags.is_sprite_zero_hit = true;
+                    ppu.is_nmi_pending = true;
                 }
             }
-            else if ppu_clock_tick_result.should_notify_vblank_started()
-            {
-                if ppu.bus.palette.was_recently_changed()
-                {
-                    pattern_tables.left.refresh_textures(&ppu.bus).context(codeloc!())?;
-                    pattern_tables.right.refresh_textures(&ppu.bus).context(codeloc!())?;
-                }
 
-                if let Some(mut pipeline) = PPURenderingPipeline::start(ppu, &env, &pattern_tables, &mut canvas)
-                {
-                    pipeline.render_background_sprites_from_oam();
-                    pipeline.render_background_from_nametables();
-                    pipeline.render_foreground_sprites_from_oam();
-                    pipeline.end();
-                }
+            ppu.should_suppress_vblank_flag = false;
+        }
+        else if ppu_clock_tick_result.should_notify_vblank_ended()
+        {
+            ppu.status_flags.has_vblank_started = false;
+            ppu.status_flags.is_sprite_zero_hit = false;
+            ppu.status_flags.has_sprite_overflow = false;
+        }
+    }
 
-                ppu.status_flags.has_vblank_started = true;
-                if ppu.control_flags.is_nmi_enabled
-                {
-                    ppu.cpu_channels.signal_vblank();
-                }
-            }
-            else if ppu_clock_tick_result.should_notify_vblank_ended()
-            {
-                ppu.status_flags.has_vblank_started = false;
-                ppu.status_flags.is_sprite_zero_hit = false;
-            }
+    //the NMI line is low while both the vblank flag and the NMI enable bit are set, the CPU reacts to its falling edge
+    fn is_nmi_signal_active(&self) -> bool
+    {
+        return self.status_flags.has_vblank_started && self.control_flags.is_nmi_enabled;
+    }
 
-            ppu.handle_read_commands_from_cpu();
-            ppu.handle_write_commands_from_cpu();
+    pub fn is_signaling_nmi(&mut self) -> bool
+    {
+        let is_nmi_pending = self.is_nmi_pending;
+        self.is_nmi_pending = false;
+        return is_nmi_pending;
+    }
+
+    //true once per frame, when vblank starts and the frame can be presented
+    pub fn was_frame_completed(&mut self) -> bool
+    {
+        let is_frame_ready = self.is_frame_ready;
+        self.is_frame_ready = false;
+        return is_frame_ready;
+    }
+}
 
-            ppu.input_subsystem.handle_physical_joystick_events();
+impl Snapshot for PPU
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        writer.put_byte(self.status_flags.to_byte());
+        writer.put_byte(self.control_flags.to_byte());
+        writer.put_byte(self.mask_flags.to_byte());
+        self.scroll.save_snapshot(writer);
+        self.bus.save_snapshot(writer);
+        self.oam.save_snapshot(writer);
+        self.clock.save_snapshot(writer);
+        writer.put_u16(self.oam_pointer);
+        writer.put_byte(self.read_buffer);
+        writer.put_bool(self.is_nmi_pending);
+    }
 
-            let mut sdl_event_pump = sdl.event_pump().map_err(|e|anyhow!(e.clone())).context(codeloc!())?;
-            for event in sdl_event_pump.poll_iter()
-            {
-                match event
-                {
-                    Event::KeyDown { keycode: Some(keycode), .. } => { ppu.input_subsystem.handle_physical_keyboard_keydown(keycode, &mut env); }
-                    Event::KeyUp { keycode: Some(keycode), .. } => { ppu.input_subsystem.handle_physical_keyboard_keyup(keycode, &mut env); }
-                    Event::Window { win_event: WindowEvent::Resized(w, h), .. } => { ppu.window_metrics.on_window_resized(w, h); }
-                    Event::Quit { .. } => { env.is_shutting_down.store(true, Ordering::Relaxed); return Ok(()); }
-                    _ => {}
-                }
-            }
-        }
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.status_flags = PPUStatusFlags::from_byte(reader.get_byte().context(codeloc!())?);
+        self.control_flags = PPUControlFlags::from_byte(reader.get_byte().context(codeloc!())?);
+        self.mask_flags = PPUMaskFlags::from_byte(reader.get_byte().context(codeloc!())?);
+        self.scroll.load_snapshot(reader).context(codeloc!())?;
+        self.bus.load_snapshot(reader).context(codeloc!())?;
+        self.oam.load_snapshot(reader).context(codeloc!())?;
+        self.clock.load_snapshot(reader).context(codeloc!())?;
+        self.oam_pointer = reader.get_u16().context(codeloc!())?;
+        self.read_buffer = reader.get_byte().context(codeloc!())?;
+        self.is_nmi_pending = reader.get_bool().context(codeloc!())?;
+        return Ok(());
     }
 }
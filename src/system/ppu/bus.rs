@@ -1,41 +1,35 @@
-use crate::system::{address, byte};
-use crate::system::ppu::character_rom::CharacterROM;
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::{address, byte, color};
+use crate::system::mappers::SharedMapper;
 use crate::system::ppu::palette::Palette;
 use crate::system::ppu::vram::VRAM;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
 
 pub const PATTERN_TABLE0_START_ADDRESS : address = 0x0000;
-pub const PATTERN_TABLE0_END_ADDRESS : address = 0x0FFF;
 pub const PATTERN_TABLE1_START_ADDRESS : address = 0x1000;
 pub const PATTERN_TABLE1_END_ADDRESS : address = 0x1FFF;
 pub const NAMETABLE0_START_ADDRESS : address = 0x2000;
-const NAMETABLE0_END_ADDRESS : address = 0x23FF;
-pub const NAMETABLE1_START_ADDRESS : address = 0x2400;
-const NAMETABLE1_END_ADDRESS : address = 0x27FF;
-const NAMETABLE2_START_ADDRESS : address = 0x2800;
-const NAMETABLE2_END_ADDRESS : address = 0x2BFF;
-const NAMETABLE3_START_ADDRESS : address = 0x2C00;
-const NAMETABLE3_END_ADDRESS : address = 0x2FFF;
-const NAMETABLE_MIRROR_START_ADDRESS : address = 0x3000;
 const NAMETABLE_MIRROR_END_ADDRESS : address = 0x3EFF;
-const PALETTE_START_ADDRESS : address = 0x3F00;
+pub const PALETTE_START_ADDRESS : address = 0x3F00;
 const PALETTE_END_ADDRESS : address = 0x3FFF;
 
 pub struct PPUBus
 {
     pub vram : VRAM,
-    pub character_rom : CharacterROM,
+    pub mapper : SharedMapper,
     pub palette : Palette,
 }
 
 impl PPUBus
 {
-    pub fn new(character_rom : CharacterROM) -> PPUBus
+    pub fn new(mapper : SharedMapper, system_colors : Box<[color]>) -> PPUBus
     {
         return PPUBus
         {
             vram: VRAM::new(),
-            character_rom: character_rom,
-            palette: Palette::new(),
+            mapper: mapper,
+            palette: Palette::new(system_colors),
         };
     }
 
@@ -43,44 +37,45 @@ impl PPUBus
     {
         if raw_address >= PATTERN_TABLE0_START_ADDRESS && raw_address <= PATTERN_TABLE1_END_ADDRESS
         {
-            return self.character_rom.get(raw_address);
+            return self.mapper.lock().ppu_read(raw_address);
         }
 
-        if raw_address >= NAMETABLE0_START_ADDRESS && raw_address <= NAMETABLE3_END_ADDRESS
+        if raw_address >= NAMETABLE0_START_ADDRESS && raw_address <= NAMETABLE_MIRROR_END_ADDRESS
         {
-            return self.vram.get(raw_address-NAMETABLE0_START_ADDRESS);
-        }
-
-        if raw_address >= NAMETABLE_MIRROR_START_ADDRESS && raw_address <= NAMETABLE_MIRROR_END_ADDRESS
-        {
-            let max_address = NAMETABLE3_END_ADDRESS-NAMETABLE0_START_ADDRESS+1;
-            return self.vram.get((raw_address-NAMETABLE_MIRROR_START_ADDRESS)%max_address);
+            let mirroring = self.mapper.lock().mirroring();
+            let vram_address = mirroring.translate_nametable_address(raw_address-NAMETABLE0_START_ADDRESS);
+            return self.vram.get(vram_address);
         }
 
         if raw_address >= PALETTE_START_ADDRESS && raw_address <= PALETTE_END_ADDRESS
         {
-            self.palette.get_index(raw_address-PALETTE_START_ADDRESS);
+            return self.palette.get_index(raw_address-PALETTE_START_ADDRESS);
         }
 
         return 0;
     }
 
+    //a read made by the rendering pipeline. the mapper sees the address once the value was read,
+    //MMC3 counts scanlines from these, MMC2 and MMC4 switch CHR banks after fetching tiles $FD and $FE
+    pub fn fetch(self : &PPUBus, raw_address : address) -> byte
+    {
+        let value = self.get(raw_address);
+        self.mapper.lock().notify_ppu_address_fetch(raw_address);
+        return value;
+    }
+
     pub fn put(self : &mut PPUBus, raw_address : address, value : byte)
     {
         if raw_address >= PATTERN_TABLE0_START_ADDRESS && raw_address <= PATTERN_TABLE1_END_ADDRESS
         {
-            self.character_rom.put(raw_address, value);
-        }
-
-        if raw_address >= NAMETABLE0_START_ADDRESS && raw_address <= NAMETABLE3_END_ADDRESS
-        {
-            self.vram.put(raw_address-NAMETABLE0_START_ADDRESS, value);
+            self.mapper.lock().ppu_write(raw_address, value);
         }
 
-        if raw_address >= NAMETABLE_MIRROR_START_ADDRESS && raw_address <= NAMETABLE_MIRROR_END_ADDRESS
+        if raw_address >= NAMETABLE0_START_ADDRESS && raw_address <= NAMETABLE_MIRROR_END_ADDRESS
         {
-            let max_address = NAMETABLE3_END_ADDRESS-NAMETABLE0_START_ADDRESS+1;
-            self.vram.put((raw_address-NAMETABLE_MIRROR_START_ADDRESS)%max_address, value);
+            let mirroring = self.mapper.lock().mirroring();
+            let vram_address = mirroring.translate_nametable_address(raw_address-NAMETABLE0_START_ADDRESS);
+            self.vram.put(vram_address, value);
         }
 
         if raw_address >= PALETTE_START_ADDRESS && raw_address <= PALETTE_END_ADDRESS
@@ -88,4 +83,20 @@ impl PPUBus
             self.palette.put_index(raw_address-PALETTE_START_ADDRESS, value);
         }
     }
-}
\ No newline at end of file
+}
+
+impl Snapshot for PPUBus
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        self.vram.save_snapshot(writer);
+        self.palette.save_snapshot(writer);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.vram.load_snapshot(reader).context(codeloc!())?;
+        self.palette.load_snapshot(reader).context(codeloc!())?;
+        return Ok(());
+    }
+}
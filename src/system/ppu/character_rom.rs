@@ -1,26 +1,26 @@
-use crate::system::{address, byte, mapper};
-
-pub const DONKEY_KONG_CHARACTER_ROM_HASH : &str = "c5a3bb0d1eb21b1a5bec887af7f42ad5";
-pub const PINBALL_CHARACTER_ROM_HASH : &str = "ef5d81145c203594564482ca6c301bf2";
-pub const SMB1_CHARACTER_ROM_HASH : &str = "7bbce748f81502207b5a3b87e4d3e856";
+use std::cmp;
+use anyhow::Result;
+use crate::system::{address, byte};
+use crate::system::rom::header::RomHeader;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
 
 pub struct CharacterROM
 {
-    mapper : mapper,
     is_writeable : bool,
     bytes : Box<[byte]>,
 }
 
 impl CharacterROM
 {
-    pub fn new(mapper : mapper, bytes : &[byte]) -> CharacterROM
+    pub fn new(header : &RomHeader, bytes : &[byte]) -> CharacterROM
     {
         let should_use_character_ram = bytes.is_empty();
+        let character_ram_size = cmp::max(8*1024, header.character_ram_size+header.character_nvram_size);
         let bytes = if !should_use_character_ram
             { bytes.to_owned().into_boxed_slice() } //CHR-ROM
-        else { Box::new([0; 8*1024]) }; //8kB CHR-RAM
+        else { vec![0; character_ram_size].into_boxed_slice() }; //CHR-RAM, 8kB unless the header says otherwise
 
-        return CharacterROM { mapper, is_writeable:should_use_character_ram, bytes };
+        return CharacterROM { is_writeable:should_use_character_ram, bytes };
     }
 
     pub fn get(&self, raw_address : address) -> byte
@@ -38,8 +38,43 @@ impl CharacterROM
         }
     }
 
-    pub fn hash(&self) -> String
+    pub fn number_of_banks(&self, bank_size : usize) -> usize
+    {
+        return (self.bytes.len() / bank_size).max(1);
+    }
+
+    pub fn get_from_bank(&self, bank_index : usize, bank_size : usize, offset : usize) -> byte
+    {
+        let bank_index = bank_index % self.number_of_banks(bank_size);
+        let address = bank_index * bank_size + (offset % bank_size);
+        return self.bytes[address % self.bytes.len()];
+    }
+
+    pub fn put_to_bank(&mut self, bank_index : usize, bank_size : usize, offset : usize, value : byte)
+    {
+        if self.is_writeable
+        {
+            let bank_index = bank_index % self.number_of_banks(bank_size);
+            let address = bank_index * bank_size + (offset % bank_size);
+            let address = address % self.bytes.len();
+            self.bytes[address] = value;
+        }
+    }
+}
+
+impl Snapshot for CharacterROM
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        //CHR-ROM contents come from the ROM file, only CHR-RAM has to be saved
+        writer.put_bytes(if self.is_writeable { &self.bytes } else { &[] });
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
     {
-        return format!("{:x}", md5::compute(&*self.bytes));
+        if self.is_writeable { reader.get_bytes_into(&mut self.bytes)?; }
+        else { reader.get_bytes()?; }
+
+        return Ok(());
     }
 }
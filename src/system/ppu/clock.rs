@@ -1,88 +1,120 @@
-use crate::system::ppu::character_rom::{DONKEY_KONG_CHARACTER_ROM_HASH, PINBALL_CHARACTER_ROM_HASH, SMB1_CHARACTER_ROM_HASH};
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
 
-const NUMBER_OF_VISIBLE_SCAN_LINES : usize = 240;
-const NUMBER_OF_SCAN_LINES : usize = 262;
+//https://www.nesdev.org/wiki/PPU_frame_timing
+pub const NUMBER_OF_DOTS_PER_SCANLINE : usize = 341;
+pub const NUMBER_OF_VISIBLE_SCANLINES : usize = 240;
+const NUMBER_OF_SCANLINES : usize = 262;
 
 const VBLANK_START_SCANLINE_NUMBER : usize = 241;
-const VBLANK_END_SCANLINE_NUMBER : usize = 262;
+pub const PRE_RENDER_SCANLINE_NUMBER : usize = 261;
+
+const VBLANK_FLAG_DOT_NUMBER : usize = 1;
 
 pub struct PPUClock
 {
-    cycle_count : usize,
-    scanline_cycle_count_threshold : usize,
-    current_scanline : usize,
+    dot : usize,
+    scanline : usize,
+    is_odd_frame : bool,
 }
 
 pub struct PPUClockTickResult
 {
-    should_notify_scanline_reached : bool,
+    pub dot : usize,
     pub scanline_number : usize,
 }
 
 impl PPUClockTickResult
 {
-    pub fn should_notify_visible_scanline_reached(&self) -> bool
+    pub fn should_render_dot(&self) -> bool
     {
-        return self.should_notify_scanline_reached &&
-            self.scanline_number < VBLANK_START_SCANLINE_NUMBER;
+        //visible scanlines and the pre-render scanline fetch tiles and sprites
+        return self.scanline_number < NUMBER_OF_VISIBLE_SCANLINES || self.scanline_number == PRE_RENDER_SCANLINE_NUMBER;
     }
 
     pub fn should_notify_vblank_started(&self) -> bool
     {
-        return self.should_notify_scanline_reached &&
-            self.scanline_number == VBLANK_START_SCANLINE_NUMBER;
+        return self.dot == VBLANK_FLAG_DOT_NUMBER && self.scanline_number == VBLANK_START_SCANLINE_NUMBER;
     }
 
     pub fn should_notify_vblank_ended(&self) -> bool
     {
-        return self.should_notify_scanline_reached &&
-            self.scanline_number == VBLANK_END_SCANLINE_NUMBER;
+        return self.dot == VBLANK_FLAG_DOT_NUMBER && self.scanline_number == PRE_RENDER_SCANLINE_NUMBER;
     }
 }
 
 impl PPUClock
 {
-    pub fn new(character_rom_hash : &String) -> PPUClock
+    pub fn new() -> PPUClock
     {
-        //todo thresholds should not be hardcoded, thresholds should be determined based on hardware capabilities!
-        let threshold = if character_rom_hash == DONKEY_KONG_CHARACTER_ROM_HASH { 50 }
-            else if character_rom_hash == PINBALL_CHARACTER_ROM_HASH { 10 }
-            else if character_rom_hash == SMB1_CHARACTER_ROM_HASH { 40 }
-            else { 40 };
-
         return PPUClock
         {
-            cycle_count: 0,
-            scanline_cycle_count_threshold: threshold,
-            current_scanline: 0,
+            dot: 0,
+            scanline: 0,
+            is_odd_frame: false,
         };
     }
 
-    pub fn tick(&mut self) -> PPUClockTickResult
+    //https://www.nesdev.org/wiki/PPU_frame_timing#VBL_Flag_Timing
+    pub fn is_right_before_vblank_flag_is_set(&self) -> bool
+    {
+        return self.scanline == VBLANK_START_SCANLINE_NUMBER && self.dot == VBLANK_FLAG_DOT_NUMBER-1;
+    }
+
+    pub fn is_right_after_vblank_flag_is_set(&self) -> bool
+    {
+        return self.scanline == VBLANK_START_SCANLINE_NUMBER && self.dot >= VBLANK_FLAG_DOT_NUMBER && self.dot <= VBLANK_FLAG_DOT_NUMBER+1;
+    }
+
+    pub fn is_on_rendering_scanline(&self) -> bool
+    {
+        return self.scanline < NUMBER_OF_VISIBLE_SCANLINES || self.scanline == PRE_RENDER_SCANLINE_NUMBER;
+    }
+
+    //advances by one dot. three dots pass for every CPU cycle
+    pub fn tick(&mut self, is_rendering_enabled : bool) -> PPUClockTickResult
     {
-        self.cycle_count += 1;
+        self.dot += 1;
 
-        if self.cycle_count >= self.scanline_cycle_count_threshold
+        //with rendering enabled, the pre-render scanline of odd frames is one dot shorter
+        let is_short_scanline = self.is_odd_frame && is_rendering_enabled && self.scanline == PRE_RENDER_SCANLINE_NUMBER;
+        let number_of_dots = if is_short_scanline { NUMBER_OF_DOTS_PER_SCANLINE-1 } else { NUMBER_OF_DOTS_PER_SCANLINE };
+
+        if self.dot >= number_of_dots
         {
-            self.cycle_count = 0;
-            self.current_scanline += 1;
+            self.dot = 0;
+            self.scanline += 1;
 
-            if self.current_scanline > VBLANK_END_SCANLINE_NUMBER
+            if self.scanline >= NUMBER_OF_SCANLINES
             {
-                self.current_scanline = 0;
+                self.scanline = 0;
+                self.is_odd_frame = !self.is_odd_frame;
             }
-
-            return PPUClockTickResult
-            {
-                should_notify_scanline_reached: true,
-                scanline_number: self.current_scanline,
-            };
         }
 
         return PPUClockTickResult
         {
-            should_notify_scanline_reached: false,
-            scanline_number: self.current_scanline,
+            dot: self.dot,
+            scanline_number: self.scanline,
         };
     }
 }
+
+impl Snapshot for PPUClock
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        writer.put_usize(self.dot);
+        writer.put_usize(self.scanline);
+        writer.put_bool(self.is_odd_frame);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.dot = reader.get_usize().context(codeloc!())?;
+        self.scanline = reader.get_usize().context(codeloc!())?;
+        self.is_odd_frame = reader.get_bool().context(codeloc!())?;
+        return Ok(());
+    }
+}
@@ -1,76 +1,171 @@
 use crate::system::{address, byte};
+use crate::system::ppu::bus::PALETTE_START_ADDRESS;
 use crate::system::ppu::flags::control_flags::PPUControlFlags;
 use crate::system::ppu::flags::mask_flags::PPUMaskFlags;
+use crate::system::ppu::renderer::GREYSCALE_COLOR_INDEX_MASK;
 use crate::system::ppu::PPU;
-use crate::system::ppu_channels::CPUToPPUCommTarget;
+
+const PPU_ADDRESS_MASK : address = 0x3FFF;
+const PALETTE_TO_UNDERLYING_NAMETABLE_MIRROR_OFFSET : address = 0x1000; //$3F00-$3FFF hides the nametable mirror at $2F00-$2FFF
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum CPUToPPUCommTarget
+{
+    ControlFlags,
+    MaskFlags,
+    StatusFlags,
+    OAMAddress,
+    OAMData,
+    ScrollPosition,
+    BusAddress,
+    BusData,
+    OAM_DMA,
+    Unknown,
+}
+
+impl CPUToPPUCommTarget
+{
+    pub fn from_address(address : address) -> CPUToPPUCommTarget
+    {
+        return match address
+        {
+            0x2000 => CPUToPPUCommTarget::ControlFlags,
+            0x2001 => CPUToPPUCommTarget::MaskFlags,
+            0x2002 => CPUToPPUCommTarget::StatusFlags,
+            0x2003 => CPUToPPUCommTarget::OAMAddress,
+            0x2004 => CPUToPPUCommTarget::OAMData,
+            0x2005 => CPUToPPUCommTarget::ScrollPosition,
+            0x2006 => CPUToPPUCommTarget::BusAddress,
+            0x2007 => CPUToPPUCommTarget::BusData,
+            0x4014 => CPUToPPUCommTarget::OAM_DMA,
+            _      => CPUToPPUCommTarget::Unknown,
+        }
+    }
+}
 
 impl PPU
 {
-    pub fn handle_read_commands_from_cpu(&mut self)
+    pub fn handle_read_command_from_cpu(&mut self, target : CPUToPPUCommTarget) -> byte
     {
         let ppu = self;
-        if let Ok(target) = ppu.cpu_channels.get_read_command_from_cpu()
+        return match target
         {
-            ppu.cpu_channels.respond_to_read_command_from_cpu(target, match target
+            CPUToPPUCommTarget::ControlFlags => ppu.control_flags.to_byte(),
+            CPUToPPUCommTarget::MaskFlags => ppu.mask_flags.to_byte(),
+            CPUToPPUCommTarget::StatusFlags => ppu.read_status_flags(),
+            CPUToPPUCommTarget::OAMAddress => ppu.oam_pointer as byte,
+            CPUToPPUCommTarget::OAMData => ppu.oam.get(ppu.oam_pointer),
+            CPUToPPUCommTarget::BusAddress => ppu.scroll.get_bus_address() as byte,
+            CPUToPPUCommTarget::BusData =>
             {
-                CPUToPPUCommTarget::ControlFlags => ppu.control_flags.to_byte(),
-                CPUToPPUCommTarget::MaskFlags => ppu.mask_flags.to_byte(),
-                CPUToPPUCommTarget::StatusFlags => ppu.status_flags.to_byte(),
-                CPUToPPUCommTarget::OAMAddress => ppu.oam_pointer as byte,
-                CPUToPPUCommTarget::OAMData => ppu.oam.get(ppu.oam_pointer),
-                CPUToPPUCommTarget::BusAddress => ppu.bus_pointer.as_byte(),
-                CPUToPPUCommTarget::BusData => ppu.bus.get(ppu.bus_pointer.as_address()),
-                CPUToPPUCommTarget::Joystick => ppu.input_subsystem.get_pressed_key(),
-                _ => 0,
-            });
-        }
+                let value = ppu.read_bus_data();
+                ppu.increment_bus_address();
+                value
+            }
+            _ => 0,
+        };
     }
 
-    pub fn handle_write_commands_from_cpu(&mut self)
+    pub fn handle_write_command_from_cpu(&mut self, target : CPUToPPUCommTarget, values : &[byte])
     {
         let ppu = self;
-        match ppu.cpu_channels.get_write_command_from_cpu()
+        match target
         {
-            Ok((CPUToPPUCommTarget::ControlFlags, values)) =>
+            CPUToPPUCommTarget::ControlFlags =>
             {
+                //enabling NMI while the vblank flag is still set triggers an NMI right away
+                let was_nmi_signal_active = ppu.is_nmi_signal_active();
                 ppu.control_flags = PPUControlFlags::from_byte(values[0]);
+                ppu.scroll.write_control(values[0]);
+                if !was_nmi_signal_active && ppu.is_nmi_signal_active()
+                {
+                    ppu.is_nmi_pending = true;
+                }
             }
-            Ok((CPUToPPUCommTarget::MaskFlags, values)) =>
+            CPUToPPUCommTarget::MaskFlags =>
             {
                 ppu.mask_flags = PPUMaskFlags::from_byte(values[0]);
             }
-            Ok((CPUToPPUCommTarget::OAMAddress, values)) =>
+            CPUToPPUCommTarget::OAMAddress =>
             {
                 ppu.oam_pointer = values[0] as address;
             }
-            Ok((CPUToPPUCommTarget::OAMData, values)) =>
+            CPUToPPUCommTarget::OAMData =>
             {
                 ppu.oam.put(ppu.oam_pointer, values[0]);
                 ppu.oam_pointer = ppu.oam_pointer.wrapping_add(1);
             }
-            Ok((CPUToPPUCommTarget::ScrollPosition, values)) =>
+            CPUToPPUCommTarget::ScrollPosition =>
             {
-                ppu.scroll.write(values[0] as f32);
+                ppu.scroll.write_scroll(values[0]);
             }
-            Ok((CPUToPPUCommTarget::BusAddress, values)) =>
+            CPUToPPUCommTarget::BusAddress =>
             {
-                ppu.bus_pointer.write(values[0]);
+                ppu.scroll.write_address(values[0]);
             }
-            Ok((CPUToPPUCommTarget::BusData, values)) =>
+            CPUToPPUCommTarget::BusData =>
             {
-                ppu.bus.put(ppu.bus_pointer.as_address(), values[0]);
-                ppu.bus_pointer = ppu.bus_pointer.wrapping_add(ppu.control_flags.vram_address_increment_amount as address);
+                ppu.bus.put(ppu.scroll.get_bus_address(), values[0]);
+                ppu.increment_bus_address();
             }
-            Ok((CPUToPPUCommTarget::OAM_DMA, values)) =>
+            CPUToPPUCommTarget::OAM_DMA =>
             {
-                ppu.oam.put_many(ppu.oam_pointer, &values);
+                ppu.oam.put_many(ppu.oam_pointer, values);
                 ppu.oam_pointer = ppu.oam_pointer.wrapping_add(values.len() as address);
             }
-            Ok((CPUToPPUCommTarget::Joystick, values)) =>
-            {
-                ppu.input_subsystem.set_strobe_enabled(values[0] & 0b00000001 == 1);
-            }
             _ => {}
         }
     }
+
+    //https://www.nesdev.org/wiki/PPU_registers#PPUSTATUS
+    //reading the status clears the vblank flag and the write toggle. a read one dot before the flag is set
+    //hides the flag for the whole frame, a read right when it is set still returns it, and both suppress the NMI
+    fn read_status_flags(&mut self) -> byte
+    {
+        if self.clock.is_right_before_vblank_flag_is_set()
+        {
+            self.should_suppress_vblank_flag = true;
+        }
+        else if self.clock.is_right_after_vblank_flag_is_set()
+        {
+            self.is_nmi_pending = false;
+        }
+
+        let value = self.status_flags.to_byte();
+        self.status_flags.has_vblank_started = false;
+        self.scroll.reset_write_toggle();
+        return value;
+    }
+
+    //https://www.nesdev.org/wiki/PPU_registers#The_PPUDATA_read_buffer
+    //VRAM reads return the contents of an internal buffer, which is then refilled from the current address.
+    //palette reads are immediate and go through the greyscale mask, the buffer gets the nametable byte hidden under the palette instead
+    fn read_bus_data(&mut self) -> byte
+    {
+        let bus_address = self.scroll.get_bus_address() & PPU_ADDRESS_MASK;
+        if bus_address >= PALETTE_START_ADDRESS
+        {
+            self.read_buffer = self.bus.get(bus_address - PALETTE_TO_UNDERLYING_NAMETABLE_MIRROR_OFFSET);
+            let color_index = self.bus.get(bus_address);
+            return if self.mask_flags.should_render_as_grayscale { color_index & GREYSCALE_COLOR_INDEX_MASK } else { color_index };
+        }
+
+        let value = self.read_buffer;
+        self.read_buffer = self.bus.get(bus_address);
+        return value;
+    }
+
+    fn increment_bus_address(&mut self)
+    {
+        //while rendering, a $2007 access bumps the rendering address both horizontally and vertically
+        if self.is_rendering_enabled() && self.clock.is_on_rendering_scanline()
+        {
+            self.scroll.increment_coarse_x();
+            self.scroll.increment_y();
+        }
+        else
+        {
+            self.scroll.increment_bus_address(self.control_flags.vram_address_increment_amount as address);
+        }
+    }
 }
@@ -1,5 +1,4 @@
 pub mod control_flags;
 pub mod mask_flags;
 pub mod status_flags;
-pub mod scroll_flags;
-pub mod bus_pointer_latch;
+pub mod scroll_registers;
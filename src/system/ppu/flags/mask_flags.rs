@@ -3,14 +3,14 @@ use crate::system::byte;
 #[derive(PartialEq, Eq)]
 pub struct PPUMaskFlags
 {
-    pub should_emphasize_blue : bool, //todo use this
-    pub should_emphasize_green : bool, //todo use this
-    pub should_emphasize_red : bool, //todo use this
+    pub should_emphasize_blue : bool,
+    pub should_emphasize_green : bool,
+    pub should_emphasize_red : bool,
     pub should_show_sprites : bool,
     pub should_show_background : bool,
-    pub should_show_sprites_in_leftmost_part_of_screen: bool, //todo use this
-    pub should_show_background_in_leftmost_part_of_screen: bool, //todo use this
-    pub should_render_as_grayscale : bool, //todo use this
+    pub should_show_sprites_in_leftmost_part_of_screen: bool,
+    pub should_show_background_in_leftmost_part_of_screen: bool,
+    pub should_render_as_grayscale : bool,
 }
 
 impl PPUMaskFlags
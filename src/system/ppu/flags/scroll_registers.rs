@@ -0,0 +1,189 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::{address, byte};
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
+
+//https://www.nesdev.org/wiki/PPU_scrolling
+//layout of v and t: fine Y (3 bits), nametable (2 bits), coarse Y (5 bits), coarse X (5 bits)
+const COARSE_X_MASK : address = 0b000000000011111;
+const COARSE_Y_MASK : address = 0b000001111100000;
+const NAMETABLE_MASK : address = 0b000110000000000;
+const HORIZONTAL_NAMETABLE_MASK : address = 0b000010000000000;
+const VERTICAL_NAMETABLE_MASK : address = 0b000100000000000;
+const FINE_Y_MASK : address = 0b111000000000000;
+const HORIZONTAL_POSITION_MASK : address = HORIZONTAL_NAMETABLE_MASK | COARSE_X_MASK;
+const VERTICAL_POSITION_MASK : address = FINE_Y_MASK | VERTICAL_NAMETABLE_MASK | COARSE_Y_MASK;
+const VRAM_ADDRESS_MASK : address = 0x3FFF;
+const NUMBER_OF_TILE_ROWS_IN_NAMETABLE : address = 30;
+
+const NAMETABLES_START_ADDRESS : address = 0x2000;
+const ATTRIBUTE_TABLE_OFFSET : address = 0x3C0;
+
+//the internal registers shared by $2000, $2005, $2006 and $2007 writes and $2002 reads
+pub struct PPUScrollRegisters
+{
+    pub vram_address : address,           //v, the current VRAM address, also used while rendering
+    pub temporary_vram_address : address, //t, the address of the top left onscreen tile
+    pub fine_x : byte,                    //x, the horizontal scroll inside a tile
+    is_second_write : bool,               //w, toggles between the first and second $2005/$2006 write
+}
+
+impl PPUScrollRegisters
+{
+    pub fn new() -> PPUScrollRegisters
+    {
+        return PPUScrollRegisters
+        {
+            vram_address: 0,
+            temporary_vram_address: 0,
+            fine_x: 0,
+            is_second_write: false,
+        };
+    }
+
+    pub fn write_control(&mut self, value : byte)
+    {
+        self.temporary_vram_address = (self.temporary_vram_address & !NAMETABLE_MASK) | (((value & 0b11) as address) << 10);
+    }
+
+    pub fn write_scroll(&mut self, value : byte)
+    {
+        if self.is_second_write
+        {
+            self.temporary_vram_address = (self.temporary_vram_address & !(FINE_Y_MASK | COARSE_Y_MASK))
+                | (((value & 0b111) as address) << 12) | (((value >> 3) as address) << 5);
+        }
+        else
+        {
+            self.temporary_vram_address = (self.temporary_vram_address & !COARSE_X_MASK) | ((value >> 3) as address);
+            self.fine_x = value & 0b111;
+        }
+
+        self.is_second_write = !self.is_second_write;
+    }
+
+    pub fn write_address(&mut self, value : byte)
+    {
+        if self.is_second_write
+        {
+            self.temporary_vram_address = (self.temporary_vram_address & 0xFF00) | (value as address);
+            self.vram_address = self.temporary_vram_address;
+        }
+        else
+        {
+            //the highest bit of t is cleared by the first write
+            self.temporary_vram_address = (self.temporary_vram_address & 0x00FF) | (((value & 0b111111) as address) << 8);
+        }
+
+        self.is_second_write = !self.is_second_write;
+    }
+
+    pub fn reset_write_toggle(&mut self)
+    {
+        self.is_second_write = false;
+    }
+
+    pub fn get_bus_address(&self) -> address
+    {
+        return self.vram_address & VRAM_ADDRESS_MASK;
+    }
+
+    pub fn increment_bus_address(&mut self, amount : address)
+    {
+        self.vram_address = self.vram_address.wrapping_add(amount) & 0x7FFF;
+    }
+
+    pub fn increment_coarse_x(&mut self)
+    {
+        if self.vram_address & COARSE_X_MASK == COARSE_X_MASK
+        {
+            self.vram_address &= !COARSE_X_MASK;
+            self.vram_address ^= HORIZONTAL_NAMETABLE_MASK;
+        }
+        else
+        {
+            self.vram_address += 1;
+        }
+    }
+
+    pub fn increment_y(&mut self)
+    {
+        if self.vram_address & FINE_Y_MASK != FINE_Y_MASK
+        {
+            self.vram_address += 0b001000000000000;
+            return;
+        }
+
+        self.vram_address &= !FINE_Y_MASK;
+        let mut coarse_y = (self.vram_address & COARSE_Y_MASK) >> 5;
+        if coarse_y == NUMBER_OF_TILE_ROWS_IN_NAMETABLE-1
+        {
+            coarse_y = 0;
+            self.vram_address ^= VERTICAL_NAMETABLE_MASK;
+        }
+        else if coarse_y == 31
+        {
+            //rows 30 and 31 hold the attribute table, they wrap without switching nametables
+            coarse_y = 0;
+        }
+        else
+        {
+            coarse_y += 1;
+        }
+
+        self.vram_address = (self.vram_address & !COARSE_Y_MASK) | (coarse_y << 5);
+    }
+
+    pub fn copy_horizontal_position(&mut self)
+    {
+        self.vram_address = (self.vram_address & !HORIZONTAL_POSITION_MASK) | (self.temporary_vram_address & HORIZONTAL_POSITION_MASK);
+    }
+
+    pub fn copy_vertical_position(&mut self)
+    {
+        self.vram_address = (self.vram_address & !VERTICAL_POSITION_MASK) | (self.temporary_vram_address & VERTICAL_POSITION_MASK);
+    }
+
+    pub fn get_tile_address(&self) -> address
+    {
+        return NAMETABLES_START_ADDRESS | (self.vram_address & 0x0FFF);
+    }
+
+    pub fn get_attribute_address(&self) -> address
+    {
+        let v = self.vram_address;
+        return NAMETABLES_START_ADDRESS | ATTRIBUTE_TABLE_OFFSET | (v & NAMETABLE_MASK) | ((v >> 4) & 0b111000) | ((v >> 2) & 0b000111);
+    }
+
+    //each attribute byte covers 4x4 tiles, two bits for every 2x2 tiles quadrant
+    pub fn get_attribute_shift(&self) -> byte
+    {
+        let v = self.vram_address;
+        return (((v >> 4) & 0b100) | (v & 0b010)) as byte;
+    }
+
+    pub fn get_fine_y(&self) -> address
+    {
+        return (self.vram_address & FINE_Y_MASK) >> 12;
+    }
+}
+
+impl Snapshot for PPUScrollRegisters
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        writer.put_u16(self.vram_address);
+        writer.put_u16(self.temporary_vram_address);
+        writer.put_byte(self.fine_x);
+        writer.put_bool(self.is_second_write);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        self.vram_address = reader.get_u16().context(codeloc!())?;
+        self.temporary_vram_address = reader.get_u16().context(codeloc!())?;
+        self.fine_x = reader.get_byte().context(codeloc!())?;
+        self.is_second_write = reader.get_bool().context(codeloc!())?;
+        return Ok(());
+    }
+}
@@ -0,0 +1,40 @@
+use crate::system::byte;
+use crate::system::ppu::metrics::{NES_DISPLAY_HEIGHT, NES_DISPLAY_WIDTH};
+use crate::system::ppu::palette::Palette;
+
+//the picture produced by the PPU, one system palette index per pixel:
+//the emphasis bits of PPUMASK followed by the 6 bit color index (0-511)
+pub struct Framebuffer
+{
+    pixels : Box<[u16]>,
+}
+
+impl Framebuffer
+{
+    pub fn new() -> Framebuffer
+    {
+        return Framebuffer { pixels: vec![0; (NES_DISPLAY_WIDTH as usize) * (NES_DISPLAY_HEIGHT as usize)].into_boxed_slice() };
+    }
+
+    pub fn put(&mut self, x : usize, y : usize, color_index : u16)
+    {
+        self.pixels[y * (NES_DISPLAY_WIDTH as usize) + x] = color_index;
+    }
+
+    pub fn as_slice(&self) -> &[u16]
+    {
+        return &self.pixels;
+    }
+
+    //four bytes per pixel, in red, green, blue, alpha order
+    pub fn to_rgba_bytes(&self, palette : &Palette) -> Box<[byte]>
+    {
+        return self.pixels.iter()
+            .flat_map(|color_index|
+            {
+                let color = palette.get_system_color(*color_index);
+                [(color >> 16) as byte, (color >> 8) as byte, color as byte, (color >> 24) as byte]
+            })
+            .collect();
+    }
+}
@@ -16,17 +16,6 @@ impl WindowMetrics
         return WindowMetrics { scale_x:3.0, scale_y:3.0 };
     }
 
-    pub fn get_scale(&self) -> (f32, f32) { (self.scale_x, self.scale_y) }
-    pub fn get_scale_x(&self) -> f32 { self.scale_x }
-    pub fn get_scale_y(&self) -> f32 { self.scale_y }
-
-    pub fn get_window_size(&self) -> (u32, u32) { (self.get_window_width(), self.get_window_height()) }
     pub fn get_window_width(&self) -> u32 { ((NES_DISPLAY_WIDTH as f32) * self.scale_x) as u32 }
     pub fn get_window_height(&self) -> u32 { ((NES_DISPLAY_HEIGHT as f32) * self.scale_y) as u32 }
-
-    pub fn on_window_resized(&mut self, width : i32, height : i32)
-    {
-        self.scale_x = (width as f32) / (NES_DISPLAY_WIDTH as f32);
-        self.scale_y = (height as f32) / (NES_DISPLAY_HEIGHT as f32);
-    }
 }
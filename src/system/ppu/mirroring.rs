@@ -0,0 +1,68 @@
+use anyhow::{anyhow, Result};
+use crate::system::address;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
+
+const NAMETABLE_SIZE : address = 0x400;
+const NUMBER_OF_NAMETABLES : address = 4;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Mirroring
+{
+    Horizontal,        //$2000=$2400, $2800=$2C00 (vertical scrolling games)
+    Vertical,          //$2000=$2800, $2400=$2C00 (horizontal scrolling games)
+    SingleScreenLower, //all four nametables point to the first 1kB of CIRAM
+    SingleScreenUpper, //all four nametables point to the second 1kB of CIRAM
+    FourScreen,        //four distinct nametables, extra 2kB VRAM on the cartridge
+}
+
+impl Mirroring
+{
+    //translates a nametable address relative to $2000 into an offset inside CIRAM
+    pub fn translate_nametable_address(&self, relative_address : address) -> address
+    {
+        let relative_address = relative_address % (NAMETABLE_SIZE*NUMBER_OF_NAMETABLES);
+        let logical_nametable_index = relative_address / NAMETABLE_SIZE;
+        let offset = relative_address % NAMETABLE_SIZE;
+
+        let physical_nametable_index = match self
+        {
+            Mirroring::Horizontal => logical_nametable_index / 2,
+            Mirroring::Vertical => logical_nametable_index % 2,
+            Mirroring::SingleScreenLower => 0,
+            Mirroring::SingleScreenUpper => 1,
+            Mirroring::FourScreen => logical_nametable_index,
+        };
+
+        return physical_nametable_index*NAMETABLE_SIZE + offset;
+    }
+}
+
+impl Snapshot for Mirroring
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        writer.put_byte(match self
+        {
+            Mirroring::Horizontal => 0,
+            Mirroring::Vertical => 1,
+            Mirroring::SingleScreenLower => 2,
+            Mirroring::SingleScreenUpper => 3,
+            Mirroring::FourScreen => 4,
+        });
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        *self = match reader.get_byte()?
+        {
+            0 => Mirroring::Horizontal,
+            1 => Mirroring::Vertical,
+            2 => Mirroring::SingleScreenLower,
+            3 => Mirroring::SingleScreenUpper,
+            4 => Mirroring::FourScreen,
+            value => return Err(anyhow!("Invalid mirroring {} in save state!", value)),
+        };
+
+        return Ok(());
+    }
+}
@@ -1,10 +1,15 @@
+use anyhow::Result;
 use crate::system::{address, byte};
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
 
 pub struct PPUOAM
 {
     bytes : Box<[byte]>
 }
 
+const NUMBER_OF_SPRITES : usize = 64;
+const MAX_NUMBER_OF_SPRITES_PER_SCANLINE : usize = 8;
+
 #[derive(Clone, Eq, PartialEq, Hash)]
 pub struct Sprite
 {
@@ -16,6 +21,13 @@ pub struct Sprite
     pub palette_index : byte,
     pub should_flip_horizontally : bool,
     pub should_flip_vertically : bool,
+    pub is_behind_background : bool,
+}
+
+pub struct SpriteEvaluationResult
+{
+    pub sprites : Vec<Sprite>,
+    pub has_overflow : bool,
 }
 
 impl PPUOAM
@@ -37,7 +49,7 @@ impl PPUOAM
         self.bytes[address] = value;
     }
 
-    pub fn put_many(self : &mut PPUOAM, start_raw_address : address, values : &Box<[byte]>)
+    pub fn put_many(self : &mut PPUOAM, start_raw_address : address, values : &[byte])
     {
         let mut index = (start_raw_address as usize) % self.bytes.len();
         for value in values.iter()
@@ -50,62 +62,77 @@ impl PPUOAM
         }
     }
 
-    pub fn get_8pixel_high_background_sprites(&self) -> Vec<Sprite>
+    //https://www.nesdev.org/wiki/PPU_sprite_evaluation
+    //copies the sprites of the scanline into secondary OAM, at most 8 of them unless the limit is disabled.
+    //after the 8th sprite the hardware keeps searching for overflowing sprites, but a bug makes it increment
+    //both the sprite index and the byte index inside the sprite, so it reads tile numbers, flags or X positions as Y
+    pub fn evaluate_sprites_on_scanline(&self, scanline : usize, should_use_16pixel_high_sprites : bool, should_limit_sprites : bool) -> SpriteEvaluationResult
     {
-        let flags_filter = |flags : byte| ((flags & 0b00100000) >> 5) == 1;
-        let pattern_table_index_parser = |data : byte| data as address;
+        let sprite_height = if should_use_16pixel_high_sprites { 16 } else { 8 };
+        let pattern_table_index_parser : fn(byte) -> address = if should_use_16pixel_high_sprites
+            { |data : byte| (data & 0b11111110) as address }
+        else { |data : byte| data as address };
+        let is_in_range = |y : byte| scanline >= (y as usize) && scanline < (y as usize) + sprite_height;
 
-        return self.get_sprites(flags_filter, pattern_table_index_parser);
-    }
+        let mut sprites : Vec<Sprite> = Vec::new();
+        let mut sprite_index = 0usize;
+        while sprite_index < NUMBER_OF_SPRITES && sprites.len() < MAX_NUMBER_OF_SPRITES_PER_SCANLINE
+        {
+            if is_in_range(self.bytes[sprite_index*4])
+            {
+                sprites.push(self.get_sprite(sprite_index*4, pattern_table_index_parser));
+            }
 
-    pub fn get_16pixel_high_background_sprites(&self) -> Vec<Sprite>
-    {
-        let flags_filter = |flags : byte| ((flags & 0b00100000) >> 5) == 1;
-        let pattern_table_index_parser = |data : byte| (data & 0b11111110) as address;
+            sprite_index += 1;
+        }
 
-        return self.get_sprites(flags_filter, pattern_table_index_parser);
-    }
+        let mut has_overflow = false;
+        let mut byte_index = 0usize;
+        for sprite_index in sprite_index..NUMBER_OF_SPRITES
+        {
+            if !should_limit_sprites && is_in_range(self.bytes[sprite_index*4])
+            {
+                sprites.push(self.get_sprite(sprite_index*4, pattern_table_index_parser));
+            }
 
-    pub fn get_8pixel_high_foreground_sprites(&self) -> Vec<Sprite>
-    {
-        let flags_filter = |flags : byte| ((flags & 0b00100000) >> 5) == 0;
-        let pattern_table_index_parser = |data : byte| data as address;
+            if !has_overflow
+            {
+                if is_in_range(self.bytes[sprite_index*4 + byte_index]) { has_overflow = true; }
+                else { byte_index = (byte_index+1) % 4; }
+            }
+        }
 
-        return self.get_sprites(flags_filter, pattern_table_index_parser);
+        return SpriteEvaluationResult { sprites, has_overflow };
     }
 
-    pub fn get_16pixel_high_foreground_sprites(&self) -> Vec<Sprite>
+    fn get_sprite(&self, index : usize, pattern_table_index_parser : fn(byte) -> address) -> Sprite
     {
-        let flags_filter = |flags : byte| ((flags & 0b00100000) >> 5) == 0;
-        let pattern_table_index_parser = |data : byte| (data & 0b11111110) as address;
-
-        return self.get_sprites(flags_filter, pattern_table_index_parser);
+        let pattern_table_data = self.bytes[index+1];
+        let flags = self.bytes[index+2];
+        return Sprite
+        {
+            index: index as byte,
+            x: self.bytes[index+3],
+            y: self.bytes[index],
+            should_use_right_pattern_table: pattern_table_data & 0b00000001 == 1,
+            pattern_table_index: pattern_table_index_parser(pattern_table_data),
+            palette_index: flags & 0b00000011,
+            should_flip_horizontally: (flags & 0b01000000) >> 6 == 1,
+            should_flip_vertically: (flags & 0b10000000) >> 7 == 1,
+            is_behind_background: (flags & 0b00100000) >> 5 == 1,
+        };
     }
+}
 
-    fn get_sprites(&self, flags_filter : fn(byte) -> bool, pattern_table_index_parser : fn(byte) -> address) -> Vec<Sprite>
+impl Snapshot for PPUOAM
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
     {
-        let mut sprites : Vec<Sprite> = Vec::new();
-
-        for index in (0..self.bytes.len()).step_by(4)
-        {
-            let flags = self.bytes[index+2];
-            if flags_filter(flags)
-            {
-                let pattern_table_data = self.bytes[index+1];
-                sprites.push(Sprite
-                {
-                    index: index as byte,
-                    x: self.bytes[index+3],
-                    y: self.bytes[index],
-                    should_use_right_pattern_table: pattern_table_data & 0b00000001 == 1,
-                    pattern_table_index: pattern_table_index_parser(pattern_table_data),
-                    palette_index: flags & 0b00000011,
-                    should_flip_horizontally: (flags & 0b01000000) >> 6 == 1,
-                    should_flip_vertically: (flags & 0b10000000) >> 7 == 1,
-                });
-            }
-        }
+        writer.put_bytes(&self.bytes);
+    }
 
-        return sprites;
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        return reader.get_bytes_into(&mut self.bytes);
     }
 }
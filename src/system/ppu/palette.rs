@@ -1,55 +1,61 @@
+use anyhow::Result;
 use crate::system::{address, byte, color};
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
 
-const PALETTE_COLORS_SIZE : usize = 64;
 const PALETTE_INDICES_SIZE : usize = 0x20;
+const SPRITE_PALETTES_OFFSET : usize = 0x10;
+const COLOR_INDEX_MASK : byte = 0b00111111;
 
 pub struct Palette
 {
-    was_recently_changed : bool,
-    colors : [color; PALETTE_COLORS_SIZE],
+    colors : Box<[color]>,
     indices : [byte; PALETTE_INDICES_SIZE]
 }
 
 impl Palette
 {
-    pub fn new() -> Palette
+    //colors holds the 512 system colors, see SystemPalette
+    pub fn new(colors : Box<[color]>) -> Palette
     {
-        let mut colors = [0 as color; PALETTE_COLORS_SIZE];
-        let colors_bytes = *include_bytes!("palette.pal");
-        for i in 0..PALETTE_COLORS_SIZE
-        {
-            let red = colors_bytes[i*3] as color;
-            let green = colors_bytes[i*3+1] as color;
-            let blue = colors_bytes[i*3+2] as color;
-            colors[i] = (0xFF << 24) | (red << 16) | (green << 8) | blue;
-        }
-
         let mut indices = [0 as byte; PALETTE_INDICES_SIZE];
         for i in 0..indices.len() { indices[i] = i as byte; }
-        return Palette { was_recently_changed:true, colors, indices };
+        return Palette { colors, indices };
+    }
+
+    //https://www.nesdev.org/wiki/PPU_palettes#Memory_Map
+    //$3F10, $3F14, $3F18 and $3F1C are mirrors of $3F00, $3F04, $3F08 and $3F0C
+    fn translate_address(raw_address : address) -> usize
+    {
+        let address = (raw_address as usize) % PALETTE_INDICES_SIZE;
+        return if address >= SPRITE_PALETTES_OFFSET && address % 4 == 0 { address - SPRITE_PALETTES_OFFSET } else { address };
     }
 
     pub fn get_index(&self, raw_address : address) -> byte
     {
-        return self.indices[(raw_address as usize) % self.indices.len()];
+        return self.indices[Palette::translate_address(raw_address)];
     }
 
-    pub fn get_color(&self, raw_address : address) -> color
+    pub fn get_system_color(&self, index : u16) -> color
     {
-        let index = self.get_index(raw_address) as usize;
-        return self.colors[index % self.colors.len()];
+        return self.colors[(index as usize) % self.colors.len()];
     }
 
     pub fn put_index(&mut self, raw_address : address, index : byte)
     {
-        self.indices[(raw_address as usize) % self.indices.len()] = index;
-        self.was_recently_changed = true;
+        self.indices[Palette::translate_address(raw_address)] = index & COLOR_INDEX_MASK;
+    }
+}
+
+impl Snapshot for Palette
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        writer.put_bytes(&self.indices);
     }
 
-    pub fn was_recently_changed(&mut self) -> bool
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
     {
-        let was_recently_changed_prev = self.was_recently_changed;
-        self.was_recently_changed = false;
-        return was_recently_changed_prev;
+        reader.get_bytes_into(&mut self.indices)?;
+        return Ok(());
     }
 }
@@ -0,0 +1,346 @@
+use crate::system::{address, byte};
+use crate::system::ppu::bus::PATTERN_TABLE1_START_ADDRESS;
+use crate::system::ppu::clock::{NUMBER_OF_VISIBLE_SCANLINES, PRE_RENDER_SCANLINE_NUMBER};
+use crate::system::ppu::oam::Sprite;
+use crate::system::ppu::PPU;
+
+const TILE_SIZE_IN_BYTES : address = 16;
+const TILE_PLANE_SIZE_IN_BYTES : address = 8;
+const TILE_SIZE_IN_PIXELS : usize = 8;
+const UNUSED_SPRITE_SLOT_TILE_INDEX : address = 0xFF;
+const NUMBER_OF_SPRITE_FETCH_SLOTS : usize = 8;
+const SPRITE_PALETTES_OFFSET : byte = 0x10;
+const LAST_COLUMN_INDEX : usize = 255;
+pub const GREYSCALE_COLOR_INDEX_MASK : byte = 0x30;
+
+struct ScanlineSprite
+{
+    sprite : Sprite,
+    pattern_low : byte,
+    pattern_high : byte,
+}
+
+//https://www.nesdev.org/wiki/PPU_rendering
+//the background is drawn through two 16 bit pattern shift registers, fed with a new tile every 8 dots.
+//sprites are evaluated one scanline ahead, their patterns are fetched during dots 257-320
+pub struct PPURenderer
+{
+    next_tile_index : byte,
+    next_tile_attribute : byte,
+    next_tile_pattern_low : byte,
+    next_tile_pattern_high : byte,
+    pattern_low_shift_register : u16,
+    pattern_high_shift_register : u16,
+    attribute_low_shift_register : u16,
+    attribute_high_shift_register : u16,
+    next_scanline_sprites : Vec<ScanlineSprite>,
+    scanline_sprites : Vec<ScanlineSprite>,
+}
+
+impl PPURenderer
+{
+    pub fn new() -> PPURenderer
+    {
+        return PPURenderer
+        {
+            next_tile_index: 0,
+            next_tile_attribute: 0,
+            next_tile_pattern_low: 0,
+            next_tile_pattern_high: 0,
+            pattern_low_shift_register: 0,
+            pattern_high_shift_register: 0,
+            attribute_low_shift_register: 0,
+            attribute_high_shift_register: 0,
+            next_scanline_sprites: Vec::new(),
+            scanline_sprites: Vec::new(),
+        };
+    }
+
+    fn shift_background_registers(&mut self)
+    {
+        self.pattern_low_shift_register <<= 1;
+        self.pattern_high_shift_register <<= 1;
+        self.attribute_low_shift_register <<= 1;
+        self.attribute_high_shift_register <<= 1;
+    }
+
+    fn load_background_registers(&mut self)
+    {
+        self.pattern_low_shift_register = (self.pattern_low_shift_register & 0xFF00) | (self.next_tile_pattern_low as u16);
+        self.pattern_high_shift_register = (self.pattern_high_shift_register & 0xFF00) | (self.next_tile_pattern_high as u16);
+        self.attribute_low_shift_register = (self.attribute_low_shift_register & 0xFF00) | if self.next_tile_attribute & 0b01 != 0 { 0xFF } else { 0x00 };
+        self.attribute_high_shift_register = (self.attribute_high_shift_register & 0xFF00) | if self.next_tile_attribute & 0b10 != 0 { 0xFF } else { 0x00 };
+    }
+}
+
+impl PPU
+{
+    pub fn is_rendering_enabled(&self) -> bool
+    {
+        return self.mask_flags.should_show_background || self.mask_flags.should_show_sprites;
+    }
+
+    //called for every dot of the visible and pre-render scanlines
+    pub fn render_dot(&mut self, scanline : usize, dot : usize)
+    {
+        let is_visible_scanline = scanline < NUMBER_OF_VISIBLE_SCANLINES;
+        if is_visible_scanline && dot >= 1 && dot <= 256 && !self.is_rendering_enabled()
+        {
+            let backdrop_color = self.get_output_color_index(0);
+            self.framebuffer.put(dot-1, scanline, backdrop_color);
+        }
+
+        if !self.is_rendering_enabled() { return }
+
+        if (dot >= 2 && dot <= 257) || (dot >= 322 && dot <= 337)
+        {
+            self.renderer.shift_background_registers();
+        }
+
+        if ((dot >= 9 && dot <= 257) || (dot >= 329 && dot <= 337)) && dot % 8 == 1
+        {
+            self.renderer.load_background_registers();
+        }
+
+        if (dot >= 1 && dot <= 256) || (dot >= 321 && dot <= 336)
+        {
+            self.fetch_background_tile(dot);
+        }
+
+        if dot == 256
+        {
+            self.scroll.increment_y();
+        }
+        else if dot == 257
+        {
+            self.scroll.copy_horizontal_position();
+            self.evaluate_sprites(scanline);
+        }
+        else if scanline == PRE_RENDER_SCANLINE_NUMBER && dot >= 280 && dot <= 304
+        {
+            self.scroll.copy_vertical_position();
+        }
+        else if dot == 321
+        {
+            self.renderer.scanline_sprites = std::mem::take(&mut self.renderer.next_scanline_sprites);
+        }
+        else if dot == 337 || dot == 339
+        {
+            //two unused nametable fetches end the scanline
+            self.bus.fetch(self.scroll.get_tile_address());
+        }
+
+        if dot >= 257 && dot <= 320
+        {
+            self.fetch_sprites(scanline, dot);
+        }
+
+        if is_visible_scanline && dot >= 1 && dot <= 256
+        {
+            let color_index = self.compose_pixel(dot-1);
+            self.framebuffer.put(dot-1, scanline, color_index);
+        }
+    }
+
+    fn fetch_background_tile(&mut self, dot : usize)
+    {
+        match (dot-1) % 8
+        {
+            0 =>
+            {
+                self.renderer.next_tile_index = self.bus.fetch(self.scroll.get_tile_address());
+            }
+            2 =>
+            {
+                let attribute = self.bus.fetch(self.scroll.get_attribute_address());
+                self.renderer.next_tile_attribute = (attribute >> self.scroll.get_attribute_shift()) & 0b11;
+            }
+            4 =>
+            {
+                let pattern_address = self.get_background_pattern_address();
+                self.renderer.next_tile_pattern_low = self.bus.fetch(pattern_address);
+            }
+            6 =>
+            {
+                let pattern_address = self.get_background_pattern_address();
+                self.renderer.next_tile_pattern_high = self.bus.fetch(pattern_address + TILE_PLANE_SIZE_IN_BYTES);
+            }
+            7 =>
+            {
+                self.scroll.increment_coarse_x();
+            }
+            _ => {}
+        }
+    }
+
+    fn get_background_pattern_address(&self) -> address
+    {
+        return self.control_flags.base_pattern_table_address_for_background
+            + (self.renderer.next_tile_index as address) * TILE_SIZE_IN_BYTES + self.scroll.get_fine_y();
+    }
+
+    fn evaluate_sprites(&mut self, scanline : usize)
+    {
+        //sprites found now are drawn on the next scanline, nothing is drawn on scanline 0
+        if scanline == PRE_RENDER_SCANLINE_NUMBER
+        {
+            self.renderer.next_scanline_sprites.clear();
+            return;
+        }
+
+        let result = self.oam.evaluate_sprites_on_scanline(scanline,
+            self.control_flags.should_use_16pixel_high_sprites, !self.should_disable_sprite_limit);
+        if result.has_overflow { self.status_flags.has_sprite_overflow = true; }
+
+        self.renderer.next_scanline_sprites = result.sprites.into_iter()
+            .map(|sprite| ScanlineSprite { sprite, pattern_low: 0, pattern_high: 0 })
+            .collect();
+    }
+
+    fn fetch_sprites(&mut self, scanline : usize, dot : usize)
+    {
+        //each of the 8 slots fetches two garbage nametable bytes and the two planes of a sprite row
+        let slot_index = (dot-257) / 8;
+        match (dot-257) % 8
+        {
+            0 | 2 =>
+            {
+                self.bus.fetch(self.scroll.get_tile_address());
+            }
+            4 | 6 =>
+            {
+                let plane_offset = if (dot-257) % 8 == 4 { 0 } else { TILE_PLANE_SIZE_IN_BYTES };
+                let pattern_address = match self.renderer.next_scanline_sprites.get(slot_index)
+                {
+                    Some(scanline_sprite) => self.get_sprite_pattern_address(&scanline_sprite.sprite, scanline),
+                    None => self.get_unused_sprite_slot_pattern_address(),
+                };
+
+                let pattern = self.bus.fetch(pattern_address + plane_offset);
+                if let Some(scanline_sprite) = self.renderer.next_scanline_sprites.get_mut(slot_index)
+                {
+                    let pattern = if scanline_sprite.sprite.should_flip_horizontally { pattern.reverse_bits() } else { pattern };
+                    if plane_offset == 0 { scanline_sprite.pattern_low = pattern; } else { scanline_sprite.pattern_high = pattern; }
+                }
+            }
+            _ => {}
+        }
+
+        if dot == 320
+        {
+            //with the sprite limit disabled, sprites past the eighth one have no fetch slot left
+            for slot_index in NUMBER_OF_SPRITE_FETCH_SLOTS..self.renderer.next_scanline_sprites.len()
+            {
+                let sprite = &self.renderer.next_scanline_sprites[slot_index].sprite;
+                let pattern_address = self.get_sprite_pattern_address(sprite, scanline);
+                let (low, high) = (self.bus.get(pattern_address), self.bus.get(pattern_address + TILE_PLANE_SIZE_IN_BYTES));
+
+                let scanline_sprite = &mut self.renderer.next_scanline_sprites[slot_index];
+                let should_flip = scanline_sprite.sprite.should_flip_horizontally;
+                scanline_sprite.pattern_low = if should_flip { low.reverse_bits() } else { low };
+                scanline_sprite.pattern_high = if should_flip { high.reverse_bits() } else { high };
+            }
+        }
+    }
+
+    fn get_sprite_pattern_address(&self, sprite : &Sprite, scanline : usize) -> address
+    {
+        let sprite_height = if self.control_flags.should_use_16pixel_high_sprites { 2*TILE_SIZE_IN_PIXELS } else { TILE_SIZE_IN_PIXELS };
+        let row = scanline - (sprite.y as usize);
+        let row = (if sprite.should_flip_vertically { sprite_height - 1 - row } else { row }) as address;
+
+        let (pattern_table_address, tile_index) = if self.control_flags.should_use_16pixel_high_sprites
+        {
+            let pattern_table_address = if sprite.should_use_right_pattern_table { PATTERN_TABLE1_START_ADDRESS } else { 0x0000 };
+            (pattern_table_address, sprite.pattern_table_index + row / (TILE_SIZE_IN_PIXELS as address))
+        }
+        else { (self.control_flags.base_pattern_table_address_for_foreground, sprite.pattern_table_index) };
+
+        return pattern_table_address + tile_index * TILE_SIZE_IN_BYTES + (row % (TILE_SIZE_IN_PIXELS as address));
+    }
+
+    fn get_unused_sprite_slot_pattern_address(&self) -> address
+    {
+        //empty sprite slots fetch tile $FF, which lives in the right pattern table for 8x16 sprites
+        return if self.control_flags.should_use_16pixel_high_sprites { PATTERN_TABLE1_START_ADDRESS + (UNUSED_SPRITE_SLOT_TILE_INDEX & 0b11111110) * TILE_SIZE_IN_BYTES }
+        else { self.control_flags.base_pattern_table_address_for_foreground + UNUSED_SPRITE_SLOT_TILE_INDEX * TILE_SIZE_IN_BYTES };
+    }
+
+    fn get_background_pixel(&self, x : usize) -> (byte, byte)
+    {
+        if !self.mask_flags.should_show_background { return (0, 0) }
+        if x < TILE_SIZE_IN_PIXELS && !self.mask_flags.should_show_background_in_leftmost_part_of_screen { return (0, 0) }
+
+        let bit_mask = 0x8000u16 >> self.scroll.fine_x;
+        let pixel = (((self.renderer.pattern_high_shift_register & bit_mask) != 0) as byte) << 1
+            | (((self.renderer.pattern_low_shift_register & bit_mask) != 0) as byte);
+        let palette = (((self.renderer.attribute_high_shift_register & bit_mask) != 0) as byte) << 1
+            | (((self.renderer.attribute_low_shift_register & bit_mask) != 0) as byte);
+        return (pixel, palette);
+    }
+
+    fn compose_pixel(&mut self, x : usize) -> u16
+    {
+        let (background_pixel, background_palette) = self.get_background_pixel(x);
+
+        let mut sprite_pixel : Option<(byte, byte, bool)> = None;
+        let mut is_sprite_zero_opaque = false;
+        let are_sprites_visible = self.mask_flags.should_show_sprites
+            && (x >= TILE_SIZE_IN_PIXELS || self.mask_flags.should_show_sprites_in_leftmost_part_of_screen);
+
+        if are_sprites_visible
+        {
+            //the opaque pixel of the sprite with the lowest OAM index wins, even if it is behind the background
+            for scanline_sprite in &self.renderer.scanline_sprites
+            {
+                let column = x.wrapping_sub(scanline_sprite.sprite.x as usize);
+                if column >= TILE_SIZE_IN_PIXELS { continue }
+
+                let bit = 7 - column;
+                let pixel = ((scanline_sprite.pattern_high >> bit) & 1) << 1 | ((scanline_sprite.pattern_low >> bit) & 1);
+                if pixel == 0 { continue }
+
+                if scanline_sprite.sprite.index == 0 { is_sprite_zero_opaque = true; }
+                if sprite_pixel.is_none()
+                {
+                    let sprite = &scanline_sprite.sprite;
+                    sprite_pixel = Some((pixel, sprite.palette_index, sprite.is_behind_background));
+                }
+            }
+        }
+
+        //https://www.nesdev.org/wiki/PPU_OAM#Sprite_zero_hits
+        //an opaque pixel of sprite zero over an opaque background pixel, never at the rightmost column
+        if is_sprite_zero_opaque && background_pixel != 0 && x != LAST_COLUMN_INDEX
+        {
+            self.status_flags.is_sprite_zero_hit = true;
+        }
+
+        //the debugger hides layers from the picture only, sprite zero hits still happen
+        let background_pixel = if self.debugger.should_render_background { background_pixel } else { 0 };
+        let sprite_pixel = if self.debugger.should_render_sprites { sprite_pixel } else { None };
+
+        //https://www.nesdev.org/wiki/PPU_sprite_priority
+        let palette_address = match sprite_pixel
+        {
+            Some((pixel, palette, is_behind_background)) if !is_behind_background || background_pixel == 0 => SPRITE_PALETTES_OFFSET + palette * 4 + pixel,
+            _ if background_pixel != 0 => background_palette * 4 + background_pixel,
+            _ => 0,
+        };
+
+        return self.get_output_color_index(palette_address as address);
+    }
+
+    fn get_output_color_index(&self, palette_address : address) -> u16
+    {
+        let color_index = self.bus.palette.get_index(palette_address);
+
+        //greyscale keeps only the brightness of the color, the grey column of the system palette
+        let color_index = if self.mask_flags.should_render_as_grayscale { color_index & GREYSCALE_COLOR_INDEX_MASK } else { color_index };
+
+        let emphasis = ((self.mask_flags.should_emphasize_blue as u16) << 2)
+            | ((self.mask_flags.should_emphasize_green as u16) << 1)
+            | (self.mask_flags.should_emphasize_red as u16);
+        return (emphasis << 6) | (color_index as u16);
+    }
+}
@@ -0,0 +1,149 @@
+use std::f64::consts::PI;
+use std::fs;
+use std::path::PathBuf;
+use anyhow::{anyhow, Context, Result};
+use crate::codeloc;
+use crate::system::{byte, color};
+
+pub const NUMBER_OF_BASE_COLORS : usize = 64;
+pub const NUMBER_OF_EMPHASIS_COMBINATIONS : usize = 8;
+pub const NUMBER_OF_SYSTEM_COLORS : usize = NUMBER_OF_BASE_COLORS * NUMBER_OF_EMPHASIS_COMBINATIONS;
+
+const EMPHASIS_RED_BIT : usize = 0b001;
+const EMPHASIS_GREEN_BIT : usize = 0b010;
+const EMPHASIS_BLUE_BIT : usize = 0b100;
+
+//an emphasis bit darkens the other two color channels
+const EMPHASIS_ATTENUATION : f64 = 0.746;
+
+//https://www.nesdev.org/wiki/PPU_palettes
+//the 2C02 outputs 64 colors, each one in 8 variants given by the emphasis bits of PPUMASK.
+//colors are stored in the order of .pal files: emphasis * 64 + color index
+#[derive(Clone)]
+pub enum SystemPalette
+{
+    Default,
+    NTSCDecoder,
+    File(PathBuf),
+}
+
+impl SystemPalette
+{
+    pub fn parse(value : &str) -> SystemPalette
+    {
+        return match value
+        {
+            "default" => SystemPalette::Default,
+            "ntsc" => SystemPalette::NTSCDecoder,
+            _ => SystemPalette::File(PathBuf::from(value)),
+        };
+    }
+
+    pub fn load_colors(&self) -> Result<Box<[color]>>
+    {
+        return match self
+        {
+            SystemPalette::Default => parse_pal_file("palette.pal", include_bytes!("palette.pal")),
+            SystemPalette::NTSCDecoder => Ok(generate_ntsc_decoder_colors()),
+            SystemPalette::File(path) =>
+            {
+                let bytes = fs::read(path).context(codeloc!())?;
+                parse_pal_file(&path.display().to_string(), &bytes)
+            }
+        };
+    }
+}
+
+//a .pal file holds RGB triplets, either the 64 base colors or all the 512 emphasized colors
+fn parse_pal_file(file_name : &str, bytes : &[byte]) -> Result<Box<[color]>>
+{
+    let colors = bytes.chunks_exact(3)
+        .map(|rgb| (0xFF << 24) | ((rgb[0] as color) << 16) | ((rgb[1] as color) << 8) | (rgb[2] as color))
+        .collect::<Vec<color>>();
+
+    return match bytes.len()
+    {
+        length if length == NUMBER_OF_BASE_COLORS*3 => Ok(emphasize_base_colors(&colors)),
+        length if length == NUMBER_OF_SYSTEM_COLORS*3 => Ok(colors.into_boxed_slice()),
+        length => Err(anyhow!("Invalid palette file {}: expected {} bytes ({} colors) or {} bytes ({} colors), found {}!",
+            file_name, NUMBER_OF_BASE_COLORS*3, NUMBER_OF_BASE_COLORS, NUMBER_OF_SYSTEM_COLORS*3, NUMBER_OF_SYSTEM_COLORS, length)),
+    };
+}
+
+fn emphasize_base_colors(base_colors : &[color]) -> Box<[color]>
+{
+    let mut colors : Vec<color> = Vec::with_capacity(NUMBER_OF_SYSTEM_COLORS);
+    for emphasis in 0..NUMBER_OF_EMPHASIS_COMBINATIONS
+    {
+        let attenuate = |channel : color, emphasis_bit : usize| -> color
+        {
+            let is_attenuated = emphasis != 0 && emphasis & emphasis_bit == 0;
+            return if is_attenuated { ((channel as f64) * EMPHASIS_ATTENUATION) as color } else { channel };
+        };
+
+        for base_color in base_colors
+        {
+            let red = attenuate((base_color >> 16) & 0xFF, EMPHASIS_RED_BIT);
+            let green = attenuate((base_color >> 8) & 0xFF, EMPHASIS_GREEN_BIT);
+            let blue = attenuate(base_color & 0xFF, EMPHASIS_BLUE_BIT);
+            colors.push((0xFF << 24) | (red << 16) | (green << 8) | blue);
+        }
+    }
+
+    return colors.into_boxed_slice();
+}
+
+//https://www.nesdev.org/wiki/NTSC_video#Emulating_in_C++_code
+//decodes the square wave the PPU sends to the TV, twelve samples per pixel, into YIQ and then RGB
+fn generate_ntsc_decoder_colors() -> Box<[color]>
+{
+    const LOW_LEVELS : [f64; 4] = [0.228, 0.312, 0.552, 0.880];
+    const HIGH_LEVELS : [f64; 4] = [0.616, 0.840, 1.100, 1.100];
+    const BLACK_LEVEL : f64 = LOW_LEVELS[1];
+    const WHITE_LEVEL : f64 = HIGH_LEVELS[3];
+
+    let is_in_color_phase = |phase : usize, hue : usize| (hue + phase + 8) % 12 < 6;
+    let gamma_fix = |value : f64| if value <= 0.0 { 0.0 } else { value.powf(2.2 / 1.8) };
+    let to_channel = |value : f64| (gamma_fix(value) * 255.0).clamp(0.0, 255.0) as color;
+
+    let mut colors : Vec<color> = Vec::with_capacity(NUMBER_OF_SYSTEM_COLORS);
+    for emphasis in 0..NUMBER_OF_EMPHASIS_COMBINATIONS
+    {
+        for color_index in 0..NUMBER_OF_BASE_COLORS
+        {
+            let hue = color_index & 0x0F;
+            let level = if hue < 0x0E { (color_index >> 4) & 0b11 } else { 1 };
+
+            let mut low = LOW_LEVELS[level];
+            let mut high = HIGH_LEVELS[level];
+            if hue == 0x00 { low = high; }
+            if hue == 0x0D { high = low; }
+            if hue >= 0x0E { low = BLACK_LEVEL; high = BLACK_LEVEL; }
+
+            let (mut y, mut i, mut q) = (0.0, 0.0, 0.0);
+            for phase in 0..12
+            {
+                let mut signal = if is_in_color_phase(phase, hue) { high } else { low };
+
+                if (emphasis & EMPHASIS_RED_BIT != 0 && is_in_color_phase(phase, 12))
+                    || (emphasis & EMPHASIS_GREEN_BIT != 0 && is_in_color_phase(phase, 4))
+                    || (emphasis & EMPHASIS_BLUE_BIT != 0 && is_in_color_phase(phase, 8))
+                {
+                    signal *= EMPHASIS_ATTENUATION;
+                }
+
+                let value = (signal - BLACK_LEVEL) / (WHITE_LEVEL - BLACK_LEVEL) / 12.0;
+                y += value;
+                i += value * (PI * (phase as f64) / 6.0).cos();
+                q += value * (PI * (phase as f64) / 6.0).sin();
+            }
+
+            let red = to_channel(y + 0.946882*i + 0.623557*q);
+            let green = to_channel(y - 0.274788*i - 0.635691*q);
+            let blue = to_channel(y - 1.108545*i + 1.709007*q);
+            colors.push((0xFF << 24) | (red << 16) | (green << 8) | blue);
+        }
+    }
+
+    return colors.into_boxed_slice();
+}
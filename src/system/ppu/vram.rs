@@ -1,4 +1,6 @@
+use anyhow::Result;
 use crate::system::{address, byte};
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
 
 pub struct VRAM
 {
@@ -9,7 +11,8 @@ impl VRAM
 {
     pub fn new() -> VRAM
     {
-        return VRAM { bytes: Box::new([0; 16*1024]) }; //16kB
+        //2kB CIRAM inside the console + 2kB cartridge VRAM, used only by four-screen boards
+        return VRAM { bytes: Box::new([0; 4*1024]) };
     }
 
     pub fn get(self : &VRAM, raw_address : address) -> byte
@@ -24,3 +27,16 @@ impl VRAM
         self.bytes[address] = value;
     }
 }
+
+impl Snapshot for VRAM
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter)
+    {
+        writer.put_bytes(&self.bytes);
+    }
+
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>
+    {
+        return reader.get_bytes_into(&mut self.bytes);
+    }
+}
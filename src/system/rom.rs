@@ -1,13 +1,19 @@
 use std::cmp;
-use anyhow::{anyhow, Result};
-use crate::system::{byte, mapper};
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::byte;
+use crate::system::cpu::program_ram::ProgramRAM;
 use crate::system::cpu::program_rom::ProgramROM;
 use crate::system::ppu::character_rom::CharacterROM;
+use crate::system::rom::header::{RomHeader, TRAINER_SIZE};
+
+pub mod header;
 
 pub struct ParsedROM
 {
-    pub mapper : mapper,
+    pub header : RomHeader,
     pub program_rom : ProgramROM,
+    pub program_ram : ProgramRAM,
     pub character_rom : CharacterROM,
 }
 
@@ -17,29 +23,28 @@ impl ROMParser
 {
     pub fn parse(bytes : Box<[byte]>) -> Result<ParsedROM>
     {
-        if bytes.len()>=4 && bytes[0]==0x4E && bytes[1]==0x45 && bytes[2]==0x53 && bytes[3]==0x1A
+        let header = RomHeader::parse(&bytes).context(codeloc!())?;
+
+        let mut program_ram = ProgramRAM::new(&header);
+        if header.has_trainer
         {
-            //todo parse all INES flags https://www.nesdev.org/wiki/INES
-            let header = &bytes[0x00..0x10];
-            let mapper = (header[7]&0xF0) | (header[6]>>4);
-
-            let offset_of_program_rom = header.len();
-            let size_of_program_rom = (header[4] as usize)*16*1024;
-            let program_rom_start_index = cmp::min(bytes.len(), offset_of_program_rom);
-            let program_rom_end_index = cmp::min(bytes.len(), offset_of_program_rom+size_of_program_rom);
-            let program_rom_bytes = &bytes[program_rom_start_index..program_rom_end_index];
-            let program_rom = ProgramROM::new(mapper, program_rom_bytes);
-
-            let offset_of_character_rom = offset_of_program_rom+size_of_program_rom;
-            let size_of_character_rom = (header[5] as usize)*8*1024;
-            let character_rom_start_index = cmp::min(bytes.len(), offset_of_character_rom);
-            let character_rom_end_index = cmp::min(bytes.len(), offset_of_character_rom+size_of_character_rom);
-            let character_rom_bytes = &bytes[character_rom_start_index..character_rom_end_index];
-            let character_rom = CharacterROM::new(mapper, character_rom_bytes);
-
-            return Ok(ParsedROM { mapper, program_rom, character_rom });
+            let trainer_bytes = ROMParser::slice(&bytes, header.trainer_offset(), TRAINER_SIZE);
+            program_ram.load_trainer(trainer_bytes);
         }
 
-        return Err(anyhow!("Invalid ROM file!"));
+        let program_rom_bytes = ROMParser::slice(&bytes, header.program_rom_offset(), header.program_rom_size);
+        let program_rom = ProgramROM::new(program_rom_bytes);
+
+        let character_rom_bytes = ROMParser::slice(&bytes, header.character_rom_offset(), header.character_rom_size);
+        let character_rom = CharacterROM::new(&header, character_rom_bytes);
+
+        return Ok(ParsedROM { header, program_rom, program_ram, character_rom });
+    }
+
+    fn slice(bytes : &[byte], offset : usize, size : usize) -> &[byte]
+    {
+        let start_index = cmp::min(bytes.len(), offset);
+        let end_index = cmp::min(bytes.len(), offset.saturating_add(size));
+        return &bytes[start_index..end_index];
     }
 }
@@ -0,0 +1,205 @@
+use anyhow::{anyhow, Result};
+use crate::system::{byte, mapper};
+use crate::system::ppu::mirroring::Mirroring;
+
+pub const HEADER_SIZE : usize = 16;
+pub const TRAINER_SIZE : usize = 512;
+
+const PROGRAM_ROM_BANK_SIZE : usize = 16*1024;
+const CHARACTER_ROM_BANK_SIZE : usize = 8*1024;
+const DEFAULT_PROGRAM_RAM_SIZE : usize = 8*1024;
+const DEFAULT_CHARACTER_RAM_SIZE : usize = 8*1024;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RomFormat
+{
+    INES,
+    NES2,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ConsoleType
+{
+    NES,
+    VsSystem,
+    Playchoice10,
+    Extended(byte),
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum TVSystem
+{
+    NTSC,
+    PAL,
+    MultipleRegion,
+    Dendy,
+}
+
+//https://www.nesdev.org/wiki/INES
+//https://www.nesdev.org/wiki/NES_2.0
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RomHeader
+{
+    pub format : RomFormat,
+    pub mapper : mapper,
+    pub submapper : byte,
+    pub program_rom_size : usize,
+    pub character_rom_size : usize,
+    pub program_ram_size : usize,
+    pub program_nvram_size : usize,
+    pub character_ram_size : usize,
+    pub character_nvram_size : usize,
+    pub mirroring : Mirroring,
+    pub has_battery : bool,
+    pub has_trainer : bool,
+    pub console_type : ConsoleType,
+    pub tv_system : TVSystem,
+}
+
+impl RomHeader
+{
+    pub fn parse(bytes : &[byte]) -> Result<RomHeader>
+    {
+        if bytes.len() < HEADER_SIZE || bytes[0]!=0x4E || bytes[1]!=0x45 || bytes[2]!=0x53 || bytes[3]!=0x1A
+        {
+            return Err(anyhow!("Invalid ROM file!"));
+        }
+
+        let header = &bytes[0..HEADER_SIZE];
+        let is_four_screen_mode_enabled = (header[6] & 0b00001000) >> 3 == 1;
+        let mirroring = if is_four_screen_mode_enabled { Mirroring::FourScreen }
+            else if header[6] & 0b00000001 == 1 { Mirroring::Vertical }
+            else { Mirroring::Horizontal };
+
+        let has_battery = (header[6] & 0b00000010) >> 1 == 1;
+        let has_trainer = (header[6] & 0b00000100) >> 2 == 1;
+
+        //byte 7 bits 2-3: 10 = NES 2.0, 01 = archaic iNES, 00 = iNES unless bytes 12-15 hold garbage
+        return match header[7] & 0b00001100
+        {
+            0b00001000 => RomHeader::parse_nes2(header, bytes.len(), mirroring, has_battery, has_trainer),
+            0b00000000 =>
+            {
+                //old dumping tools wrote garbage such as "DiskDude!" in bytes 7-15
+                let is_archaic_ines = header[12..HEADER_SIZE].iter().any(|value| *value != 0);
+                Ok(RomHeader::parse_ines(header, is_archaic_ines, mirroring, has_battery, has_trainer))
+            }
+            _ => Ok(RomHeader::parse_ines(header, true, mirroring, has_battery, has_trainer)),
+        };
+    }
+
+    fn parse_ines(header : &[byte], is_archaic_ines : bool, mirroring : Mirroring, has_battery : bool, has_trainer : bool) -> RomHeader
+    {
+        //an archaic header only has a valid byte 6, so the upper mapper nibble and the console type are unknown
+        let console_type = match header[7] & 0b00000011
+        {
+            1 if !is_archaic_ines => ConsoleType::VsSystem,
+            2 if !is_archaic_ines => ConsoleType::Playchoice10,
+            _ => ConsoleType::NES,
+        };
+
+        let mapper_high_nibble = if is_archaic_ines { 0 } else { header[7] & 0xF0 };
+        let mapper = (mapper_high_nibble | (header[6] >> 4)) as mapper;
+
+        let character_rom_size = (header[5] as usize) * CHARACTER_ROM_BANK_SIZE;
+        let program_ram_size = if is_archaic_ines || header[8]==0 { DEFAULT_PROGRAM_RAM_SIZE }
+            else { (header[8] as usize) * 8*1024 };
+
+        return RomHeader
+        {
+            format: RomFormat::INES,
+            mapper: mapper,
+            submapper: 0,
+            program_rom_size: (header[4] as usize) * PROGRAM_ROM_BANK_SIZE,
+            character_rom_size: character_rom_size,
+            program_ram_size: if has_battery { 0 } else { program_ram_size },
+            program_nvram_size: if has_battery { program_ram_size } else { 0 },
+            character_ram_size: if character_rom_size==0 { DEFAULT_CHARACTER_RAM_SIZE } else { 0 },
+            character_nvram_size: 0,
+            mirroring: mirroring,
+            has_battery: has_battery,
+            has_trainer: has_trainer,
+            console_type: console_type,
+            tv_system: if !is_archaic_ines && header[9] & 0b00000001 == 1 { TVSystem::PAL } else { TVSystem::NTSC },
+        };
+    }
+
+    fn parse_nes2(header : &[byte], file_size : usize, mirroring : Mirroring, has_battery : bool, has_trainer : bool) -> Result<RomHeader>
+    {
+        let console_type = match header[7] & 0b00000011
+        {
+            1 => ConsoleType::VsSystem,
+            2 => ConsoleType::Playchoice10,
+            3 => ConsoleType::Extended(header[13] & 0x0F),
+            _ => ConsoleType::NES,
+        };
+
+        let mapper = ((header[8] & 0x0F) as mapper) << 8 | ((header[7] & 0xF0) | (header[6] >> 4)) as mapper;
+
+        //the exponent-multiplier notation can describe sizes of up to 2^63 bytes, which no file can hold
+        let program_rom_size = RomHeader::parse_nes2_rom_size(header[4], header[9] & 0x0F, PROGRAM_ROM_BANK_SIZE);
+        let character_rom_size = RomHeader::parse_nes2_rom_size(header[5], header[9] >> 4, CHARACTER_ROM_BANK_SIZE);
+        if program_rom_size > file_size || character_rom_size > file_size
+        {
+            return Err(anyhow!("Invalid ROM file! PRG-ROM size {} or CHR-ROM size {} exceeds the file size {}", program_rom_size, character_rom_size, file_size));
+        }
+
+        return Ok(RomHeader
+        {
+            format: RomFormat::NES2,
+            mapper: mapper,
+            submapper: header[8] >> 4,
+            program_rom_size: program_rom_size,
+            character_rom_size: character_rom_size,
+            program_ram_size: RomHeader::parse_nes2_ram_size(header[10] & 0x0F),
+            program_nvram_size: RomHeader::parse_nes2_ram_size(header[10] >> 4),
+            character_ram_size: RomHeader::parse_nes2_ram_size(header[11] & 0x0F),
+            character_nvram_size: RomHeader::parse_nes2_ram_size(header[11] >> 4),
+            mirroring: mirroring,
+            has_battery: has_battery,
+            has_trainer: has_trainer,
+            console_type: console_type,
+            tv_system: match header[12] & 0b00000011
+            {
+                1 => TVSystem::PAL,
+                2 => TVSystem::MultipleRegion,
+                3 => TVSystem::Dendy,
+                _ => TVSystem::NTSC,
+            },
+        });
+    }
+
+    fn parse_nes2_rom_size(size_lsb : byte, size_msb : byte, bank_size : usize) -> usize
+    {
+        if size_msb == 0x0F
+        {
+            //exponent-multiplier notation: EEEEEEMM, size = 2^E * (MM*2+1)
+            let exponent = (size_lsb >> 2) as u32;
+            let multiplier = ((size_lsb & 0b00000011) as usize)*2+1;
+            return 2usize.checked_pow(exponent).unwrap_or(0).saturating_mul(multiplier);
+        }
+
+        return (((size_msb as usize) << 8) | (size_lsb as usize)) * bank_size;
+    }
+
+    fn parse_nes2_ram_size(shift_count : byte) -> usize
+    {
+        return if shift_count==0 { 0 } else { 64 << shift_count };
+    }
+
+    pub fn trainer_offset(&self) -> usize
+    {
+        return HEADER_SIZE;
+    }
+
+    pub fn program_rom_offset(&self) -> usize
+    {
+        //the 512 byte trainer, when present, sits between the header and PRG-ROM
+        return HEADER_SIZE + if self.has_trainer { TRAINER_SIZE } else { 0 };
+    }
+
+    pub fn character_rom_offset(&self) -> usize
+    {
+        return self.program_rom_offset().saturating_add(self.program_rom_size);
+    }
+}
@@ -0,0 +1,75 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::cpu::program_ram::ProgramRAM;
+use crate::system::mappers::SharedMapper;
+
+const FLUSH_INTERVAL : Duration = Duration::from_secs(5);
+const SHUTDOWN_POLLING_INTERVAL : Duration = Duration::from_millis(100);
+
+//battery-backed PRG-RAM, stored as a raw dump next to the ROM (game.nes -> game.sav)
+//this is the format used by most other emulators, so saves can be moved between them
+#[derive(Clone)]
+pub struct SaveFile
+{
+    path : PathBuf,
+}
+
+impl SaveFile
+{
+    pub fn new(rom_file_path : &Path) -> SaveFile
+    {
+        return SaveFile { path: rom_file_path.with_extension("sav") };
+    }
+
+    pub fn load(&self, program_ram : &mut ProgramRAM) -> Result<()>
+    {
+        if !program_ram.is_battery_backed || !self.path.exists() { return Ok(()) }
+
+        let bytes = fs::read(&self.path).context(codeloc!())?;
+        program_ram.load_bytes(&bytes);
+        return Ok(());
+    }
+
+    pub fn flush(&self, mapper : &SharedMapper) -> Result<()>
+    {
+        let mut locked_mapper = mapper.lock();
+        let program_ram = locked_mapper.program_ram();
+        if !program_ram.is_battery_backed || !program_ram.was_recently_changed() { return Ok(()) }
+
+        let bytes = program_ram.as_bytes().to_vec();
+        drop(locked_mapper);
+
+        //the RAM stays marked as changed until it is on disk, so a failed write is retried on the next flush
+        if let Err(error) = fs::write(&self.path, bytes)
+        {
+            mapper.lock().program_ram().mark_as_changed();
+            return Err(error).context(codeloc!());
+        }
+
+        return Ok(());
+    }
+
+    pub fn run(&self, mapper : SharedMapper, is_shutting_down : Arc<AtomicBool>) -> Result<()>
+    {
+        let mut last_flush_time = Instant::now();
+        while !is_shutting_down.load(Ordering::Relaxed)
+        {
+            thread::sleep(SHUTDOWN_POLLING_INTERVAL);
+
+            if last_flush_time.elapsed() >= FLUSH_INTERVAL
+            {
+                if let Err(error) = self.flush(&mapper) { eprintln!("{:?}", error); }
+                last_flush_time = Instant::now();
+            }
+        }
+
+        if let Err(error) = self.flush(&mapper) { eprintln!("{:?}", error); }
+        return Ok(());
+    }
+}
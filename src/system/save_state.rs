@@ -0,0 +1,121 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{anyhow, Context, Result};
+use crate::codeloc;
+use crate::system::byte;
+use crate::system::cpu::CPU;
+use crate::system::snapshot::{Snapshot, SnapshotReader, SnapshotWriter};
+
+const SAVE_STATE_MAGIC : &[byte] = b"NESSTATE";
+const SAVE_STATE_VERSION : u16 = 8;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SaveStateRequest
+{
+    Save(byte), //slot number
+    Load(byte), //slot number
+}
+
+//numbered save state files next to the ROM (game.nes -> game.ss0 ... game.ss9)
+//file layout: magic, version, md5 of the ROM file, then the CPU, mapper, PPU and APU snapshots
+#[derive(Clone)]
+pub struct SaveStateSlots
+{
+    rom_file_path : PathBuf,
+    rom_hash : String,
+}
+
+impl SaveStateSlots
+{
+    pub fn new(rom_file_path : &Path, rom_hash : String) -> SaveStateSlots
+    {
+        return SaveStateSlots { rom_file_path: rom_file_path.to_path_buf(), rom_hash: rom_hash };
+    }
+
+    fn get_slot_file_path(&self, slot : byte) -> PathBuf
+    {
+        return self.rom_file_path.with_extension(format!("ss{}", slot));
+    }
+
+    //CPU, mapper, PPU and APU snapshots, in file order
+    fn save_components(cpu : &mut CPU) -> [Box<[byte]>; 4]
+    {
+        let mut cpu_writer = SnapshotWriter::new();
+        cpu.save_snapshot(&mut cpu_writer);
+
+        let mut mapper_writer = SnapshotWriter::new();
+        cpu.bus.mapper.lock().save_snapshot(&mut mapper_writer);
+
+        let mut ppu_writer = SnapshotWriter::new();
+        cpu.bus.ppu.save_snapshot(&mut ppu_writer);
+
+        let mut apu_writer = SnapshotWriter::new();
+        cpu.bus.apu.save_snapshot(&mut apu_writer);
+
+        return [cpu_writer.into_bytes(), mapper_writer.into_bytes(), ppu_writer.into_bytes(), apu_writer.into_bytes()];
+    }
+
+    fn load_components(cpu : &mut CPU, snapshots : [&[byte]; 4]) -> Result<()>
+    {
+        cpu.load_snapshot(&mut SnapshotReader::new(snapshots[0])).context(codeloc!())?;
+        cpu.bus.mapper.lock().load_snapshot(&mut SnapshotReader::new(snapshots[1])).context(codeloc!())?;
+        cpu.bus.ppu.load_snapshot(&mut SnapshotReader::new(snapshots[2])).context(codeloc!())?;
+        cpu.bus.apu.load_snapshot(&mut SnapshotReader::new(snapshots[3])).context(codeloc!())?;
+        return Ok(());
+    }
+
+    //called by the scheduler between two instructions
+    pub fn save(&self, slot : byte, cpu : &mut CPU) -> Result<()>
+    {
+        let mut writer = SnapshotWriter::new();
+        writer.put_bytes(SAVE_STATE_MAGIC);
+        writer.put_u16(SAVE_STATE_VERSION);
+        writer.put_bytes(self.rom_hash.as_bytes());
+        for component_snapshot in SaveStateSlots::save_components(cpu) { writer.put_bytes(&component_snapshot); }
+
+        let file_path = self.get_slot_file_path(slot);
+        fs::write(&file_path, writer.into_bytes()).context(codeloc!())?;
+        println!("[SYS] Saved state to {}", file_path.display());
+        return Ok(());
+    }
+
+    pub fn load(&self, slot : byte, cpu : &mut CPU) -> Result<()>
+    {
+        let file_path = self.get_slot_file_path(slot);
+        let bytes = fs::read(&file_path).context(codeloc!())?;
+        let mut reader = SnapshotReader::new(&bytes);
+
+        if reader.get_bytes().context(codeloc!())? != SAVE_STATE_MAGIC
+        {
+            return Err(anyhow!("{} is not a save state file!", file_path.display()));
+        }
+
+        let version = reader.get_u16().context(codeloc!())?;
+        if version != SAVE_STATE_VERSION
+        {
+            return Err(anyhow!("Unsupported save state version {}!", version));
+        }
+
+        if reader.get_bytes().context(codeloc!())? != self.rom_hash.as_bytes()
+        {
+            return Err(anyhow!("{} was saved from a different ROM!", file_path.display()));
+        }
+
+        let cpu_snapshot = reader.get_bytes().context(codeloc!())?;
+        let mapper_snapshot = reader.get_bytes().context(codeloc!())?;
+        let ppu_snapshot = reader.get_bytes().context(codeloc!())?;
+        let apu_snapshot = reader.get_bytes().context(codeloc!())?;
+
+        //a section that fails to parse would leave the console half restored,
+        //so the current state is kept aside and put back in that case
+        let backup = SaveStateSlots::save_components(cpu);
+        if let Err(error) = SaveStateSlots::load_components(cpu, [cpu_snapshot, mapper_snapshot, ppu_snapshot, apu_snapshot])
+        {
+            SaveStateSlots::load_components(cpu, [&backup[0], &backup[1], &backup[2], &backup[3]]).context(codeloc!())?;
+            return Err(error);
+        }
+
+        println!("[SYS] Loaded state from {}", file_path.display());
+        return Ok(());
+    }
+}
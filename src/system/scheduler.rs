@@ -0,0 +1,142 @@
+use anyhow::{Context, Result};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::codeloc;
+use crate::system::apu::speaker::Speaker;
+use crate::system::cpu::{CPU, CPURunEnvironment};
+use crate::system::headless::{FrameSink, SampleSink};
+use crate::system::save_state::{SaveStateRequest, SaveStateSlots};
+use crate::system::video_frontend::VideoFrontend;
+
+//NTSC frame rate is 60.0988 Hz
+pub const FRAME_DURATION : Duration = Duration::from_nanos(16_639_267);
+
+pub struct SchedulerRunEnvironment
+{
+    pub cpu_environment : CPURunEnvironment,
+    pub is_shutting_down : Arc<AtomicBool>,
+    pub should_disable_audio : bool,
+    pub should_disable_video : bool,
+    pub should_disable_interrupt_vectors : bool,
+    pub frame_sink : Option<Box<dyn FrameSink + Send>>,
+    pub sample_sink : Option<Box<dyn SampleSink + Send>>,
+    pub number_of_frames_to_run : Option<usize>,
+    pub save_state_slots : Option<SaveStateSlots>,
+    pub window_title : String,
+}
+
+//drives the whole console from a single thread: the CPU executes one instruction at a time
+//and the PPU and APU are stepped for the exact number of cycles the instruction took.
+//emulation is therefore deterministic, the host machine only affects the frame pacing
+pub struct Scheduler
+{
+    cpu : CPU,
+}
+
+impl Scheduler
+{
+    pub fn new(cpu : CPU) -> Scheduler
+    {
+        return Scheduler { cpu };
+    }
+
+    pub fn run(self : &mut Scheduler, env : SchedulerRunEnvironment) -> Result<()>
+    {
+        let scheduler = self;
+        scheduler.cpu.power_on(env.should_disable_interrupt_vectors);
+
+        let speaker = if env.should_disable_audio { None } else { Some(Speaker::new().context(codeloc!())?) };
+        if let Some(speaker) = &speaker { speaker.play(); }
+
+        //the speaker has priority, the headless sample sink is used only when audio is disabled
+        let sample_sink : Option<&dyn SampleSink> = match &speaker
+        {
+            Some(speaker) => Some(speaker),
+            None => env.sample_sink.as_deref().map(|sample_sink| sample_sink as &dyn SampleSink),
+        };
+
+        if let Some(sample_sink) = sample_sink
+        {
+            scheduler.cpu.bus.apu.clock.set_sample_rate(sample_sink.get_sample_rate());
+        }
+
+        let result = if env.should_disable_video { scheduler.run_frames(&env, None, sample_sink) }
+        else
+        {
+            VideoFrontend::with_window(&env.window_title, |video_frontend|
+                scheduler.run_frames(&env, Some(video_frontend), sample_sink))
+        };
+
+        if let Some(speaker) = &speaker { speaker.pause(); }
+        return result;
+    }
+
+    fn run_frames(&mut self, env : &SchedulerRunEnvironment, mut video_frontend : Option<&mut VideoFrontend>, sample_sink : Option<&dyn SampleSink>) -> Result<()>
+    {
+        //headless runs go as fast as possible, only a window or a speaker need real time
+        let should_pace_frames = video_frontend.is_some() || !env.should_disable_audio;
+        let mut next_frame_deadline = Instant::now() + FRAME_DURATION;
+        let mut number_of_completed_frames : usize = 0;
+
+        while !env.is_shutting_down.load(Ordering::Relaxed)
+        {
+            self.run_until_frame_is_completed(env);
+
+            number_of_completed_frames += 1;
+            if let Some(frame_sink) = &env.frame_sink
+            {
+                frame_sink.accept_frame(&self.cpu.bus.ppu.framebuffer);
+            }
+
+            if let Some(video_frontend) = &mut video_frontend
+            {
+                video_frontend.render_frame(&mut self.cpu.bus.ppu).context(codeloc!())?;
+
+                let save_state_request = video_frontend.handle_events(&mut self.cpu.bus.input_subsystem, &mut self.cpu.bus.ppu.debugger, &env.is_shutting_down);
+                if let (Some(request), Some(save_state_slots)) = (save_state_request, &env.save_state_slots)
+                {
+                    let result = match request
+                    {
+                        SaveStateRequest::Save(slot) => save_state_slots.save(slot, &mut self.cpu),
+                        SaveStateRequest::Load(slot) => save_state_slots.load(slot, &mut self.cpu),
+                    };
+
+                    if let Err(error) = result { eprintln!("{:?}", error); }
+                }
+            }
+
+            let samples = self.cpu.bus.apu.take_samples();
+            if let Some(sample_sink) = sample_sink
+            {
+                sample_sink.accept_samples(&samples);
+            }
+
+            if env.number_of_frames_to_run.is_some_and(|number_of_frames| number_of_completed_frames >= number_of_frames)
+            {
+                env.is_shutting_down.store(true, Ordering::Relaxed);
+            }
+
+            if should_pace_frames
+            {
+                let now = Instant::now();
+                if now < next_frame_deadline { thread::sleep(next_frame_deadline - now); }
+                else { next_frame_deadline = now; } //too far behind, do not try to catch up
+
+                next_frame_deadline += FRAME_DURATION;
+            }
+        }
+
+        return Ok(());
+    }
+
+    fn run_until_frame_is_completed(&mut self, env : &SchedulerRunEnvironment)
+    {
+        while !self.cpu.bus.ppu.was_frame_completed()
+        {
+            if env.is_shutting_down.load(Ordering::Relaxed) { return }
+            self.cpu.step(&env.cpu_environment);
+        }
+    }
+}
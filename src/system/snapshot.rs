@@ -0,0 +1,157 @@
+use anyhow::{anyhow, Result};
+use crate::system::byte;
+
+//a component that can dump its state into a save state and restore it later.
+//values are read back in the exact order they were written, there are no field names or tags
+pub trait Snapshot
+{
+    fn save_snapshot(&self, writer : &mut SnapshotWriter);
+    fn load_snapshot(&mut self, reader : &mut SnapshotReader) -> Result<()>;
+}
+
+pub struct SnapshotWriter
+{
+    bytes : Vec<byte>,
+}
+
+impl SnapshotWriter
+{
+    pub fn new() -> SnapshotWriter
+    {
+        return SnapshotWriter { bytes: Vec::new() };
+    }
+
+    pub fn put_byte(&mut self, value : byte)
+    {
+        self.bytes.push(value);
+    }
+
+    pub fn put_bool(&mut self, value : bool)
+    {
+        self.bytes.push(value as byte);
+    }
+
+    pub fn put_u16(&mut self, value : u16)
+    {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn put_i16(&mut self, value : i16)
+    {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn put_u64(&mut self, value : u64)
+    {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn put_usize(&mut self, value : usize)
+    {
+        self.put_u64(value as u64);
+    }
+
+    pub fn put_bytes(&mut self, values : &[byte])
+    {
+        self.put_usize(values.len());
+        self.bytes.extend_from_slice(values);
+    }
+
+    pub fn put_usizes(&mut self, values : &[usize])
+    {
+        for value in values { self.put_usize(*value); }
+    }
+
+    pub fn into_bytes(self) -> Box<[byte]>
+    {
+        return self.bytes.into_boxed_slice();
+    }
+}
+
+pub struct SnapshotReader<'a>
+{
+    bytes : &'a [byte],
+    position : usize,
+}
+
+impl <'a> SnapshotReader<'a>
+{
+    pub fn new(bytes : &'a [byte]) -> SnapshotReader<'a>
+    {
+        return SnapshotReader { bytes, position: 0 };
+    }
+
+    fn next<const N : usize>(&mut self) -> Result<[byte; N]>
+    {
+        let values = self.next_slice(N)?;
+        let mut array = [0 as byte; N];
+        array.copy_from_slice(values);
+        return Ok(array);
+    }
+
+    fn next_slice(&mut self, length : usize) -> Result<&'a [byte]>
+    {
+        //the length prefix comes from the file, so it may be large enough to overflow
+        let end_position = self.position.checked_add(length).filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| anyhow!("Save state is truncated or corrupted!"))?;
+
+        let values = &self.bytes[self.position..end_position];
+        self.position = end_position;
+        return Ok(values);
+    }
+
+    pub fn get_byte(&mut self) -> Result<byte>
+    {
+        return Ok(self.next::<1>()?[0]);
+    }
+
+    pub fn get_bool(&mut self) -> Result<bool>
+    {
+        return Ok(self.get_byte()? != 0);
+    }
+
+    pub fn get_u16(&mut self) -> Result<u16>
+    {
+        return Ok(u16::from_le_bytes(self.next()?));
+    }
+
+    pub fn get_i16(&mut self) -> Result<i16>
+    {
+        return Ok(i16::from_le_bytes(self.next()?));
+    }
+
+    pub fn get_u64(&mut self) -> Result<u64>
+    {
+        return Ok(u64::from_le_bytes(self.next()?));
+    }
+
+    pub fn get_usize(&mut self) -> Result<usize>
+    {
+        return Ok(self.get_u64()? as usize);
+    }
+
+    pub fn get_bytes(&mut self) -> Result<&'a [byte]>
+    {
+        let length = self.get_usize()?;
+        return self.next_slice(length);
+    }
+
+    //for fixed size memories: a save state from a ROM with a different RAM size is rejected
+    pub fn get_bytes_into(&mut self, destination : &mut [byte]) -> Result<()>
+    {
+        let values = self.get_bytes()?;
+        if values.len() != destination.len()
+        {
+            return Err(anyhow!("Save state memory size mismatch: expected {} bytes, found {}!", destination.len(), values.len()));
+        }
+
+        destination.copy_from_slice(values);
+        return Ok(());
+    }
+
+    pub fn get_usizes_into(&mut self, destination : &mut [usize]) -> Result<()>
+    {
+        for value in destination.iter_mut() { *value = self.get_usize()?; }
+        return Ok(());
+    }
+}
@@ -3,12 +3,22 @@ use maplit2::hashmap;
 use crate::system::test::cpu_blargg_test::test_cpu_with_blargg_testrom;
 use crate::system::test::cpu_kevtris_nestest::test_cpu_with_kevtris_nestest;
 use crate::system::test::joystick_test::test_joystick;
+use crate::system::test::mapper_test::test_mappers;
+use crate::system::test::ppu_regression_test::test_ppu_with_regression_roms;
 use crate::system::test::ppu_testsroms::{*};
+use crate::system::test::ppu_vbl_nmi_test::test_ppu_with_vbl_nmi_testroms;
+use crate::system::test::rom_header_test::test_rom_header;
+use crate::system::test::system_palette_test::test_system_palette;
 
 mod cpu_kevtris_nestest;
 mod ppu_testsroms;
 mod joystick_test;
+mod mapper_test;
 mod cpu_blargg_test;
+mod ppu_regression_test;
+mod ppu_vbl_nmi_test;
+mod rom_header_test;
+mod system_palette_test;
 
 pub struct Test {}
 impl Test
@@ -22,6 +32,13 @@ impl Test
             "ppu_blocks_test" => test_ppu_with_blocks_testrom,
             "ppu_physics_test" => test_ppu_with_physics_testrom,
             "ppu_spritecans_test" => test_ppu_with_spritecans_testrom,
+            "ppu_sprite_zero_hit_test" => test_ppu_with_sprite_zero_hit_testroms,
+            "ppu_sprite_overflow_test" => test_ppu_with_sprite_overflow_testrom,
+            "ppu_vbl_nmi_test" => test_ppu_with_vbl_nmi_testroms,
+            "ppu_regression_test" => test_ppu_with_regression_roms,
+            "mapper_test" => test_mappers,
+            "rom_header_test" => test_rom_header,
+            "system_palette_test" => test_system_palette,
             "joystick_test" => test_joystick,
         };
 
@@ -0,0 +1,138 @@
+use std::fmt::Debug;
+use anyhow::{anyhow, Context, Result};
+use crate::codeloc;
+use crate::system::{address, byte, mapper};
+use crate::system::mappers::{Mapper, MapperRegistry};
+use crate::system::rom::ROMParser;
+
+mod axrom_test;
+mod bnrom_test;
+mod cnrom_test;
+mod color_dreams_test;
+mod fme7_test;
+mod gxrom_test;
+mod mmc1_test;
+mod mmc2_test;
+mod mmc3_test;
+mod namco163_test;
+mod nina001_test;
+mod nrom_test;
+mod nsf_test;
+mod uxrom_test;
+mod vrc_irq_test;
+mod vrc4_test;
+mod vrc6_test;
+mod vrc7_test;
+
+const HEADER_SIZE : usize = 16;
+const PROGRAM_ROM_UNIT_SIZE : usize = 16*1024;
+const CHARACTER_ROM_UNIT_SIZE : usize = 8*1024;
+const KB : usize = 1024;
+
+//every board is checked on a synthetic ROM: each 1kB chunk of PRG-ROM and CHR-ROM is filled with its own index,
+//so the first two bytes of a CPU or PPU window tell which bank is mapped there
+pub fn test_mappers() -> Result<()>
+{
+    let tests = vec!
+    [
+        ("NROM", nrom_test::test_nrom as fn() -> Result<()>),
+        ("MMC1", mmc1_test::test_mmc1),
+        ("UxROM", uxrom_test::test_uxrom),
+        ("CNROM", cnrom_test::test_cnrom),
+        ("MMC3", mmc3_test::test_mmc3),
+        ("AxROM", axrom_test::test_axrom),
+        ("MMC2", mmc2_test::test_mmc2),
+        ("MMC4", mmc2_test::test_mmc4),
+        ("Color Dreams", color_dreams_test::test_color_dreams),
+        ("Namco 163", namco163_test::test_namco163),
+        ("VRC4a/c", vrc4_test::test_vrc4_mapper21),
+        ("VRC2a", vrc4_test::test_vrc2_mapper22),
+        ("VRC4e/f", vrc4_test::test_vrc4_mapper23),
+        ("VRC2b", vrc4_test::test_vrc2_mapper23),
+        ("VRC4b/d", vrc4_test::test_vrc4_mapper25),
+        ("VRC2c", vrc4_test::test_vrc2_mapper25),
+        ("VRC6a", vrc6_test::test_vrc6_mapper24),
+        ("VRC6b", vrc6_test::test_vrc6_mapper26),
+        ("NSF", nsf_test::test_nsf),
+        ("BNROM", bnrom_test::test_bnrom),
+        ("NINA-001", nina001_test::test_nina001),
+        ("GxROM", gxrom_test::test_gxrom),
+        ("FME-7", fme7_test::test_fme7),
+        ("VRC7", vrc7_test::test_vrc7),
+    ];
+
+    let mut failed_mapper_names : Vec<&str> = Vec::new();
+    for (mapper_name, test) in tests
+    {
+        match test()
+        {
+            Ok(()) => { println!("[MAPPER] {}: PASSED!", mapper_name); }
+            Err(error) =>
+            {
+                println!("[MAPPER] {}: FAILED! {:?}", mapper_name, error);
+                failed_mapper_names.push(mapper_name);
+            }
+        }
+    }
+
+    if !failed_mapper_names.is_empty()
+    {
+        return Err(anyhow!("[MAPPER] Mapper test failed for: {}", failed_mapper_names.join(", ")));
+    }
+
+    return Ok(());
+}
+
+//submapper = None builds an iNES file, Some(submapper) builds a NES 2.0 file with 8kB of PRG-RAM
+fn build_mapper(mapper_number : mapper, submapper : Option<byte>, number_of_program_rom_units : byte, number_of_character_rom_units : byte) -> Result<Box<dyn Mapper>>
+{
+    let mut header = [0 as byte; HEADER_SIZE];
+    header[0..4].copy_from_slice(b"NES\x1A");
+    header[4] = number_of_program_rom_units;
+    header[5] = number_of_character_rom_units;
+    header[6] = ((mapper_number & 0x0F) as byte) << 4;
+    header[7] = (mapper_number & 0xF0) as byte;
+    if let Some(submapper) = submapper
+    {
+        header[7] |= 0b00001000;
+        header[8] = (submapper << 4) | ((mapper_number >> 8) as byte & 0x0F);
+        header[10] = 0x07;
+    }
+
+    let program_rom_size = (number_of_program_rom_units as usize) * PROGRAM_ROM_UNIT_SIZE;
+    let character_rom_size = (number_of_character_rom_units as usize) * CHARACTER_ROM_UNIT_SIZE;
+    let mut bytes = header.to_vec();
+    for rom_size in [program_rom_size, character_rom_size]
+    {
+        for chunk_index in 0..rom_size/KB
+        {
+            let chunk_index_bytes = (chunk_index as u16).to_le_bytes();
+            bytes.extend((0..KB).map(|offset| chunk_index_bytes[offset % 2]));
+        }
+    }
+
+    let parsed_rom = ROMParser::parse(bytes.into_boxed_slice()).context(codeloc!())?;
+    return MapperRegistry::build(parsed_rom).context(codeloc!());
+}
+
+fn program_bank_at(mapper : &mut dyn Mapper, raw_address : address, bank_size : usize) -> usize
+{
+    let chunk_index = u16::from_le_bytes([mapper.cpu_read(raw_address), mapper.cpu_read(raw_address+1)]) as usize;
+    return chunk_index * KB / bank_size;
+}
+
+fn character_bank_at(mapper : &dyn Mapper, raw_address : address, bank_size : usize) -> usize
+{
+    let chunk_index = u16::from_le_bytes([mapper.ppu_read(raw_address), mapper.ppu_read(raw_address+1)]) as usize;
+    return chunk_index * KB / bank_size;
+}
+
+fn expect<T : PartialEq + Debug>(description : &str, actual : T, expected : T) -> Result<()>
+{
+    if actual != expected
+    {
+        return Err(anyhow!("{}: expected {:?}, found {:?}", description, expected, actual));
+    }
+
+    return Ok(());
+}
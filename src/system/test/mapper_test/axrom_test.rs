@@ -0,0 +1,19 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::ppu::mirroring::Mirroring;
+use crate::system::test::mapper_test::{build_mapper, expect, program_bank_at, KB};
+
+pub fn test_axrom() -> Result<()>
+{
+    let mut mapper = build_mapper(7, None, 16, 0).context(codeloc!())?;
+    expect("power on mirroring", mapper.mirroring(), Mirroring::SingleScreenLower)?;
+
+    //bits 0-2 select 32kB of PRG-ROM, bit 4 selects the CIRAM page for all four nametables
+    mapper.cpu_write(0x8000, 0x12);
+    expect("PRG at $8000", program_bank_at(mapper.as_mut(), 0x8000, 32*KB), 2)?;
+    expect("mirroring", mapper.mirroring(), Mirroring::SingleScreenUpper)?;
+    mapper.cpu_write(0xFFFF, 0xE7);
+    expect("PRG at $8000 with the unused bits set", program_bank_at(mapper.as_mut(), 0x8000, 32*KB), 7)?;
+    expect("mirroring", mapper.mirroring(), Mirroring::SingleScreenLower)?;
+    return Ok(());
+}
@@ -0,0 +1,23 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::test::mapper_test::{build_mapper, expect, program_bank_at, KB};
+
+pub fn test_bnrom() -> Result<()>
+{
+    //an iNES mapper 34 file with CHR-RAM is a BNROM board, and so is NES 2.0 submapper 2
+    for submapper in [None, Some(2)]
+    {
+        let mut mapper = build_mapper(34, submapper, 8, 0).context(codeloc!())?;
+        mapper.cpu_write(0x8000, 3);
+        expect("PRG at $8000", program_bank_at(mapper.as_mut(), 0x8000, 32*KB), 3)?;
+
+        //$7FFD-$7FFF are plain PRG-RAM on BNROM, not NINA-001 registers
+        mapper.cpu_write(0x7FFD, 1);
+        expect("PRG at $8000 after a $7FFD write", program_bank_at(mapper.as_mut(), 0x8000, 32*KB), 3)?;
+
+        mapper.ppu_write(0x0000, 0x77);
+        expect("CHR-RAM after a write", mapper.ppu_read(0x0000), 0x77)?;
+    }
+
+    return Ok(());
+}
@@ -0,0 +1,24 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::test::mapper_test::{build_mapper, character_bank_at, expect, program_bank_at, KB};
+
+pub fn test_cnrom() -> Result<()>
+{
+    let mut mapper = build_mapper(3, None, 2, 4).context(codeloc!())?;
+    expect("power on CHR at $0000", character_bank_at(mapper.as_ref(), 0x0000, 8*KB), 0)?;
+
+    //the whole 8kB pattern table area switches at once, PRG-ROM does not switch at all
+    mapper.cpu_write(0x8000, 2);
+    expect("CHR at $0000", character_bank_at(mapper.as_ref(), 0x0000, 8*KB), 2)?;
+    expect("CHR at $1000", character_bank_at(mapper.as_ref(), 0x1000, 4*KB), 5)?;
+    expect("PRG at $8000", program_bank_at(mapper.as_mut(), 0x8000, 16*KB), 0)?;
+    expect("PRG at $C000", program_bank_at(mapper.as_mut(), 0xC000, 16*KB), 1)?;
+
+    mapper.cpu_write(0xC000, 3);
+    expect("CHR at $0000", character_bank_at(mapper.as_ref(), 0x0000, 8*KB), 3)?;
+
+    //CHR-ROM ignores PPU writes
+    mapper.ppu_write(0x0000, 0xFF);
+    expect("CHR at $0000 after a PPU write", character_bank_at(mapper.as_ref(), 0x0000, 8*KB), 3)?;
+    return Ok(());
+}
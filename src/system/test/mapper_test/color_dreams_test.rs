@@ -0,0 +1,16 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::test::mapper_test::{build_mapper, character_bank_at, expect, program_bank_at, KB};
+
+pub fn test_color_dreams() -> Result<()>
+{
+    //one register, PRG in the low bits, CHR in the high nibble: the reverse of GxROM
+    let mut mapper = build_mapper(11, None, 8, 16).context(codeloc!())?;
+    mapper.cpu_write(0x8000, 0x52);
+    expect("PRG at $8000", program_bank_at(mapper.as_mut(), 0x8000, 32*KB), 2)?;
+    expect("CHR at $0000", character_bank_at(mapper.as_ref(), 0x0000, 8*KB), 5)?;
+    mapper.cpu_write(0xC000, 0xF3);
+    expect("PRG at $8000", program_bank_at(mapper.as_mut(), 0x8000, 32*KB), 3)?;
+    expect("CHR at $0000", character_bank_at(mapper.as_ref(), 0x0000, 8*KB), 15)?;
+    return Ok(());
+}
@@ -0,0 +1,64 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::{address, byte};
+use crate::system::mappers::Mapper;
+use crate::system::ppu::mirroring::Mirroring;
+use crate::system::test::mapper_test::{build_mapper, character_bank_at, expect, program_bank_at, KB};
+
+fn write_fme7_command(mapper : &mut dyn Mapper, command : byte, parameter : byte)
+{
+    mapper.cpu_write(0x8000, command);
+    mapper.cpu_write(0xA000, parameter);
+}
+
+pub fn test_fme7() -> Result<()>
+{
+    let mut mapper = build_mapper(69, None, 16, 32).context(codeloc!())?;
+    write_fme7_command(mapper.as_mut(), 0x9, 5);
+    write_fme7_command(mapper.as_mut(), 0xA, 6);
+    write_fme7_command(mapper.as_mut(), 0xB, 7);
+    expect("PRG at $8000", program_bank_at(mapper.as_mut(), 0x8000, 8*KB), 5)?;
+    expect("PRG at $A000", program_bank_at(mapper.as_mut(), 0xA000, 8*KB), 6)?;
+    expect("PRG at $C000", program_bank_at(mapper.as_mut(), 0xC000, 8*KB), 7)?;
+    expect("fixed PRG at $E000", program_bank_at(mapper.as_mut(), 0xE000, 8*KB), 31)?;
+
+    //command 8 maps either a PRG-ROM bank or the PRG-RAM at $6000, the RAM has its own enable bit
+    write_fme7_command(mapper.as_mut(), 0x8, 3);
+    expect("PRG-ROM at $6000", program_bank_at(mapper.as_mut(), 0x6000, 8*KB), 3)?;
+    write_fme7_command(mapper.as_mut(), 0x8, 0b11000000);
+    mapper.cpu_write(0x6000, 0x42);
+    expect("PRG-RAM at $6000", mapper.cpu_read(0x6000), 0x42)?;
+    write_fme7_command(mapper.as_mut(), 0x8, 0b01000000);
+    expect("disabled PRG-RAM at $6000", mapper.cpu_read(0x6000), 0x00)?;
+
+    for command in 0..8
+    {
+        write_fme7_command(mapper.as_mut(), command, 20 + command);
+        expect("CHR window", character_bank_at(mapper.as_ref(), (command as address)*0x400, KB), 20 + command as usize)?;
+    }
+
+    for (value, mirroring) in [(0, Mirroring::Vertical), (1, Mirroring::Horizontal), (2, Mirroring::SingleScreenLower), (3, Mirroring::SingleScreenUpper)]
+    {
+        write_fme7_command(mapper.as_mut(), 0xC, value);
+        expect("mirroring", mapper.mirroring(), mirroring)?;
+    }
+
+    //16 bit down counter, the IRQ fires when it wraps from $0000 to $FFFF
+    write_fme7_command(mapper.as_mut(), 0xE, 1);
+    write_fme7_command(mapper.as_mut(), 0xF, 0);
+    write_fme7_command(mapper.as_mut(), 0xD, 0x81);
+    mapper.notify_cpu_cycles(1);
+    expect("IRQ pending at $0000", mapper.is_irq_pending(), false)?;
+    mapper.notify_cpu_cycles(1);
+    expect("IRQ pending after wrapping", mapper.is_irq_pending(), true)?;
+    write_fme7_command(mapper.as_mut(), 0xD, 0);
+    expect("IRQ pending after acknowledge", mapper.is_irq_pending(), false)?;
+
+    //with counting disabled the counter holds
+    write_fme7_command(mapper.as_mut(), 0xE, 0);
+    write_fme7_command(mapper.as_mut(), 0xF, 0);
+    write_fme7_command(mapper.as_mut(), 0xD, 0x01);
+    mapper.notify_cpu_cycles(2);
+    expect("IRQ pending with counting disabled", mapper.is_irq_pending(), false)?;
+    return Ok(());
+}
@@ -0,0 +1,16 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::test::mapper_test::{build_mapper, character_bank_at, expect, program_bank_at, KB};
+
+pub fn test_gxrom() -> Result<()>
+{
+    //PRG in bits 4-5, CHR in bits 0-1
+    let mut mapper = build_mapper(66, None, 8, 4).context(codeloc!())?;
+    mapper.cpu_write(0x8000, 0x21);
+    expect("PRG at $8000", program_bank_at(mapper.as_mut(), 0x8000, 32*KB), 2)?;
+    expect("CHR at $0000", character_bank_at(mapper.as_ref(), 0x0000, 8*KB), 1)?;
+    mapper.cpu_write(0x8000, 0xCE);
+    expect("PRG at $8000 with the unused bits set", program_bank_at(mapper.as_mut(), 0x8000, 32*KB), 0)?;
+    expect("CHR at $0000 with the unused bits set", character_bank_at(mapper.as_ref(), 0x0000, 8*KB), 2)?;
+    return Ok(());
+}
@@ -0,0 +1,96 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::{address, byte};
+use crate::system::mappers::Mapper;
+use crate::system::ppu::mirroring::Mirroring;
+use crate::system::test::mapper_test::{build_mapper, character_bank_at, expect, program_bank_at, KB};
+
+//MMC1 registers are loaded serially, one bit per write, LSB first
+fn write_mmc1_register(mapper : &mut dyn Mapper, raw_address : address, value : byte)
+{
+    for bit_index in 0..5 { mapper.cpu_write(raw_address, (value >> bit_index) & 1); }
+}
+
+pub fn test_mmc1() -> Result<()>
+{
+    let mut mapper = build_mapper(1, None, 8, 4).context(codeloc!())?;
+    expect("power on PRG at $C000", program_bank_at(mapper.as_mut(), 0xC000, 16*KB), 7)?;
+
+    write_mmc1_register(mapper.as_mut(), 0xE000, 3);
+    expect("PRG at $8000", program_bank_at(mapper.as_mut(), 0x8000, 16*KB), 3)?;
+
+    //the register is only committed on the fifth write, and the address of that write picks the register
+    mapper.cpu_write(0xE000, 1);
+    mapper.cpu_write(0xE000, 0);
+    mapper.cpu_write(0xE000, 1);
+    mapper.cpu_write(0xE000, 0);
+    expect("PRG at $8000 after 4 writes", program_bank_at(mapper.as_mut(), 0x8000, 16*KB), 3)?;
+    mapper.cpu_write(0xA000, 0);
+    expect("PRG at $8000 after the fifth write went to $A000", program_bank_at(mapper.as_mut(), 0x8000, 16*KB), 3)?;
+    expect("8kB CHR at $0000 after the fifth write went to $A000", character_bank_at(mapper.as_ref(), 0x0000, 4*KB), 4)?;
+
+    //a write with bit 7 set drops the bits shifted in so far
+    mapper.cpu_write(0xE000, 1);
+    mapper.cpu_write(0xE000, 1);
+    mapper.cpu_write(0xE000, 0x80);
+    write_mmc1_register(mapper.as_mut(), 0xE000, 2);
+    expect("PRG at $8000 after a serial reset", program_bank_at(mapper.as_mut(), 0x8000, 16*KB), 2)?;
+
+    //fix the first bank at $8000, switch 16kB at $C000, 4kB CHR banks, vertical mirroring
+    write_mmc1_register(mapper.as_mut(), 0x8000, 0b11010);
+    expect("fixed PRG at $8000", program_bank_at(mapper.as_mut(), 0x8000, 16*KB), 0)?;
+    expect("PRG at $C000", program_bank_at(mapper.as_mut(), 0xC000, 16*KB), 2)?;
+    expect("mirroring", mapper.mirroring(), Mirroring::Vertical)?;
+
+    write_mmc1_register(mapper.as_mut(), 0xA000, 3);
+    write_mmc1_register(mapper.as_mut(), 0xC000, 6);
+    expect("4kB CHR at $0000", character_bank_at(mapper.as_ref(), 0x0000, 4*KB), 3)?;
+    expect("4kB CHR at $1000", character_bank_at(mapper.as_ref(), 0x1000, 4*KB), 6)?;
+
+    //switch to 32kB PRG and 8kB CHR banks, 8kB CHR mode ignores the low bit of CHR bank 0 and the whole CHR bank 1 register
+    write_mmc1_register(mapper.as_mut(), 0x8000, 0b00011);
+    expect("8kB CHR at $0000", character_bank_at(mapper.as_ref(), 0x0000, 4*KB), 2)?;
+    expect("8kB CHR at $1000", character_bank_at(mapper.as_ref(), 0x1000, 4*KB), 3)?;
+    expect("mirroring", mapper.mirroring(), Mirroring::Horizontal)?;
+
+    //32kB PRG mode ignores the low bit of the PRG bank
+    write_mmc1_register(mapper.as_mut(), 0xE000, 5);
+    expect("32kB PRG at $8000", program_bank_at(mapper.as_mut(), 0x8000, 16*KB), 4)?;
+    expect("32kB PRG at $C000", program_bank_at(mapper.as_mut(), 0xC000, 16*KB), 5)?;
+
+    //the reset write also fixes the last bank at $C000 again
+    mapper.cpu_write(0x8000, 0x80);
+    expect("PRG at $C000 after reset", program_bank_at(mapper.as_mut(), 0xC000, 16*KB), 7)?;
+
+    //bit 4 of the PRG bank register disables PRG-RAM
+    mapper.cpu_write(0x6000, 0x42);
+    expect("PRG-RAM at $6000", mapper.cpu_read(0x6000), 0x42)?;
+    write_mmc1_register(mapper.as_mut(), 0xE000, 0b10000);
+    expect("disabled PRG-RAM at $6000", mapper.cpu_read(0x6000), 0x00)?;
+    mapper.cpu_write(0x6000, 0x24);
+    write_mmc1_register(mapper.as_mut(), 0xE000, 0b00000);
+    expect("PRG-RAM at $6000 after a write while disabled", mapper.cpu_read(0x6000), 0x42)?;
+
+    write_mmc1_register(mapper.as_mut(), 0x8000, 0b01100);
+    expect("one screen mirroring", mapper.mirroring(), Mirroring::SingleScreenLower)?;
+    write_mmc1_register(mapper.as_mut(), 0x8000, 0b01101);
+    expect("one screen mirroring", mapper.mirroring(), Mirroring::SingleScreenUpper)?;
+    return test_surom();
+}
+
+//SUROM: 512kB of PRG-ROM, bit 4 of the CHR bank 0 register selects the 256kB half
+fn test_surom() -> Result<()>
+{
+    let mut mapper = build_mapper(1, None, 32, 0).context(codeloc!())?;
+    expect("power on PRG at $C000", program_bank_at(mapper.as_mut(), 0xC000, 16*KB), 15)?;
+
+    write_mmc1_register(mapper.as_mut(), 0xE000, 2);
+    write_mmc1_register(mapper.as_mut(), 0xA000, 0b10000);
+    expect("upper half PRG at $8000", program_bank_at(mapper.as_mut(), 0x8000, 16*KB), 18)?;
+    expect("upper half fixed PRG at $C000", program_bank_at(mapper.as_mut(), 0xC000, 16*KB), 31)?;
+
+    write_mmc1_register(mapper.as_mut(), 0xA000, 0b00000);
+    expect("lower half PRG at $8000", program_bank_at(mapper.as_mut(), 0x8000, 16*KB), 2)?;
+    expect("lower half fixed PRG at $C000", program_bank_at(mapper.as_mut(), 0xC000, 16*KB), 15)?;
+    return Ok(());
+}
@@ -0,0 +1,81 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::mappers::Mapper;
+use crate::system::ppu::mirroring::Mirroring;
+use crate::system::test::mapper_test::{build_mapper, character_bank_at, expect, program_bank_at, KB};
+
+//one bank per pattern table and latch state, the latches start at $FE
+fn write_character_banks(mapper : &mut dyn Mapper)
+{
+    mapper.cpu_write(0xB000, 2); //$0000 FD
+    mapper.cpu_write(0xC000, 3); //$0000 FE
+    mapper.cpu_write(0xD000, 4); //$1000 FD
+    mapper.cpu_write(0xE000, 5); //$1000 FE
+}
+
+pub fn test_mmc2() -> Result<()>
+{
+    let mut mapper = build_mapper(9, None, 8, 16).context(codeloc!())?;
+    mapper.cpu_write(0xA000, 5);
+    expect("PRG at $8000", program_bank_at(mapper.as_mut(), 0x8000, 8*KB), 5)?;
+    expect("fixed PRG at $A000", program_bank_at(mapper.as_mut(), 0xA000, 8*KB), 13)?;
+    expect("fixed PRG at $E000", program_bank_at(mapper.as_mut(), 0xE000, 8*KB), 15)?;
+
+    write_character_banks(mapper.as_mut());
+    expect("FE CHR at $0000", character_bank_at(mapper.as_ref(), 0x0000, 4*KB), 3)?;
+    expect("FE CHR at $1000", character_bank_at(mapper.as_ref(), 0x1000, 4*KB), 5)?;
+
+    //the second plane fetch of tile $FD / $FE in the left pattern table flips the left latch only
+    mapper.notify_ppu_address_fetch(0x0FD8);
+    expect("FD CHR at $0000 after tile $FD", character_bank_at(mapper.as_ref(), 0x0000, 4*KB), 2)?;
+    expect("FE CHR at $1000 after tile $FD on the left", character_bank_at(mapper.as_ref(), 0x1000, 4*KB), 5)?;
+
+    //other rows of the trigger tiles, and their neighbours, are not decoded by MMC2 in the left pattern table
+    for raw_address in [0x0FE9, 0x0FEF, 0x0FE0, 0x0FF8, 0x0FC8]
+    {
+        mapper.notify_ppu_address_fetch(raw_address);
+        expect("FD CHR at $0000 after a non trigger fetch", character_bank_at(mapper.as_ref(), 0x0000, 4*KB), 2)?;
+    }
+
+    mapper.notify_ppu_address_fetch(0x0FE8);
+    expect("FE CHR at $0000 after tile $FE", character_bank_at(mapper.as_ref(), 0x0000, 4*KB), 3)?;
+
+    //in the right pattern table, any row of the tile triggers the latch
+    mapper.notify_ppu_address_fetch(0x1FDB);
+    expect("FD CHR at $1000 after tile $FD", character_bank_at(mapper.as_ref(), 0x1000, 4*KB), 4)?;
+    expect("FE CHR at $0000 after tile $FD on the right", character_bank_at(mapper.as_ref(), 0x0000, 4*KB), 3)?;
+    mapper.notify_ppu_address_fetch(0x1FEF);
+    expect("FE CHR at $1000 after tile $FE", character_bank_at(mapper.as_ref(), 0x1000, 4*KB), 5)?;
+
+    //a bank register write takes effect immediately for the current latch state
+    mapper.cpu_write(0xE000, 7);
+    expect("FE CHR at $1000 after a register write", character_bank_at(mapper.as_ref(), 0x1000, 4*KB), 7)?;
+
+    mapper.cpu_write(0xF000, 1);
+    expect("mirroring", mapper.mirroring(), Mirroring::Horizontal)?;
+    mapper.cpu_write(0xF000, 0);
+    expect("mirroring", mapper.mirroring(), Mirroring::Vertical)?;
+    return Ok(());
+}
+
+pub fn test_mmc4() -> Result<()>
+{
+    let mut mapper = build_mapper(10, None, 8, 16).context(codeloc!())?;
+    mapper.cpu_write(0xA000, 3);
+    expect("16kB PRG at $8000", program_bank_at(mapper.as_mut(), 0x8000, 16*KB), 3)?;
+    expect("fixed PRG at $C000", program_bank_at(mapper.as_mut(), 0xC000, 16*KB), 7)?;
+
+    //MMC4 decodes the whole $0FD8-$0FDF / $0FE8-$0FEF range in the left pattern table too
+    write_character_banks(mapper.as_mut());
+    mapper.notify_ppu_address_fetch(0x0FDA);
+    expect("FD CHR at $0000", character_bank_at(mapper.as_ref(), 0x0000, 4*KB), 2)?;
+    mapper.notify_ppu_address_fetch(0x0FEF);
+    expect("FE CHR at $0000", character_bank_at(mapper.as_ref(), 0x0000, 4*KB), 3)?;
+    mapper.notify_ppu_address_fetch(0x0FF0);
+    expect("FE CHR at $0000 after tile $FF", character_bank_at(mapper.as_ref(), 0x0000, 4*KB), 3)?;
+
+    //PRG-RAM at $6000, used by the Fire Emblem boards for saves
+    mapper.cpu_write(0x6000, 0x42);
+    expect("PRG-RAM at $6000", mapper.cpu_read(0x6000), 0x42)?;
+    return Ok(());
+}
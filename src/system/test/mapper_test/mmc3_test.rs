@@ -0,0 +1,100 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::mappers::Mapper;
+use crate::system::ppu::mirroring::Mirroring;
+use crate::system::test::mapper_test::{build_mapper, character_bank_at, expect, program_bank_at, KB};
+
+//background fetches from $0000 followed by a sprite fetch from $1000, like one rendered scanline
+fn simulate_scanline(mapper : &mut dyn Mapper)
+{
+    for _ in 0..4 { mapper.notify_ppu_address_fetch(0x0000); }
+    mapper.notify_ppu_address_fetch(0x1000);
+}
+
+pub fn test_mmc3() -> Result<()>
+{
+    let mut mapper = build_mapper(4, None, 16, 16).context(codeloc!())?;
+    mapper.cpu_write(0x8000, 6); mapper.cpu_write(0x8001, 5);
+    mapper.cpu_write(0x8000, 7); mapper.cpu_write(0x8001, 9);
+    expect("R6 PRG at $8000", program_bank_at(mapper.as_mut(), 0x8000, 8*KB), 5)?;
+    expect("R7 PRG at $A000", program_bank_at(mapper.as_mut(), 0xA000, 8*KB), 9)?;
+    expect("second last PRG at $C000", program_bank_at(mapper.as_mut(), 0xC000, 8*KB), 30)?;
+    expect("last PRG at $E000", program_bank_at(mapper.as_mut(), 0xE000, 8*KB), 31)?;
+
+    //PRG mode 1 swaps $8000 and $C000, $A000 and $E000 stay where they are
+    mapper.cpu_write(0x8000, 0x46);
+    expect("swapped PRG at $8000", program_bank_at(mapper.as_mut(), 0x8000, 8*KB), 30)?;
+    expect("swapped R6 PRG at $C000", program_bank_at(mapper.as_mut(), 0xC000, 8*KB), 5)?;
+    expect("R7 PRG at $A000 in mode 1", program_bank_at(mapper.as_mut(), 0xA000, 8*KB), 9)?;
+
+    //R0/R1 are 2kB banks that ignore their low bit, R2-R5 are 1kB banks
+    mapper.cpu_write(0x8000, 0); mapper.cpu_write(0x8001, 11);
+    mapper.cpu_write(0x8000, 2); mapper.cpu_write(0x8001, 40);
+    expect("R0 CHR at $0000", character_bank_at(mapper.as_ref(), 0x0000, KB), 10)?;
+    expect("R0 CHR at $0400", character_bank_at(mapper.as_ref(), 0x0400, KB), 11)?;
+    expect("R2 CHR at $1000", character_bank_at(mapper.as_ref(), 0x1000, KB), 40)?;
+    mapper.cpu_write(0x8000, 0x82);
+    expect("inverted R2 CHR at $0000", character_bank_at(mapper.as_ref(), 0x0000, KB), 40)?;
+    expect("inverted R0 CHR at $1400", character_bank_at(mapper.as_ref(), 0x1400, KB), 11)?;
+
+    mapper.cpu_write(0xA000, 1);
+    expect("mirroring", mapper.mirroring(), Mirroring::Horizontal)?;
+
+    //$A001: bit 7 enables PRG-RAM, bit 6 protects it from writes
+    mapper.cpu_write(0x6000, 0x42);
+    mapper.cpu_write(0xA001, 0b11000000);
+    mapper.cpu_write(0x6000, 0x24);
+    expect("write protected PRG-RAM", mapper.cpu_read(0x6000), 0x42)?;
+    mapper.cpu_write(0xA001, 0b00000000);
+    expect("disabled PRG-RAM", mapper.cpu_read(0x6000), 0x00)?;
+    mapper.cpu_write(0xA001, 0b10000000);
+    expect("enabled PRG-RAM", mapper.cpu_read(0x6000), 0x42)?;
+
+    return test_mmc3_irq(mapper.as_mut());
+}
+
+fn test_mmc3_irq(mapper : &mut dyn Mapper) -> Result<()>
+{
+    //latch 2: the first A12 rise reloads the counter, the third one brings it to zero
+    mapper.cpu_write(0xC000, 2);
+    mapper.cpu_write(0xC001, 0);
+    mapper.cpu_write(0xE001, 0);
+    simulate_scanline(mapper);
+    simulate_scanline(mapper);
+    expect("IRQ pending after 2 scanlines", mapper.is_irq_pending(), false)?;
+    simulate_scanline(mapper);
+    expect("IRQ pending after 3 scanlines", mapper.is_irq_pending(), true)?;
+
+    //$E000 acknowledges and disables, the counter keeps running but raises nothing
+    mapper.cpu_write(0xE000, 0);
+    expect("IRQ pending after acknowledge", mapper.is_irq_pending(), false)?;
+    for _ in 0..3 { simulate_scanline(mapper); }
+    expect("IRQ pending while disabled", mapper.is_irq_pending(), false)?;
+
+    //A12 must stay low for a few fetches, rising edges closer together than that are filtered out
+    mapper.cpu_write(0xC000, 1);
+    mapper.cpu_write(0xC001, 0);
+    mapper.cpu_write(0xE001, 0);
+    simulate_scanline(mapper); //reload to 1
+    for _ in 0..8
+    {
+        mapper.notify_ppu_address_fetch(0x0000);
+        mapper.notify_ppu_address_fetch(0x1000);
+    }
+    expect("IRQ pending after filtered A12 rises", mapper.is_irq_pending(), false)?;
+    simulate_scanline(mapper);
+    expect("IRQ pending after an unfiltered A12 rise", mapper.is_irq_pending(), true)?;
+    mapper.cpu_write(0xE000, 0);
+
+    //with latch 0 the IRQ fires on every scanline
+    mapper.cpu_write(0xC000, 0);
+    mapper.cpu_write(0xC001, 0);
+    mapper.cpu_write(0xE001, 0);
+    simulate_scanline(mapper);
+    expect("IRQ pending with latch 0", mapper.is_irq_pending(), true)?;
+    mapper.cpu_write(0xE000, 0);
+    mapper.cpu_write(0xE001, 0);
+    simulate_scanline(mapper);
+    expect("IRQ pending with latch 0 on the next scanline", mapper.is_irq_pending(), true)?;
+    return Ok(());
+}
@@ -0,0 +1,95 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::{address, byte};
+use crate::system::mappers::Mapper;
+use crate::system::ppu::mirroring::Mirroring;
+use crate::system::test::mapper_test::{build_mapper, character_bank_at, expect, program_bank_at, KB};
+
+fn get_audio_output(mapper : &dyn Mapper) -> f64
+{
+    return mapper.expansion_audio_channels()[0].synthesize(0.0);
+}
+
+//bit 7 of the address port turns on auto increment, so consecutive data port writes fill consecutive RAM bytes
+fn write_audio_ram(mapper : &mut dyn Mapper, ram_address : byte, values : &[byte])
+{
+    mapper.cpu_write(0xF800, 0b10000000 | ram_address);
+    for value in values { mapper.cpu_write(0x4800, *value); }
+}
+
+fn read_audio_ram(mapper : &mut dyn Mapper, ram_address : byte) -> byte
+{
+    mapper.cpu_write(0xF800, ram_address);
+    return mapper.cpu_read(0x4800);
+}
+
+pub fn test_namco163() -> Result<()>
+{
+    let mut mapper = build_mapper(19, None, 16, 32).context(codeloc!())?;
+    mapper.cpu_write(0xE000, 4);
+    mapper.cpu_write(0xE800, 5);
+    mapper.cpu_write(0xF000, 6);
+    expect("PRG at $8000", program_bank_at(mapper.as_mut(), 0x8000, 8*KB), 4)?;
+    expect("PRG at $A000", program_bank_at(mapper.as_mut(), 0xA000, 8*KB), 5)?;
+    expect("PRG at $C000", program_bank_at(mapper.as_mut(), 0xC000, 8*KB), 6)?;
+    expect("fixed PRG at $E000", program_bank_at(mapper.as_mut(), 0xE000, 8*KB), 31)?;
+
+    for register_index in 0..8
+    {
+        mapper.cpu_write(0x8000 + register_index*0x800, 10 + register_index as byte);
+        expect("CHR window", character_bank_at(mapper.as_ref(), register_index*0x400, KB), 10 + register_index as usize)?;
+    }
+
+    for (register_index, bank) in [0xE0, 0xE1, 0xE0, 0xE1].iter().enumerate()
+    {
+        mapper.cpu_write(0xC000 + (register_index as address)*0x800, *bank);
+    }
+    expect("mirroring", mapper.mirroring(), Mirroring::Vertical)?;
+
+    //15 bit up counter, the IRQ fires when it reaches $7FFF
+    mapper.cpu_write(0x5000, 0xFD);
+    mapper.cpu_write(0x5800, 0xFF);
+    mapper.notify_cpu_cycles(1);
+    expect("IRQ pending at $7FFE", mapper.is_irq_pending(), false)?;
+    mapper.notify_cpu_cycles(1);
+    expect("IRQ pending at $7FFF", mapper.is_irq_pending(), true)?;
+    mapper.cpu_write(0x5800, 0x00);
+    expect("IRQ pending after acknowledge", mapper.is_irq_pending(), false)?;
+
+    return test_namco163_audio();
+}
+
+fn test_namco163_audio() -> Result<()>
+{
+    let mut mapper = build_mapper(19, None, 16, 32).context(codeloc!())?;
+    let mapper = mapper.as_mut();
+
+    //a 4 sample waveform at RAM address 0: 15, 0, 12, 8
+    write_audio_ram(mapper, 0x00, &[0x0F, 0x8C]);
+
+    //only channel 7 enabled, at full volume, advancing one sample per update
+    //$78-$7F: frequency low, phase low, frequency mid, phase mid, frequency high and length, phase high, wave address, volume
+    write_audio_ram(mapper, 0x78, &[0x00, 0x00, 0x00, 0x00, 0b11111101, 0x00, 0x00, 0x0F]);
+    expect("output at sample 0", get_audio_output(mapper), 0.4375)?;
+
+    //the only enabled channel is updated every 15 CPU cycles
+    mapper.notify_cpu_cycles(14);
+    expect("output before the first update", get_audio_output(mapper), 0.4375)?;
+    mapper.notify_cpu_cycles(1);
+    expect("output at sample 1", get_audio_output(mapper), -0.5)?;
+    expect("phase high byte at sample 1", read_audio_ram(mapper, 0x7D), 1)?;
+
+    mapper.notify_cpu_cycles(15);
+    expect("output at sample 2", get_audio_output(mapper), 0.25)?;
+    mapper.notify_cpu_cycles(30);
+    expect("output after wrapping around the waveform", get_audio_output(mapper), 0.4375)?;
+    expect("phase high byte after wrapping", read_audio_ram(mapper, 0x7D), 0)?;
+
+    //the phase is plain RAM, games can reset or move it
+    write_audio_ram(mapper, 0x7D, &[0x02]);
+    expect("output after a phase write", get_audio_output(mapper), 0.25)?;
+
+    mapper.cpu_write(0xE000, 0b01000000);
+    expect("muted output", get_audio_output(mapper), 0.0)?;
+    return Ok(());
+}
@@ -0,0 +1,27 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::test::mapper_test::{build_mapper, character_bank_at, expect, program_bank_at, KB};
+
+pub fn test_nina001() -> Result<()>
+{
+    //NES 2.0 submapper 1, and iNES mapper 34 files with CHR-ROM
+    for submapper in [Some(1), None]
+    {
+        let mut mapper = build_mapper(34, submapper, 4, 1).context(codeloc!())?;
+        mapper.cpu_write(0x7FFD, 1);
+        mapper.cpu_write(0x7FFE, 1);
+        mapper.cpu_write(0x7FFF, 0);
+        expect("PRG at $8000", program_bank_at(mapper.as_mut(), 0x8000, 32*KB), 1)?;
+        expect("CHR at $0000", character_bank_at(mapper.as_ref(), 0x0000, 4*KB), 1)?;
+        expect("CHR at $1000", character_bank_at(mapper.as_ref(), 0x1000, 4*KB), 0)?;
+
+        //the registers overlay PRG-RAM, the value also lands in the RAM underneath
+        expect("PRG-RAM under $7FFE", mapper.cpu_read(0x7FFE), 1)?;
+
+        //writes to $8000-$FFFF do not switch banks, unlike BNROM
+        mapper.cpu_write(0x8000, 0);
+        expect("PRG at $8000 after a ROM write", program_bank_at(mapper.as_mut(), 0x8000, 32*KB), 1)?;
+    }
+
+    return Ok(());
+}
@@ -0,0 +1,24 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::test::mapper_test::{build_mapper, character_bank_at, expect, program_bank_at, KB};
+
+pub fn test_nrom() -> Result<()>
+{
+    let mut mapper = build_mapper(0, None, 2, 1).context(codeloc!())?;
+    expect("NROM-256 PRG at $8000", program_bank_at(mapper.as_mut(), 0x8000, 16*KB), 0)?;
+    expect("NROM-256 PRG at $C000", program_bank_at(mapper.as_mut(), 0xC000, 16*KB), 1)?;
+    expect("CHR at $1C00", character_bank_at(mapper.as_ref(), 0x1C00, KB), 7)?;
+
+    //CHR-ROM ignores writes, PRG-RAM at $6000-$7FFF keeps them
+    mapper.ppu_write(0x0000, 0xAA);
+    expect("CHR-ROM after a write", mapper.ppu_read(0x0000), 0x00)?;
+    mapper.cpu_write(0x6000, 0x55);
+    expect("PRG-RAM at $6000", mapper.cpu_read(0x6000), 0x55)?;
+
+    //NROM-128 mirrors its only 16kB bank at $C000, CHR-RAM takes writes
+    let mut mapper = build_mapper(0, None, 1, 0).context(codeloc!())?;
+    expect("NROM-128 PRG mirrored at $C000", program_bank_at(mapper.as_mut(), 0xC000, 16*KB), 0)?;
+    mapper.ppu_write(0x1FFF, 0xAA);
+    expect("CHR-RAM after a write", mapper.ppu_read(0x1FFF), 0xAA)?;
+    return Ok(());
+}
@@ -0,0 +1,29 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::test::mapper_test::{build_mapper, expect, program_bank_at, KB};
+
+pub fn test_nsf() -> Result<()>
+{
+    let mut mapper = build_mapper(31, None, 16, 0).context(codeloc!())?;
+    expect("initial PRG at $8000", program_bank_at(mapper.as_mut(), 0x8000, 4*KB), 0)?;
+    expect("initial PRG at $E000", program_bank_at(mapper.as_mut(), 0xE000, 4*KB), 6)?;
+    expect("initial PRG at $F000", program_bank_at(mapper.as_mut(), 0xF000, 4*KB), 63)?;
+
+    //one register per 4kB window, $5FF8 = $8000 ... $5FFF = $F000
+    mapper.cpu_write(0x5FF8, 5);
+    mapper.cpu_write(0x5FFF, 2);
+    expect("PRG at $8000", program_bank_at(mapper.as_mut(), 0x8000, 4*KB), 5)?;
+    expect("PRG at $F000", program_bank_at(mapper.as_mut(), 0xF000, 4*KB), 2)?;
+
+    //only A0-A2 select the register, anywhere in $5000-$5FFF
+    mapper.cpu_write(0x5000, 9);
+    mapper.cpu_write(0x5A0B, 11);
+    expect("PRG at $8000 after a $5000 write", program_bank_at(mapper.as_mut(), 0x8000, 4*KB), 9)?;
+    expect("PRG at $B000 after a $5A0B write", program_bank_at(mapper.as_mut(), 0xB000, 4*KB), 11)?;
+    expect("PRG at $F000", program_bank_at(mapper.as_mut(), 0xF000, 4*KB), 2)?;
+
+    //bank numbers wrap around the ROM size
+    mapper.cpu_write(0x5FF9, 64 + 3);
+    expect("PRG at $9000 past the end of the ROM", program_bank_at(mapper.as_mut(), 0x9000, 4*KB), 3)?;
+    return Ok(());
+}
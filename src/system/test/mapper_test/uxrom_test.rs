@@ -0,0 +1,23 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::test::mapper_test::{build_mapper, expect, program_bank_at, KB};
+
+pub fn test_uxrom() -> Result<()>
+{
+    //UOROM: 16 banks, the last one stays at $C000 whatever is selected
+    let mut mapper = build_mapper(2, None, 16, 0).context(codeloc!())?;
+    expect("power on PRG at $8000", program_bank_at(mapper.as_mut(), 0x8000, 16*KB), 0)?;
+    expect("fixed PRG at $C000", program_bank_at(mapper.as_mut(), 0xC000, 16*KB), 15)?;
+
+    //any address in $8000-$FFFF selects the bank
+    mapper.cpu_write(0xFFFF, 9);
+    expect("PRG at $8000", program_bank_at(mapper.as_mut(), 0x8000, 16*KB), 9)?;
+    expect("fixed PRG at $C000", program_bank_at(mapper.as_mut(), 0xC000, 16*KB), 15)?;
+    mapper.cpu_write(0x8000, 15);
+    expect("last bank at $8000 too", program_bank_at(mapper.as_mut(), 0x8000, 16*KB), 15)?;
+
+    //UxROM boards use CHR-RAM
+    mapper.ppu_write(0x0123, 0x5A);
+    expect("CHR-RAM after a write", mapper.ppu_read(0x0123), 0x5A)?;
+    return Ok(());
+}
@@ -0,0 +1,101 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::{address, byte, mapper};
+use crate::system::ppu::mirroring::Mirroring;
+use crate::system::test::mapper_test::{build_mapper, character_bank_at, expect, program_bank_at, KB};
+use crate::system::test::mapper_test::vrc_irq_test::{test_vrc_irq, VRCIRQLatchRegisters, VRCIRQRegisters};
+
+//register addresses are given as the offsets of registers 0-3 for the board's address lines
+fn test_vrc4(mapper_number : mapper, register_offsets : [address; 4]) -> Result<()>
+{
+    let mut mapper = build_mapper(mapper_number, None, 16, 64).context(codeloc!())?;
+    mapper.cpu_write(0x8000, 3);
+    mapper.cpu_write(0xA000, 4);
+    expect("PRG at $8000", program_bank_at(mapper.as_mut(), 0x8000, 8*KB), 3)?;
+    expect("PRG at $A000", program_bank_at(mapper.as_mut(), 0xA000, 8*KB), 4)?;
+    expect("second last PRG at $C000", program_bank_at(mapper.as_mut(), 0xC000, 8*KB), 30)?;
+    expect("last PRG at $E000", program_bank_at(mapper.as_mut(), 0xE000, 8*KB), 31)?;
+
+    //registers 2 and 3 at $9000 select the PRG swap mode, registers 0 and 1 the mirroring
+    mapper.cpu_write(0x9000 + register_offsets[0], 0b00000000);
+    mapper.cpu_write(0x9000 + register_offsets[2], 0b00000010);
+    expect("swapped PRG at $8000", program_bank_at(mapper.as_mut(), 0x8000, 8*KB), 30)?;
+    expect("swapped PRG at $C000", program_bank_at(mapper.as_mut(), 0xC000, 8*KB), 3)?;
+    expect("mirroring after a swap mode write", mapper.mirroring(), Mirroring::Vertical)?;
+    mapper.cpu_write(0x9000 + register_offsets[0], 0b00000011);
+    expect("mirroring", mapper.mirroring(), Mirroring::SingleScreenUpper)?;
+    expect("swapped PRG at $8000 after a mirroring write", program_bank_at(mapper.as_mut(), 0x8000, 8*KB), 30)?;
+
+    //the 5th bit of the high nibble reaches CHR banks above 256kB
+    mapper.cpu_write(0xB000 + register_offsets[0], 0x05);
+    mapper.cpu_write(0xB000 + register_offsets[1], 0x11);
+    mapper.cpu_write(0xE000 + register_offsets[2], 0x07);
+    expect("CHR at $0000", character_bank_at(mapper.as_ref(), 0x0000, KB), 0x115)?;
+    expect("CHR at $1C00", character_bank_at(mapper.as_ref(), 0x1C00, KB), 0x007)?;
+
+    let irq_registers = VRCIRQRegisters
+    {
+        latch: VRCIRQLatchRegisters::Nibbles(0xF000 + register_offsets[0], 0xF000 + register_offsets[1]),
+        control_address: 0xF000 + register_offsets[2],
+        acknowledge_address: 0xF000 + register_offsets[3],
+    };
+    return test_vrc_irq(mapper.as_mut(), &irq_registers);
+}
+
+fn test_vrc2(mapper_number : mapper, submapper : Option<byte>, register_offsets : [address; 4], should_ignore_low_character_bank_bit : bool) -> Result<()>
+{
+    let mut mapper = build_mapper(mapper_number, submapper, 16, 64).context(codeloc!())?;
+    mapper.cpu_write(0x8000, 3);
+    mapper.cpu_write(0xA000, 4);
+    expect("PRG at $8000", program_bank_at(mapper.as_mut(), 0x8000, 8*KB), 3)?;
+    expect("PRG at $A000", program_bank_at(mapper.as_mut(), 0xA000, 8*KB), 4)?;
+
+    //VRC2 has no PRG swap mode and only two mirroring modes, every $9xxx register controls the mirroring
+    mapper.cpu_write(0x9000 + register_offsets[2], 0b00000011);
+    expect("PRG at $8000 after a $9xxx write", program_bank_at(mapper.as_mut(), 0x8000, 8*KB), 3)?;
+    expect("mirroring", mapper.mirroring(), Mirroring::Horizontal)?;
+
+    //only 4 bits of the high nibble
+    mapper.cpu_write(0xB000 + register_offsets[0], 0x04);
+    mapper.cpu_write(0xB000 + register_offsets[1], 0x11);
+    let expected_bank = if should_ignore_low_character_bank_bit { 0x14 >> 1 } else { 0x14 };
+    expect("CHR at $0000", character_bank_at(mapper.as_ref(), 0x0000, KB), expected_bank)?;
+
+    //VRC2 has no IRQ counter, $Fxxx writes do nothing
+    mapper.cpu_write(0xF000 + register_offsets[2], 0b00000110);
+    mapper.notify_cpu_cycles(100000);
+    return expect("IRQ pending", mapper.is_irq_pending(), false);
+}
+
+pub fn test_vrc4_mapper21() -> Result<()>
+{
+    test_vrc4(21, [0x000, 0x002, 0x004, 0x006]).context("VRC4a")?;
+    return test_vrc4(21, [0x000, 0x040, 0x080, 0x0C0]).context("VRC4c");
+}
+
+pub fn test_vrc2_mapper22() -> Result<()>
+{
+    return test_vrc2(22, None, [0x000, 0x002, 0x001, 0x003], true);
+}
+
+pub fn test_vrc4_mapper23() -> Result<()>
+{
+    test_vrc4(23, [0x000, 0x001, 0x002, 0x003]).context("VRC4f")?;
+    return test_vrc4(23, [0x000, 0x004, 0x008, 0x00C]).context("VRC4e");
+}
+
+pub fn test_vrc2_mapper23() -> Result<()>
+{
+    return test_vrc2(23, Some(3), [0x000, 0x001, 0x002, 0x003], false);
+}
+
+pub fn test_vrc4_mapper25() -> Result<()>
+{
+    test_vrc4(25, [0x000, 0x002, 0x001, 0x003]).context("VRC4b")?;
+    return test_vrc4(25, [0x000, 0x008, 0x004, 0x00C]).context("VRC4d");
+}
+
+pub fn test_vrc2_mapper25() -> Result<()>
+{
+    return test_vrc2(25, Some(3), [0x000, 0x002, 0x001, 0x003], false);
+}
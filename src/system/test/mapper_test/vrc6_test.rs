@@ -0,0 +1,104 @@
+use anyhow::{Context, Result};
+use crate::codeloc;
+use crate::system::{address, byte, mapper};
+use crate::system::mappers::Mapper;
+use crate::system::ppu::mirroring::Mirroring;
+use crate::system::test::mapper_test::{build_mapper, character_bank_at, expect, program_bank_at, KB};
+use crate::system::test::mapper_test::vrc_irq_test::{test_vrc_irq, VRCIRQLatchRegisters, VRCIRQRegisters};
+
+//mixer output of a single channel: pulses peak at 15, the sawtooth at 31
+const PULSE_HIGH_OUTPUT : f64 = 0.00752 * 15.0;
+
+fn get_audio_output(mapper : &dyn Mapper) -> f64
+{
+    return mapper.expansion_audio_channels()[0].synthesize(0.0);
+}
+
+fn test_vrc6(mapper_number : mapper, register_offsets : [address; 4]) -> Result<()>
+{
+    let mut mapper = build_mapper(mapper_number, None, 16, 32).context(codeloc!())?;
+    mapper.cpu_write(0x8000, 3);
+    mapper.cpu_write(0xC000, 9);
+    expect("16kB PRG at $8000", program_bank_at(mapper.as_mut(), 0x8000, 16*KB), 3)?;
+    expect("8kB PRG at $C000", program_bank_at(mapper.as_mut(), 0xC000, 8*KB), 9)?;
+    expect("last PRG at $E000", program_bank_at(mapper.as_mut(), 0xE000, 8*KB), 31)?;
+
+    //eight 1kB CHR banks, $D000-$D003 and $E000-$E003 through the board's address lines
+    for register_index in 0..4
+    {
+        mapper.cpu_write(0xD000 + register_offsets[register_index], 10 + register_index as byte);
+        mapper.cpu_write(0xE000 + register_offsets[register_index], 20 + register_index as byte);
+        expect("CHR at $0000-$0FFF", character_bank_at(mapper.as_ref(), (register_index as address)*0x400, KB), 10 + register_index)?;
+        expect("CHR at $1000-$1FFF", character_bank_at(mapper.as_ref(), 0x1000 + (register_index as address)*0x400, KB), 20 + register_index)?;
+    }
+
+    //$B003: mirroring in bits 2-3, PRG-RAM enable in bit 7
+    mapper.cpu_write(0xB000 + register_offsets[3], 0b10000100);
+    expect("mirroring", mapper.mirroring(), Mirroring::Horizontal)?;
+    mapper.cpu_write(0x6000, 0x42);
+    expect("PRG-RAM at $6000", mapper.cpu_read(0x6000), 0x42)?;
+    mapper.cpu_write(0xB000 + register_offsets[3], 0b00001000);
+    expect("mirroring", mapper.mirroring(), Mirroring::SingleScreenLower)?;
+    expect("disabled PRG-RAM at $6000", mapper.cpu_read(0x6000), 0x00)?;
+
+    test_vrc6_audio(mapper.as_mut(), register_offsets)?;
+
+    let irq_registers = VRCIRQRegisters
+    {
+        latch: VRCIRQLatchRegisters::Byte(0xF000 + register_offsets[0]),
+        control_address: 0xF000 + register_offsets[1],
+        acknowledge_address: 0xF000 + register_offsets[2],
+    };
+    return test_vrc_irq(mapper.as_mut(), &irq_registers);
+}
+
+fn test_vrc6_audio(mapper : &mut dyn Mapper, register_offsets : [address; 4]) -> Result<()>
+{
+    //pulse 1 with a 8/16 duty cycle, full volume and period 9: each sequencer step lasts 10 CPU cycles
+    mapper.cpu_write(0x9000 + register_offsets[0], 0b01111111);
+    mapper.cpu_write(0x9000 + register_offsets[1], 9);
+    mapper.cpu_write(0x9000 + register_offsets[2], 0b10000000);
+    expect("pulse output at step 0", get_audio_output(mapper), PULSE_HIGH_OUTPUT)?;
+    mapper.notify_cpu_cycles(70);
+    expect("pulse output at step 7", get_audio_output(mapper), PULSE_HIGH_OUTPUT)?;
+    mapper.notify_cpu_cycles(10);
+    expect("pulse output at step 8", get_audio_output(mapper), 0.0)?;
+
+    //a period write changes the speed of the sequencer but not where it is, so there is no click
+    mapper.cpu_write(0x9000 + register_offsets[1], 200);
+    expect("pulse output after a period write", get_audio_output(mapper), 0.0)?;
+    mapper.cpu_write(0x9000 + register_offsets[1], 9);
+
+    //the halt bit freezes the sequencer
+    mapper.cpu_write(0x9000 + register_offsets[3], 0b00000001);
+    mapper.notify_cpu_cycles(80);
+    mapper.cpu_write(0x9000 + register_offsets[3], 0b00000000);
+    expect("pulse output after a halt", get_audio_output(mapper), 0.0)?;
+    mapper.notify_cpu_cycles(80);
+    expect("pulse output 8 steps later", get_audio_output(mapper), PULSE_HIGH_OUTPUT)?;
+    mapper.cpu_write(0x9000 + register_offsets[2], 0b00000000);
+    expect("disabled pulse output", get_audio_output(mapper), 0.0)?;
+
+    //sawtooth with period 0: the rate is added on every second CPU cycle, the 14th cycle resets the accumulator
+    mapper.cpu_write(0xB000 + register_offsets[0], 42);
+    mapper.cpu_write(0xB000 + register_offsets[1], 0);
+    mapper.cpu_write(0xB000 + register_offsets[2], 0b10000000);
+    mapper.notify_cpu_cycles(2);
+    expect("sawtooth output after one add", get_audio_output(mapper), 0.00752 * ((42 >> 3) as f64))?;
+    mapper.notify_cpu_cycles(10);
+    expect("sawtooth output after six adds", get_audio_output(mapper), 0.00752 * ((252 >> 3) as f64))?;
+    mapper.notify_cpu_cycles(2);
+    expect("sawtooth output after the reset", get_audio_output(mapper), 0.0)?;
+    mapper.cpu_write(0xB000 + register_offsets[2], 0b00000000);
+    return Ok(());
+}
+
+pub fn test_vrc6_mapper24() -> Result<()>
+{
+    return test_vrc6(24, [0, 1, 2, 3]);
+}
+
+pub fn test_vrc6_mapper26() -> Result<()>
+{
+    return test_vrc6(26, [0, 2, 1, 3]);
+}
@@ -0,0 +1,65 @@
+use anyhow::{anyhow, Context, Result};
+use crate::codeloc;
+use crate::system::byte;
+use crate::system::mappers::Mapper;
+use crate::system::ppu::mirroring::Mirroring;
+use crate::system::test::mapper_test::{build_mapper, character_bank_at, expect, program_bank_at, KB};
+use crate::system::test::mapper_test::vrc_irq_test::{test_vrc_irq, VRCIRQLatchRegisters, VRCIRQRegisters};
+
+fn get_vrc7_peak_amplitude(mapper : &dyn Mapper) -> f64
+{
+    let audio = mapper.expansion_audio_channels()[0];
+    return (0..1000).map(|index| audio.synthesize((index as f64) / 44100.0).abs()).fold(0.0, f64::max);
+}
+
+fn write_vrc7_audio_register(mapper : &mut dyn Mapper, register : byte, value : byte)
+{
+    mapper.cpu_write(0x9010, register);
+    mapper.cpu_write(0x9030, value);
+}
+
+pub fn test_vrc7() -> Result<()>
+{
+    let mut mapper = build_mapper(85, None, 16, 32).context(codeloc!())?;
+    mapper.cpu_write(0x8000, 3);
+    mapper.cpu_write(0x8010, 4);
+    mapper.cpu_write(0x9000, 5);
+    expect("PRG at $8000", program_bank_at(mapper.as_mut(), 0x8000, 8*KB), 3)?;
+    expect("PRG at $A000", program_bank_at(mapper.as_mut(), 0xA000, 8*KB), 4)?;
+    expect("PRG at $C000", program_bank_at(mapper.as_mut(), 0xC000, 8*KB), 5)?;
+    expect("fixed PRG at $E000", program_bank_at(mapper.as_mut(), 0xE000, 8*KB), 31)?;
+
+    for register_index in 0..8
+    {
+        let raw_address = 0xA000 + (register_index/2)*0x1000 + (register_index%2)*0x10;
+        mapper.cpu_write(raw_address, 10 + register_index as byte);
+        expect("CHR window", character_bank_at(mapper.as_ref(), register_index*0x400, KB), 10 + register_index as usize)?;
+    }
+
+    mapper.cpu_write(0xE000, 0b00000001);
+    expect("mirroring", mapper.mirroring(), Mirroring::Horizontal)?;
+
+    //instrument 1 (buzzy bell) on channel 0: silent before key on, audible after the attack,
+    //silent again once the release is over
+    expect("silent before key on", get_vrc7_peak_amplitude(mapper.as_ref()), 0.0)?;
+    write_vrc7_audio_register(mapper.as_mut(), 0x30, 0x10);
+    write_vrc7_audio_register(mapper.as_mut(), 0x10, 0x80);
+    write_vrc7_audio_register(mapper.as_mut(), 0x20, 0x14);
+    mapper.notify_cpu_cycles(36*1000);
+    let peak_amplitude_after_attack = get_vrc7_peak_amplitude(mapper.as_ref());
+    if peak_amplitude_after_attack < 0.01 { return Err(anyhow!("VRC7 channel is silent after key on")); }
+    write_vrc7_audio_register(mapper.as_mut(), 0x20, 0x04);
+    mapper.notify_cpu_cycles(36*10);
+    let peak_amplitude_during_release = get_vrc7_peak_amplitude(mapper.as_ref());
+    if peak_amplitude_during_release <= 0.0 { return Err(anyhow!("VRC7 channel was cut without a release")); }
+    mapper.notify_cpu_cycles(2_000_000);
+    expect("silent after release", get_vrc7_peak_amplitude(mapper.as_ref()), 0.0)?;
+
+    let irq_registers = VRCIRQRegisters
+    {
+        latch: VRCIRQLatchRegisters::Byte(0xE010),
+        control_address: 0xF000,
+        acknowledge_address: 0xF010,
+    };
+    return test_vrc_irq(mapper.as_mut(), &irq_registers);
+}
@@ -0,0 +1,69 @@
+use anyhow::Result;
+use crate::system::{address, byte};
+use crate::system::mappers::Mapper;
+use crate::system::test::mapper_test::expect;
+
+//the IRQ counter shared by VRC4, VRC6 and VRC7, reached through each board's own register addresses
+//VRC4 writes the latch as two nibbles, VRC6 and VRC7 as one byte
+pub enum VRCIRQLatchRegisters
+{
+    Nibbles(address, address),
+    Byte(address),
+}
+
+pub struct VRCIRQRegisters
+{
+    pub latch : VRCIRQLatchRegisters,
+    pub control_address : address,
+    pub acknowledge_address : address,
+}
+
+fn write_latch(mapper : &mut dyn Mapper, registers : &VRCIRQRegisters, latch : byte)
+{
+    match registers.latch
+    {
+        VRCIRQLatchRegisters::Nibbles(low_nibble_address, high_nibble_address) =>
+        {
+            mapper.cpu_write(low_nibble_address, latch & 0x0F);
+            mapper.cpu_write(high_nibble_address, latch >> 4);
+        }
+        VRCIRQLatchRegisters::Byte(raw_address) => { mapper.cpu_write(raw_address, latch); }
+    }
+}
+
+pub fn test_vrc_irq(mapper : &mut dyn Mapper, registers : &VRCIRQRegisters) -> Result<()>
+{
+    //cycle mode: the 8 bit up counter starts at the latch and overflows from $FF
+    write_latch(mapper, registers, 0xFE);
+    mapper.cpu_write(registers.control_address, 0b00000110);
+    mapper.notify_cpu_cycles(1);
+    expect("cycle mode IRQ pending after 1 cycle", mapper.is_irq_pending(), false)?;
+    mapper.notify_cpu_cycles(1);
+    expect("cycle mode IRQ pending after 2 cycles", mapper.is_irq_pending(), true)?;
+
+    //acknowledging copies the A bit into the E bit, with A clear the counter stops
+    mapper.cpu_write(registers.acknowledge_address, 0);
+    expect("IRQ pending after acknowledge", mapper.is_irq_pending(), false)?;
+    mapper.notify_cpu_cycles(1000);
+    expect("IRQ pending after acknowledge with A clear", mapper.is_irq_pending(), false)?;
+
+    //with A set, the counter keeps running from the reloaded latch after the acknowledge
+    mapper.cpu_write(registers.control_address, 0b00000111);
+    mapper.notify_cpu_cycles(2);
+    mapper.cpu_write(registers.acknowledge_address, 0);
+    mapper.notify_cpu_cycles(2);
+    expect("IRQ pending after acknowledge with A set", mapper.is_irq_pending(), true)?;
+    mapper.cpu_write(registers.control_address, 0);
+
+    //scanline mode: a 341/3 prescaler clocks the counter once every 113.67 CPU cycles
+    mapper.cpu_write(registers.control_address, 0b00000010);
+    mapper.notify_cpu_cycles(227);
+    expect("scanline mode IRQ pending after 227 cycles", mapper.is_irq_pending(), false)?;
+    mapper.notify_cpu_cycles(1);
+    expect("scanline mode IRQ pending after 2 scanlines", mapper.is_irq_pending(), true)?;
+
+    //writing the control register acknowledges too
+    mapper.cpu_write(registers.control_address, 0);
+    expect("IRQ pending after a control write", mapper.is_irq_pending(), false)?;
+    return Ok(());
+}
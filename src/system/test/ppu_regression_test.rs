@@ -0,0 +1,173 @@
+use std::{env, fs};
+use std::path::{Path, PathBuf};
+use anyhow::{anyhow, Context, Result};
+use crate::codeloc;
+use crate::system::{byte, InMemoryFrameSink, InMemorySampleSink, System, SystemStartArgs};
+use crate::system::scheduler::FRAME_DURATION;
+
+const ROMS_DIRECTORY_VARIABLE : &str = "NES_REGRESSION_ROMS_DIR";
+const RECORD_REFERENCES_VARIABLE : &str = "NES_REGRESSION_RECORD";
+const BUNDLED_ROMS_DIRECTORY : &str = "src/system/test/roms";
+const NUMBER_OF_FRAMES_TO_CAPTURE : usize = 180;
+const REFERENCE_FILE_EXTENSION : &str = "frames.md5";
+const SAMPLE_RATE : u32 = 44100;
+const SAMPLE_COUNT_TOLERANCE : f64 = 0.01; //the APU may be up to 1% short of the ideal sample count
+
+struct RegressionROM
+{
+    name : String,
+    bytes : Vec<byte>,
+    reference_file_path : PathBuf,
+}
+
+//every ROM must render exactly the frames of its reference file, which holds the md5 of each frame, a missing reference fails.
+//the bundled test ROMs are always checked, their references sit next to them in src/system/test/roms.
+//commercial ROMs (SMB1, Donkey Kong, Pinball) cannot be shipped with the emulator. these titles used to rely on ROM-hash
+//timing and scroll special cases, removed with the cycle-driven scheduler and the shared scroll registers. they are
+//also checked when NES_REGRESSION_ROMS_DIR points to a directory with them, each one next to its own reference file.
+//
+//references are only written when NES_REGRESSION_RECORD=1 is set: run the test that way once from a build whose
+//output was checked by eye, then commit the .frames.md5 files. a change that alters the picture on purpose must
+//record them again in the same commit. the baseline renderer drew whole tiles through SDL textures from a free
+//running PPU thread, so it has no deterministic palette index frames to record references from
+pub fn test_ppu_with_regression_roms() -> Result<()>
+{
+    let should_record_references = env::var(RECORD_REFERENCES_VARIABLE).map(|value| value == "1").unwrap_or(false);
+
+    let mut regression_roms = get_bundled_regression_roms();
+    match env::var(ROMS_DIRECTORY_VARIABLE)
+    {
+        Ok(roms_directory) => { regression_roms.extend(get_regression_roms_from_directory(Path::new(&roms_directory)).context(codeloc!())?); }
+        Err(_) => { println!("[PPU] {} is not set, only the bundled ROMs are checked", ROMS_DIRECTORY_VARIABLE); }
+    }
+
+    let mut failed_rom_names : Vec<String> = Vec::new();
+    for regression_rom in regression_roms
+    {
+        let rom_name = regression_rom.name;
+        let frame_hashes = capture_frame_hashes(&rom_name, regression_rom.bytes).context(codeloc!())?;
+
+        let reference_file_path = regression_rom.reference_file_path;
+        if should_record_references
+        {
+            fs::write(&reference_file_path, frame_hashes.join("\n")).context(codeloc!())?;
+            println!("[PPU] {}: recorded {} reference frames", rom_name, frame_hashes.len());
+            continue;
+        }
+
+        if !reference_file_path.exists()
+        {
+            println!("[PPU] {}: FAILED! missing reference file {}", rom_name, reference_file_path.display());
+            failed_rom_names.push(rom_name);
+            continue;
+        }
+
+        let reference_file_contents = fs::read_to_string(&reference_file_path).context(codeloc!())?;
+        let reference_frame_hashes = reference_file_contents.lines().map(|line| line.trim()).collect::<Vec<&str>>();
+        let first_mismatch = reference_frame_hashes.iter().zip(frame_hashes.iter())
+            .position(|(expected, actual)| *expected != actual);
+
+        if reference_frame_hashes.len() != frame_hashes.len()
+        {
+            println!("[PPU] {}: FAILED! rendered {} frames, the reference has {}", rom_name, frame_hashes.len(), reference_frame_hashes.len());
+            failed_rom_names.push(rom_name);
+        }
+        else if let Some(frame_index) = first_mismatch
+        {
+            println!("[PPU] {}: FAILED! frame {} differs from the reference", rom_name, frame_index);
+            failed_rom_names.push(rom_name);
+        }
+        else
+        {
+            println!("[PPU] {}: PASSED!", rom_name);
+        }
+    }
+
+    if !failed_rom_names.is_empty()
+    {
+        return Err(anyhow!("[PPU] Regression test failed for: {}", failed_rom_names.join(", ")));
+    }
+
+    return Ok(());
+}
+
+fn get_bundled_regression_roms() -> Vec<RegressionROM>
+{
+    let bundled_roms : [(&str, &[byte]); 3] =
+    [
+        ("ppu_blocks_test.nes", include_bytes!("roms/ppu_blocks_test.nes")),
+        ("ppu_physics_test.nes", include_bytes!("roms/ppu_physics_test.nes")),
+        ("ppu_spritecans_test.nes", include_bytes!("roms/ppu_spritecans_test.nes")),
+    ];
+
+    let bundled_roms_directory = Path::new(env!("CARGO_MANIFEST_DIR")).join(BUNDLED_ROMS_DIRECTORY);
+    return bundled_roms.iter().map(|(rom_name, rom_bytes)| RegressionROM
+    {
+        name: rom_name.to_string(),
+        bytes: rom_bytes.to_vec(),
+        reference_file_path: bundled_roms_directory.join(rom_name).with_extension(REFERENCE_FILE_EXTENSION),
+    }).collect();
+}
+
+fn get_regression_roms_from_directory(roms_directory : &Path) -> Result<Vec<RegressionROM>>
+{
+    let mut rom_file_paths = fs::read_dir(roms_directory).context(codeloc!())?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().map(|extension| extension == "nes").unwrap_or(false))
+        .collect::<Vec<PathBuf>>();
+    rom_file_paths.sort();
+
+    let mut regression_roms : Vec<RegressionROM> = Vec::new();
+    for rom_file_path in rom_file_paths
+    {
+        regression_roms.push(RegressionROM
+        {
+            name: rom_file_path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+            bytes: fs::read(&rom_file_path).context(codeloc!())?,
+            reference_file_path: rom_file_path.with_extension(REFERENCE_FILE_EXTENSION),
+        });
+    }
+
+    return Ok(regression_roms);
+}
+
+fn capture_frame_hashes(rom_name : &str, rom_bytes : Vec<byte>) -> Result<Vec<String>>
+{
+    let frame_sink = InMemoryFrameSink::new();
+    let sample_sink = InMemorySampleSink::new(SAMPLE_RATE);
+
+    //headless: no window and no audio device are needed, the PPU and APU are still fully emulated
+    let mut start_args = SystemStartArgs::with_rom_bytes(rom_bytes.into_boxed_slice()).context(codeloc!())?;
+    start_args.should_disable_video = true;
+    start_args.should_disable_audio = true;
+    start_args.frame_sink = Some(Box::new(frame_sink.clone()));
+    start_args.sample_sink = Some(Box::new(sample_sink.clone()));
+    start_args.number_of_frames_to_run = Some(NUMBER_OF_FRAMES_TO_CAPTURE);
+
+    System::start(start_args).context(codeloc!())?.await_termination();
+
+    let frames = frame_sink.take_frames();
+    let frame_hashes = frames.iter()
+        .map(|frame| frame.iter().flat_map(|color_index| color_index.to_le_bytes()).collect::<Vec<byte>>())
+        .map(|frame_bytes| format!("{:x}", md5::compute(&frame_bytes)))
+        .collect::<Vec<String>>();
+
+    //a ROM that did not boot leaves the screen filled with the backdrop color
+    let last_frame = frames.last().context(codeloc!())?;
+    if last_frame.iter().all(|color_index| *color_index == last_frame[0])
+    {
+        return Err(anyhow!("[PPU] {} did not render anything!", rom_name));
+    }
+
+    //one NTSC frame lasts FRAME_DURATION, so the APU must have produced SAMPLE_RATE*FRAME_DURATION samples per frame
+    let number_of_samples = sample_sink.take_samples().len();
+    let ideal_number_of_samples = (frames.len() as f64) * (SAMPLE_RATE as f64) * FRAME_DURATION.as_secs_f64();
+    let expected_number_of_samples = (ideal_number_of_samples * (1.0 - SAMPLE_COUNT_TOLERANCE)) as usize;
+    if number_of_samples < expected_number_of_samples
+    {
+        return Err(anyhow!("[APU] {} produced only {} audio samples, expected at least {}!",
+            rom_name, number_of_samples, expected_number_of_samples));
+    }
+
+    return Ok(frame_hashes);
+}
@@ -1,6 +1,92 @@
-use anyhow::{Result, Context};
+use std::io::{Cursor, Read};
+use anyhow::{anyhow, Result, Context};
+use zip::ZipArchive;
 use crate::codeloc;
-use crate::system::{System, SystemStartArgs};
+use crate::system::{address, byte, System, SystemStartArgs};
+use crate::system::debugger::RAMSnapshot;
+
+//https://github.com/christopherpow/nes-test-roms, the result protocols are described in the readme of each blargg ROM set.
+//newer ROMs write their status to $6000 once the signature DE B0 61 is at $6001-$6003:
+//$80 = still running, $81 = needs a reset, anything below $80 is the final result, 0 = passed
+const STATUS_ADDRESS : address = 0x6000;
+const SIGNATURE_ADDRESSES : [address; 3] = [0x6001, 0x6002, 0x6003];
+const SIGNATURE : [byte; 3] = [0xDE, 0xB0, 0x61];
+const RUNNING_STATUS : byte = 0x80;
+const PASSED_STATUS : byte = 0x00;
+//the 2005 ROMs (sprite_hit_tests_2005.10.05, sprite_overflow_tests) predate it, they show a result code on screen and keep it in $F8:
+//1 = passed, 2+ = number of the failed test. their reset code already stores 1 there and every test stores its own number
+//before it runs, so a 1 only means passed once a test number was seen
+const ZERO_PAGE_RESULT_ADDRESS : address = 0xF8;
+const ZERO_PAGE_PASSED_RESULT_CODE : byte = 1;
+const BLARGG_NUMBER_OF_FRAMES_TO_RUN : usize = 300;
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum BlarggTestResult
+{
+    Passed,
+    Failed(byte),
+    Unfinished,
+}
+
+//runs the ROM headless, the debugger reports the result bytes every time one of them changes
+pub fn run_blargg_testrom_headless(rom_bytes : Box<[byte]>, number_of_frames_to_run : usize) -> Result<BlarggTestResult>
+{
+    let (sender, receiver) = flume::unbounded::<RAMSnapshot>();
+
+    let mut start_args = SystemStartArgs::with_rom_bytes(rom_bytes).context(codeloc!())?;
+    start_args.should_disable_video = true;
+    start_args.should_disable_audio = true;
+    start_args.number_of_frames_to_run = Some(number_of_frames_to_run);
+    start_args.cpu_debugger.cpu_bus_watcher_targets = vec![ZERO_PAGE_RESULT_ADDRESS, STATUS_ADDRESS, SIGNATURE_ADDRESSES[0], SIGNATURE_ADDRESSES[1], SIGNATURE_ADDRESSES[2]];
+    start_args.cpu_debugger.cpu_bus_watcher = Some(sender);
+
+    let running_system = System::start(start_args).context(codeloc!())?;
+    let ram_snapshots = receiver.iter().collect::<Vec<RAMSnapshot>>();
+    running_system.await_termination();
+
+    let has_signature = |ram_snapshot : &&RAMSnapshot| SIGNATURE_ADDRESSES.iter().zip(SIGNATURE.iter())
+        .all(|(address, value)| ram_snapshot.variables.get(address) == Some(value));
+
+    if ram_snapshots.iter().any(|ram_snapshot| has_signature(&ram_snapshot))
+    {
+        //PRG-RAM may already hold a result-like value before the ROM starts, so a result counts only after $80 was seen
+        let final_status = ram_snapshots.iter().filter(has_signature)
+            .filter_map(|ram_snapshot| ram_snapshot.variables.get(&STATUS_ADDRESS).cloned())
+            .skip_while(|status| *status != RUNNING_STATUS)
+            .find(|status| *status < RUNNING_STATUS);
+
+        return Ok(match final_status
+        {
+            Some(PASSED_STATUS) => BlarggTestResult::Passed,
+            Some(status) => BlarggTestResult::Failed(status),
+            None => BlarggTestResult::Unfinished,
+        });
+    }
+
+    let last_result_code = ram_snapshots.iter()
+        .filter_map(|ram_snapshot| ram_snapshot.variables.get(&ZERO_PAGE_RESULT_ADDRESS).cloned())
+        .skip_while(|result_code| *result_code == ZERO_PAGE_PASSED_RESULT_CODE)
+        .last();
+
+    return Ok(match last_result_code
+    {
+        Some(ZERO_PAGE_PASSED_RESULT_CODE) => BlarggTestResult::Passed,
+        Some(result_code) => BlarggTestResult::Failed(result_code),
+        None => BlarggTestResult::Unfinished,
+    });
+}
+
+fn check_blargg_testrom(rom_name : &str, rom_bytes : Box<[byte]>) -> Result<()>
+{
+    match run_blargg_testrom_headless(rom_bytes, BLARGG_NUMBER_OF_FRAMES_TO_RUN).context(codeloc!())?
+    {
+        BlarggTestResult::Passed => { println!("[PPU] {}: PASSED!", rom_name); }
+        BlarggTestResult::Failed(result_code) => { return Err(anyhow!("[PPU] {}: FAILED! result code {}", rom_name, result_code)); }
+        BlarggTestResult::Unfinished => { return Err(anyhow!("[PPU] {}: FAILED! no result after {} frames", rom_name, BLARGG_NUMBER_OF_FRAMES_TO_RUN)); }
+    }
+
+    return Ok(());
+}
 
 pub fn test_ppu_with_blocks_testrom() -> Result<()>
 {
@@ -34,3 +120,36 @@ pub fn test_ppu_with_spritecans_testrom() -> Result<()>
 
     return Ok(());
 }
+
+pub fn test_ppu_with_sprite_overflow_testrom() -> Result<()>
+{
+    let rom_bytes = *include_bytes!("roms/ppu_sprite_overflow_test.nes");
+    return check_blargg_testrom("ppu_sprite_overflow_test.nes", Box::new(rom_bytes));
+}
+
+pub fn test_ppu_with_sprite_zero_hit_testroms() -> Result<()>
+{
+    let zip_archive_bytes = *include_bytes!("roms/ppu_sprite_zero_hit_test.zip");
+    let mut zip_archive = ZipArchive::new(Cursor::new(zip_archive_bytes)).context(codeloc!())?;
+    let mut failed_rom_names : Vec<String> = Vec::new();
+    for i in 0..zip_archive.len()
+    {
+        let mut rom_bytes: Vec<byte> = Vec::new();
+        let mut zipped_file = zip_archive.by_index(i).context(codeloc!())?;
+        zipped_file.read_to_end(&mut rom_bytes).context(codeloc!())?;
+
+        let rom_name = zipped_file.name().to_string();
+        if let Err(error) = check_blargg_testrom(&rom_name, rom_bytes.into_boxed_slice())
+        {
+            println!("{}", error);
+            failed_rom_names.push(rom_name);
+        }
+    }
+
+    if !failed_rom_names.is_empty()
+    {
+        return Err(anyhow!("[PPU] Sprite zero hit test failed for: {}", failed_rom_names.join(", ")));
+    }
+
+    return Ok(());
+}
@@ -0,0 +1,264 @@
+use std::{env, fs};
+use std::path::PathBuf;
+use std::fmt::Debug;
+use anyhow::{anyhow, Context, Result};
+use crate::codeloc;
+use crate::system::byte;
+use crate::system::debugger::PPUDebugger;
+use crate::system::mappers::{MapperRegistry, SharedMapper};
+use crate::system::ppu::communication::CPUToPPUCommTarget;
+use crate::system::ppu::system_palette::SystemPalette;
+use crate::system::ppu::PPU;
+use crate::system::rom::ROMParser;
+use crate::system::test::ppu_testsroms::{run_blargg_testrom_headless, BlarggTestResult};
+
+const ROMS_DIRECTORY_VARIABLE : &str = "NES_PPU_VBL_NMI_ROMS_DIR";
+const NUMBER_OF_FRAMES_TO_RUN : usize = 1800;
+
+//https://www.nesdev.org/wiki/PPU_frame_timing
+const NUMBER_OF_DOTS_PER_SCANLINE : usize = 341;
+const NUMBER_OF_DOTS_PER_FRAME : usize = 262*NUMBER_OF_DOTS_PER_SCANLINE;
+//number of dots from power-on until the vblank flag is set on dot 1 of scanline 241, and cleared on dot 1 of scanline 261
+const VBLANK_FLAG_SET_DOT_INDEX : usize = 241*NUMBER_OF_DOTS_PER_SCANLINE + 1;
+const VBLANK_FLAG_CLEAR_DOT_INDEX : usize = 261*NUMBER_OF_DOTS_PER_SCANLINE + 1;
+const VBLANK_FLAG_MASK : byte = 0b10000000;
+const NMI_ENABLE_MASK : byte = 0b10000000;
+const RENDERING_ENABLE_MASK : byte = 0b00011000;
+
+//the VBL flag / NMI timing is checked dot by dot on a bare PPU, the same cases blargg's ppu_vbl_nmi suite checks through the CPU.
+//the suite itself (01-vbl_basics ... 10-even_odd_timing) is not bundled, it is also run when NES_PPU_VBL_NMI_ROMS_DIR
+//points to its rom_singles directory, every ROM must report status 0 through the $6000 protocol
+pub fn test_ppu_with_vbl_nmi_testroms() -> Result<()>
+{
+    let tests = vec!
+    [
+        ("vbl basics", test_vbl_basics as fn() -> Result<()>),
+        ("vbl clear time", test_vbl_clear_time),
+        ("vbl set time", test_vbl_set_time),
+        ("nmi control", test_nmi_control),
+        ("nmi suppression", test_nmi_suppression),
+        ("even odd frames", test_even_odd_frames),
+    ];
+
+    let mut failed_test_names : Vec<String> = Vec::new();
+    for (test_name, test) in tests
+    {
+        match test()
+        {
+            Ok(()) => { println!("[PPU] {}: PASSED!", test_name); }
+            Err(error) =>
+            {
+                println!("[PPU] {}: FAILED! {:?}", test_name, error);
+                failed_test_names.push(test_name.to_string());
+            }
+        }
+    }
+
+    match env::var(ROMS_DIRECTORY_VARIABLE)
+    {
+        Ok(roms_directory) => { failed_test_names.extend(run_testroms_from_directory(&roms_directory).context(codeloc!())?); }
+        Err(_) => { println!("[PPU] {} is not set, the ppu_vbl_nmi ROMs are not run", ROMS_DIRECTORY_VARIABLE); }
+    }
+
+    if !failed_test_names.is_empty()
+    {
+        return Err(anyhow!("[PPU] VBL/NMI test failed for: {}", failed_test_names.join(", ")));
+    }
+
+    return Ok(());
+}
+
+//returns the names of the failed ROMs
+fn run_testroms_from_directory(roms_directory : &str) -> Result<Vec<String>>
+{
+    let mut rom_file_paths = fs::read_dir(roms_directory).context(codeloc!())?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().map(|extension| extension == "nes").unwrap_or(false))
+        .collect::<Vec<PathBuf>>();
+    rom_file_paths.sort();
+
+    if rom_file_paths.is_empty()
+    {
+        return Err(anyhow!("[PPU] No .nes files found in {}!", roms_directory));
+    }
+
+    let mut failed_rom_names : Vec<String> = Vec::new();
+    for rom_file_path in rom_file_paths
+    {
+        let rom_name = rom_file_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+        let rom_bytes = fs::read(&rom_file_path).context(codeloc!())?;
+        match run_blargg_testrom_headless(rom_bytes.into_boxed_slice(), NUMBER_OF_FRAMES_TO_RUN).context(codeloc!())?
+        {
+            BlarggTestResult::Passed => { println!("[PPU] {}: PASSED!", rom_name); }
+            BlarggTestResult::Failed(status) => { println!("[PPU] {}: FAILED! status {:02X}", rom_name, status); failed_rom_names.push(rom_name); }
+            BlarggTestResult::Unfinished => { println!("[PPU] {}: FAILED! no result after {} frames", rom_name, NUMBER_OF_FRAMES_TO_RUN); failed_rom_names.push(rom_name); }
+        }
+    }
+
+    return Ok(failed_rom_names);
+}
+
+//a PPU on top of an NROM cartridge, right after power-on: scanline 0, dot 0
+fn build_ppu() -> Result<PPU>
+{
+    let mut bytes = b"NES\x1A\x01\x01".to_vec();
+    bytes.resize(16 + 16*1024 + 8*1024, 0);
+    let parsed_rom = ROMParser::parse(bytes.into_boxed_slice()).context(codeloc!())?;
+    let mapper = SharedMapper::new(MapperRegistry::build(parsed_rom).context(codeloc!())?);
+    let system_colors = SystemPalette::Default.load_colors().context(codeloc!())?;
+    return Ok(PPU::new(mapper, system_colors, PPUDebugger::new(), false));
+}
+
+fn run_dots(ppu : &mut PPU, number_of_dots : usize)
+{
+    for _ in 0..number_of_dots { ppu.step(); }
+}
+
+fn read_status(ppu : &mut PPU) -> byte
+{
+    return ppu.handle_read_command_from_cpu(CPUToPPUCommTarget::StatusFlags);
+}
+
+fn write_control(ppu : &mut PPU, value : byte)
+{
+    ppu.handle_write_command_from_cpu(CPUToPPUCommTarget::ControlFlags, &[value]);
+}
+
+fn expect<T : PartialEq + Debug>(description : &str, actual : T, expected : T) -> Result<()>
+{
+    if actual != expected
+    {
+        return Err(anyhow!("{}: expected {:?}, found {:?}", description, expected, actual));
+    }
+
+    return Ok(());
+}
+
+fn test_vbl_basics() -> Result<()>
+{
+    let mut ppu = build_ppu().context(codeloc!())?;
+    run_dots(&mut ppu, VBLANK_FLAG_SET_DOT_INDEX + 10);
+    expect("VBL flag on the first read", read_status(&mut ppu) & VBLANK_FLAG_MASK, VBLANK_FLAG_MASK)?;
+    expect("VBL flag on the second read", read_status(&mut ppu) & VBLANK_FLAG_MASK, 0)?;
+
+    //the flag comes back one frame later
+    run_dots(&mut ppu, NUMBER_OF_DOTS_PER_FRAME);
+    expect("VBL flag one frame later", read_status(&mut ppu) & VBLANK_FLAG_MASK, VBLANK_FLAG_MASK)?;
+    return Ok(());
+}
+
+fn test_vbl_clear_time() -> Result<()>
+{
+    let mut ppu = build_ppu().context(codeloc!())?;
+    run_dots(&mut ppu, VBLANK_FLAG_CLEAR_DOT_INDEX - 1);
+    expect("VBL flag before the pre-render scanline", ppu.status_flags.has_vblank_started, true)?;
+    run_dots(&mut ppu, 1);
+    expect("VBL flag on the pre-render scanline", ppu.status_flags.has_vblank_started, false)?;
+    return Ok(());
+}
+
+fn test_vbl_set_time() -> Result<()>
+{
+    //a read one dot before the flag is set returns it clear and keeps it clear for the whole frame
+    let mut ppu = build_ppu().context(codeloc!())?;
+    run_dots(&mut ppu, VBLANK_FLAG_SET_DOT_INDEX - 1);
+    expect("VBL flag read one dot early", read_status(&mut ppu) & VBLANK_FLAG_MASK, 0)?;
+    run_dots(&mut ppu, 10);
+    expect("VBL flag after a read one dot early", read_status(&mut ppu) & VBLANK_FLAG_MASK, 0)?;
+
+    //a read on the dot the flag is set returns it
+    let mut ppu = build_ppu().context(codeloc!())?;
+    run_dots(&mut ppu, VBLANK_FLAG_SET_DOT_INDEX);
+    expect("VBL flag read on time", read_status(&mut ppu) & VBLANK_FLAG_MASK, VBLANK_FLAG_MASK)?;
+    return Ok(());
+}
+
+fn test_nmi_control() -> Result<()>
+{
+    //the NMI fires when vblank starts with NMI enabled, and only once
+    let mut ppu = build_ppu().context(codeloc!())?;
+    write_control(&mut ppu, NMI_ENABLE_MASK);
+    run_dots(&mut ppu, VBLANK_FLAG_SET_DOT_INDEX - 1);
+    expect("NMI before vblank", ppu.is_signaling_nmi(), false)?;
+    run_dots(&mut ppu, 1);
+    expect("NMI at vblank start", ppu.is_signaling_nmi(), true)?;
+    expect("NMI after it was taken", ppu.is_signaling_nmi(), false)?;
+
+    //enabling NMI while the flag is set fires it right away, enabling it again without disabling it does not
+    let mut ppu = build_ppu().context(codeloc!())?;
+    write_control(&mut ppu, 0);
+    run_dots(&mut ppu, VBLANK_FLAG_SET_DOT_INDEX + 10);
+    expect("NMI with NMI disabled", ppu.is_signaling_nmi(), false)?;
+    write_control(&mut ppu, NMI_ENABLE_MASK);
+    expect("NMI after enabling it in vblank", ppu.is_signaling_nmi(), true)?;
+    write_control(&mut ppu, NMI_ENABLE_MASK);
+    expect("NMI after enabling it twice", ppu.is_signaling_nmi(), false)?;
+    write_control(&mut ppu, 0);
+    write_control(&mut ppu, NMI_ENABLE_MASK);
+    expect("NMI after toggling it in vblank", ppu.is_signaling_nmi(), true)?;
+
+    //once the flag was read, enabling NMI has no effect
+    write_control(&mut ppu, 0);
+    read_status(&mut ppu);
+    write_control(&mut ppu, NMI_ENABLE_MASK);
+    expect("NMI after enabling it with the flag cleared", ppu.is_signaling_nmi(), false)?;
+    return Ok(());
+}
+
+fn test_nmi_suppression() -> Result<()>
+{
+    //dots after power-on at which the status is read, and whether the NMI of that frame still fires
+    let cases = [(VBLANK_FLAG_SET_DOT_INDEX - 1, false), (VBLANK_FLAG_SET_DOT_INDEX, false), (VBLANK_FLAG_SET_DOT_INDEX + 1, false), (VBLANK_FLAG_SET_DOT_INDEX + 2, true)];
+    for (read_dot_index, should_fire_nmi) in cases
+    {
+        let mut ppu = build_ppu().context(codeloc!())?;
+        write_control(&mut ppu, NMI_ENABLE_MASK);
+        run_dots(&mut ppu, read_dot_index);
+        read_status(&mut ppu);
+        run_dots(&mut ppu, 10);
+        let description = format!("NMI with a read {} dots after the flag is set", read_dot_index as isize - VBLANK_FLAG_SET_DOT_INDEX as isize);
+        expect(&description, ppu.is_signaling_nmi(), should_fire_nmi)?;
+    }
+
+    return Ok(());
+}
+
+fn test_even_odd_frames() -> Result<()>
+{
+    //with rendering enabled the pre-render scanline of every odd frame skips a dot
+    let mut ppu = build_ppu().context(codeloc!())?;
+    ppu.handle_write_command_from_cpu(CPUToPPUCommTarget::MaskFlags, &[RENDERING_ENABLE_MASK]);
+    run_dots(&mut ppu, VBLANK_FLAG_SET_DOT_INDEX);
+    read_status(&mut ppu);
+    for (frame_index, expected_number_of_dots) in [NUMBER_OF_DOTS_PER_FRAME, NUMBER_OF_DOTS_PER_FRAME - 1, NUMBER_OF_DOTS_PER_FRAME].iter().enumerate()
+    {
+        let mut number_of_dots = 0;
+        while !ppu.status_flags.has_vblank_started
+        {
+            run_dots(&mut ppu, 1);
+            number_of_dots += 1;
+        }
+
+        read_status(&mut ppu);
+        expect(&format!("dots in frame {} with rendering enabled", frame_index), number_of_dots, *expected_number_of_dots)?;
+    }
+
+    //with rendering disabled every frame has the same length
+    let mut ppu = build_ppu().context(codeloc!())?;
+    run_dots(&mut ppu, VBLANK_FLAG_SET_DOT_INDEX);
+    read_status(&mut ppu);
+    for frame_index in 0..2
+    {
+        let mut number_of_dots = 0;
+        while !ppu.status_flags.has_vblank_started
+        {
+            run_dots(&mut ppu, 1);
+            number_of_dots += 1;
+        }
+
+        read_status(&mut ppu);
+        expect(&format!("dots in frame {} with rendering disabled", frame_index), number_of_dots, NUMBER_OF_DOTS_PER_FRAME)?;
+    }
+
+    return Ok(());
+}
@@ -0,0 +1,217 @@
+use std::fmt::Debug;
+use anyhow::{anyhow, Context, Result};
+use crate::codeloc;
+use crate::system::byte;
+use crate::system::ppu::mirroring::Mirroring;
+use crate::system::rom::header::{ConsoleType, RomFormat, RomHeader, TVSystem, HEADER_SIZE, TRAINER_SIZE};
+use crate::system::rom::ROMParser;
+
+const PROGRAM_ROM_UNIT_SIZE : usize = 16*1024;
+const CHARACTER_ROM_UNIT_SIZE : usize = 8*1024;
+
+pub fn test_rom_header() -> Result<()>
+{
+    let tests = vec!
+    [
+        ("iNES", test_ines as fn() -> Result<()>),
+        ("trainer", test_trainer),
+        ("NES 2.0 ROM sizes", test_nes2_rom_sizes),
+        ("NES 2.0 mapper and submapper", test_nes2_submapper),
+        ("four-screen mirroring", test_four_screen_mirroring),
+        ("archaic iNES", test_archaic_ines),
+        ("console type", test_console_type),
+    ];
+
+    let mut failed_test_names : Vec<&str> = Vec::new();
+    for (test_name, test) in tests
+    {
+        match test()
+        {
+            Ok(()) => { println!("[ROM] {}: PASSED!", test_name); }
+            Err(error) =>
+            {
+                println!("[ROM] {}: FAILED! {:?}", test_name, error);
+                failed_test_names.push(test_name);
+            }
+        }
+    }
+
+    if !failed_test_names.is_empty()
+    {
+        return Err(anyhow!("[ROM] Header test failed for: {}", failed_test_names.join(", ")));
+    }
+
+    return Ok(());
+}
+
+fn build_header(flags : &[(usize, byte)]) -> [byte; HEADER_SIZE]
+{
+    let mut header = [0 as byte; HEADER_SIZE];
+    header[0..4].copy_from_slice(b"NES\x1A");
+    for (index, value) in flags { header[*index] = *value; }
+    return header;
+}
+
+//a file holding the header followed by file_size-HEADER_SIZE bytes of zeroes
+fn build_file(header : &[byte; HEADER_SIZE], file_size : usize) -> Vec<byte>
+{
+    let mut bytes = header.to_vec();
+    bytes.resize(file_size.max(HEADER_SIZE), 0);
+    return bytes;
+}
+
+fn expect<T : PartialEq + Debug>(description : &str, actual : T, expected : T) -> Result<()>
+{
+    if actual != expected
+    {
+        return Err(anyhow!("{}: expected {:?}, found {:?}", description, expected, actual));
+    }
+
+    return Ok(());
+}
+
+fn test_ines() -> Result<()>
+{
+    //mapper 0x42, battery, vertical mirroring, 2 PRG units, 1 CHR unit, 2 PRG-RAM units, PAL
+    let header = build_header(&[(4, 2), (5, 1), (6, 0x23), (7, 0x40), (8, 2), (9, 1)]);
+    let parsed_header = RomHeader::parse(&build_file(&header, HEADER_SIZE)).context(codeloc!())?;
+    expect("format", parsed_header.format, RomFormat::INES)?;
+    expect("mapper", parsed_header.mapper, 0x42)?;
+    expect("PRG-ROM size", parsed_header.program_rom_size, 2*PROGRAM_ROM_UNIT_SIZE)?;
+    expect("CHR-ROM size", parsed_header.character_rom_size, CHARACTER_ROM_UNIT_SIZE)?;
+    expect("CHR-RAM size", parsed_header.character_ram_size, 0)?;
+    expect("PRG-NVRAM size", parsed_header.program_nvram_size, 16*1024)?;
+    expect("mirroring", parsed_header.mirroring, Mirroring::Vertical)?;
+    expect("TV system", parsed_header.tv_system, TVSystem::PAL)?;
+
+    let header = build_header(&[(4, 1)]);
+    let parsed_header = RomHeader::parse(&build_file(&header, HEADER_SIZE)).context(codeloc!())?;
+    expect("CHR-RAM size without CHR-ROM", parsed_header.character_ram_size, 8*1024)?;
+    expect("PRG-RAM size when byte 8 is 0", parsed_header.program_ram_size, 8*1024)?;
+
+    let mut bytes = build_file(&header, HEADER_SIZE);
+    bytes[0] = b'X';
+    expect("bad magic rejected", RomHeader::parse(&bytes).is_err(), true)?;
+    return Ok(());
+}
+
+fn test_trainer() -> Result<()>
+{
+    let header = build_header(&[(4, 1), (5, 1), (6, 0b00000100)]);
+    let mut bytes = build_file(&header, HEADER_SIZE + TRAINER_SIZE + PROGRAM_ROM_UNIT_SIZE + CHARACTER_ROM_UNIT_SIZE);
+    bytes[HEADER_SIZE] = 0xA9; //first trainer byte
+    bytes[HEADER_SIZE + TRAINER_SIZE] = 0x4C; //first PRG-ROM byte
+    bytes[HEADER_SIZE + TRAINER_SIZE + PROGRAM_ROM_UNIT_SIZE] = 0x3C; //first CHR-ROM byte
+
+    let parsed_header = RomHeader::parse(&bytes).context(codeloc!())?;
+    expect("has trainer", parsed_header.has_trainer, true)?;
+    expect("PRG-ROM offset", parsed_header.program_rom_offset(), HEADER_SIZE + TRAINER_SIZE)?;
+    expect("CHR-ROM offset", parsed_header.character_rom_offset(), HEADER_SIZE + TRAINER_SIZE + PROGRAM_ROM_UNIT_SIZE)?;
+
+    let mut parsed_rom = ROMParser::parse(bytes.into_boxed_slice()).context(codeloc!())?;
+    expect("trainer at $7000", parsed_rom.program_ram.get(0x7000), 0xA9)?;
+    expect("PRG-ROM at $8000", parsed_rom.program_rom.get(0x8000), 0x4C)?;
+    expect("CHR-ROM at $0000", parsed_rom.character_rom.get(0x0000), 0x3C)?;
+
+    //loading the trainer must not dirty the RAM, otherwise every trainer ROM would write a .sav file
+    expect("PRG-RAM changed after loading", parsed_rom.program_ram.was_recently_changed(), false)?;
+    parsed_rom.program_ram.load_bytes(&[0x55; 8*1024]);
+    expect("trainer at $7000 after loading a .sav", parsed_rom.program_ram.get(0x7000), 0xA9)?;
+    expect(".sav byte at $6000", parsed_rom.program_ram.get(0x6000), 0x55)?;
+    return Ok(());
+}
+
+fn test_nes2_rom_sizes() -> Result<()>
+{
+    //PRG-ROM: MSB nibble 1, LSB 0x02 -> 0x102 units; CHR-ROM: exponent-multiplier 2^10*(1*2+1) bytes
+    let program_rom_size = 0x102*PROGRAM_ROM_UNIT_SIZE;
+    let character_rom_size = 3*1024;
+    let header = build_header(&[(4, 0x02), (5, (10 << 2) | 1), (7, 0x08), (9, 0xF1)]);
+    let parsed_header = RomHeader::parse(&build_file(&header, HEADER_SIZE + program_rom_size + character_rom_size)).context(codeloc!())?;
+    expect("format", parsed_header.format, RomFormat::NES2)?;
+    expect("PRG-ROM size", parsed_header.program_rom_size, program_rom_size)?;
+    expect("CHR-ROM size", parsed_header.character_rom_size, character_rom_size)?;
+
+    //the largest exponent describes far more than the file holds
+    let header = build_header(&[(4, 0xFF), (7, 0x08), (9, 0x0F)]);
+    expect("oversized PRG-ROM rejected", RomHeader::parse(&build_file(&header, HEADER_SIZE)).is_err(), true)?;
+
+    //RAM sizes are shift counts: 64 << n bytes
+    let header = build_header(&[(7, 0x08), (10, 0x70), (11, 0x07)]);
+    let parsed_header = RomHeader::parse(&build_file(&header, HEADER_SIZE)).context(codeloc!())?;
+    expect("PRG-RAM size", parsed_header.program_ram_size, 0)?;
+    expect("PRG-NVRAM size", parsed_header.program_nvram_size, 8*1024)?;
+    expect("CHR-RAM size", parsed_header.character_ram_size, 8*1024)?;
+    return Ok(());
+}
+
+fn test_nes2_submapper() -> Result<()>
+{
+    //mapper bits 8-11 come from byte 8 low nibble, the submapper from its high nibble
+    let header = build_header(&[(6, 0x50), (7, 0xA8), (8, 0x31)]);
+    let parsed_header = RomHeader::parse(&build_file(&header, HEADER_SIZE)).context(codeloc!())?;
+    expect("mapper", parsed_header.mapper, 0x1A5)?;
+    expect("submapper", parsed_header.submapper, 3)?;
+
+    let header = build_header(&[(6, 0x50), (7, 0xA0), (8, 0x31)]);
+    let parsed_header = RomHeader::parse(&build_file(&header, HEADER_SIZE)).context(codeloc!())?;
+    expect("iNES mapper ignores byte 8", parsed_header.mapper, 0xA5)?;
+    expect("iNES submapper", parsed_header.submapper, 0)?;
+    return Ok(());
+}
+
+fn test_four_screen_mirroring() -> Result<()>
+{
+    //the four-screen bit overrides the vertical/horizontal bit
+    let header = build_header(&[(6, 0b00001001)]);
+    let parsed_header = RomHeader::parse(&build_file(&header, HEADER_SIZE)).context(codeloc!())?;
+    expect("mirroring", parsed_header.mirroring, Mirroring::FourScreen)?;
+
+    let header = build_header(&[(6, 0b00000000)]);
+    let parsed_header = RomHeader::parse(&build_file(&header, HEADER_SIZE)).context(codeloc!())?;
+    expect("mirroring", parsed_header.mirroring, Mirroring::Horizontal)?;
+    return Ok(());
+}
+
+fn test_archaic_ines() -> Result<()>
+{
+    //"DiskDude!" in bytes 7-15 leaves byte 7 bits 2-3 at 01
+    let mut header = build_header(&[(6, 0x40)]);
+    header[7..16].copy_from_slice(b"DiskDude!");
+    let parsed_header = RomHeader::parse(&build_file(&header, HEADER_SIZE)).context(codeloc!())?;
+    expect("DiskDude! mapper", parsed_header.mapper, 4)?;
+    expect("DiskDude! console type", parsed_header.console_type, ConsoleType::NES)?;
+
+    //byte 7 bits 2-3 = 01 marks an archaic header even when bytes 12-15 are clean
+    let header = build_header(&[(6, 0x10), (7, 0x25)]);
+    let parsed_header = RomHeader::parse(&build_file(&header, HEADER_SIZE)).context(codeloc!())?;
+    expect("archaic mapper", parsed_header.mapper, 1)?;
+    expect("archaic console type", parsed_header.console_type, ConsoleType::NES)?;
+
+    //byte 7 bits 2-3 = 00 with garbage in bytes 12-15
+    let header = build_header(&[(6, 0x10), (7, 0x20), (12, 0x55)]);
+    let parsed_header = RomHeader::parse(&build_file(&header, HEADER_SIZE)).context(codeloc!())?;
+    expect("garbage in bytes 12-15 mapper", parsed_header.mapper, 1)?;
+
+    let header = build_header(&[(6, 0x10), (7, 0x20)]);
+    let parsed_header = RomHeader::parse(&build_file(&header, HEADER_SIZE)).context(codeloc!())?;
+    expect("clean iNES mapper", parsed_header.mapper, 0x21)?;
+    return Ok(());
+}
+
+fn test_console_type() -> Result<()>
+{
+    let header = build_header(&[(7, 0x01)]);
+    let parsed_header = RomHeader::parse(&build_file(&header, HEADER_SIZE)).context(codeloc!())?;
+    expect("iNES Vs. System", parsed_header.console_type, ConsoleType::VsSystem)?;
+
+    //the extended console type byte only exists in NES 2.0 headers
+    let header = build_header(&[(7, 0x03), (13, 0x05)]);
+    let parsed_header = RomHeader::parse(&build_file(&header, HEADER_SIZE)).context(codeloc!())?;
+    expect("iNES console type 3", parsed_header.console_type, ConsoleType::NES)?;
+
+    let header = build_header(&[(7, 0x0B), (13, 0x05)]);
+    let parsed_header = RomHeader::parse(&build_file(&header, HEADER_SIZE)).context(codeloc!())?;
+    expect("NES 2.0 extended console type", parsed_header.console_type, ConsoleType::Extended(5))?;
+    return Ok(());
+}
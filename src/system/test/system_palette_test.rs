@@ -0,0 +1,130 @@
+use std::{env, fs};
+use std::fmt::Debug;
+use std::path::PathBuf;
+use anyhow::{anyhow, Context, Result};
+use crate::codeloc;
+use crate::system::byte;
+use crate::system::debugger::PPUDebugger;
+use crate::system::mappers::{MapperRegistry, SharedMapper};
+use crate::system::ppu::communication::CPUToPPUCommTarget;
+use crate::system::ppu::system_palette::{SystemPalette, NUMBER_OF_BASE_COLORS, NUMBER_OF_EMPHASIS_COMBINATIONS, NUMBER_OF_SYSTEM_COLORS};
+use crate::system::ppu::PPU;
+use crate::system::rom::ROMParser;
+
+const BACKDROP_COLOR_INDEX : byte = 0x16;
+const EMPHASIS_RED_MASK : byte = 0b00100000;
+const EMPHASIS_ATTENUATION : f64 = 0.746;
+
+pub fn test_system_palette() -> Result<()>
+{
+    let tests = vec!
+    [
+        ("512 color .pal file", test_512_color_pal_file as fn() -> Result<()>),
+        ("64 color .pal file", test_64_color_pal_file),
+        ("invalid .pal file", test_invalid_pal_file),
+    ];
+
+    let mut failed_test_names : Vec<&str> = Vec::new();
+    for (test_name, test) in tests
+    {
+        match test()
+        {
+            Ok(()) => { println!("[PALETTE] {}: PASSED!", test_name); }
+            Err(error) =>
+            {
+                println!("[PALETTE] {}: FAILED! {:?}", test_name, error);
+                failed_test_names.push(test_name);
+            }
+        }
+    }
+
+    if !failed_test_names.is_empty()
+    {
+        return Err(anyhow!("[PALETTE] Palette test failed for: {}", failed_test_names.join(", ")));
+    }
+
+    return Ok(());
+}
+
+fn write_pal_file(file_name : &str, rgb_triplets : &[[byte; 3]]) -> Result<PathBuf>
+{
+    let file_path = env::temp_dir().join(file_name);
+    fs::write(&file_path, rgb_triplets.concat()).context(codeloc!())?;
+    return Ok(file_path);
+}
+
+//renders the first pixel of a frame with rendering disabled, which shows the backdrop color through the given emphasis bits
+fn render_backdrop_pixel(system_palette : &SystemPalette, emphasis : byte) -> Result<[byte; 3]>
+{
+    let mut bytes = b"NES\x1A\x01\x01".to_vec();
+    bytes.resize(16 + 16*1024 + 8*1024, 0);
+    let parsed_rom = ROMParser::parse(bytes.into_boxed_slice()).context(codeloc!())?;
+    let mapper = SharedMapper::new(MapperRegistry::build(parsed_rom).context(codeloc!())?);
+    let system_colors = system_palette.load_colors().context(codeloc!())?;
+    let mut ppu = PPU::new(mapper, system_colors, PPUDebugger::new(), false);
+
+    //the bus address is moved off the palette afterwards, otherwise the color it points to would be shown instead of the backdrop
+    for (target, value) in [(CPUToPPUCommTarget::BusAddress, 0x3F), (CPUToPPUCommTarget::BusAddress, 0x00), (CPUToPPUCommTarget::BusData, BACKDROP_COLOR_INDEX),
+        (CPUToPPUCommTarget::BusAddress, 0x00), (CPUToPPUCommTarget::BusAddress, 0x00), (CPUToPPUCommTarget::MaskFlags, emphasis << 5)]
+    {
+        ppu.handle_write_command_from_cpu(target, &[value]);
+    }
+
+    ppu.step();
+    let rgba_bytes = ppu.framebuffer.to_rgba_bytes(&ppu.bus.palette);
+    return Ok([rgba_bytes[0], rgba_bytes[1], rgba_bytes[2]]);
+}
+
+fn expect<T : PartialEq + Debug>(description : &str, actual : T, expected : T) -> Result<()>
+{
+    if actual != expected
+    {
+        return Err(anyhow!("{}: expected {:?}, found {:?}", description, expected, actual));
+    }
+
+    return Ok(());
+}
+
+fn test_512_color_pal_file() -> Result<()>
+{
+    //every entry is unique: red and green hold the entry index
+    let rgb_triplets = (0..NUMBER_OF_SYSTEM_COLORS).map(|index| [(index & 0xFF) as byte, (index >> 8) as byte, 0x42]).collect::<Vec<[byte; 3]>>();
+    let system_palette = SystemPalette::File(write_pal_file("nes_emulator_test_512_colors.pal", &rgb_triplets).context(codeloc!())?);
+
+    //the emphasis bits select one of the 8 blocks of 64 colors: (emphasis << 6) | color index
+    for emphasis in 0..NUMBER_OF_EMPHASIS_COMBINATIONS
+    {
+        let expected_rgb = rgb_triplets[(emphasis << 6) | (BACKDROP_COLOR_INDEX as usize)];
+        let rgb = render_backdrop_pixel(&system_palette, emphasis as byte).context(codeloc!())?;
+        expect(&format!("color with emphasis {:03b}", emphasis), rgb, expected_rgb)?;
+    }
+
+    return Ok(());
+}
+
+fn test_64_color_pal_file() -> Result<()>
+{
+    let rgb_triplets = (0..NUMBER_OF_BASE_COLORS).map(|index| [(index * 4) as byte, 0x80, 0xFF - (index * 4) as byte]).collect::<Vec<[byte; 3]>>();
+    let system_palette = SystemPalette::File(write_pal_file("nes_emulator_test_64_colors.pal", &rgb_triplets).context(codeloc!())?);
+
+    let base_rgb = rgb_triplets[BACKDROP_COLOR_INDEX as usize];
+    let rgb = render_backdrop_pixel(&system_palette, 0).context(codeloc!())?;
+    expect("color without emphasis", rgb, base_rgb)?;
+
+    //the other 448 colors are derived, red emphasis darkens green and blue
+    let attenuate = |channel : byte| ((channel as f64) * EMPHASIS_ATTENUATION) as byte;
+    let rgb = render_backdrop_pixel(&system_palette, EMPHASIS_RED_MASK >> 5).context(codeloc!())?;
+    expect("color with red emphasis", rgb, [base_rgb[0], attenuate(base_rgb[1]), attenuate(base_rgb[2])])?;
+    return Ok(());
+}
+
+fn test_invalid_pal_file() -> Result<()>
+{
+    let file_path = write_pal_file("nes_emulator_test_invalid.pal", &[[0, 0, 0]; 100]).context(codeloc!())?;
+    let error = SystemPalette::File(file_path.clone()).load_colors().err().context(codeloc!())?;
+    let error_message = error.to_string();
+    expect("error names the file", error_message.contains(&file_path.display().to_string()), true)?;
+    expect("error names the 64 color size", error_message.contains("192 bytes"), true)?;
+    expect("error names the 512 color size", error_message.contains("1536 bytes"), true)?;
+    return Ok(());
+}
@@ -0,0 +1,88 @@
+use anyhow::{anyhow, Context, Result};
+use std::sync::atomic::{AtomicBool, Ordering};
+use itertools::Itertools;
+use sdl2::event::Event;
+use sdl2::EventPump;
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::render::{Texture, WindowCanvas};
+use crate::codeloc;
+use crate::system::debugger::PPUDebugger;
+use crate::system::input::InputSubsystem;
+use crate::system::ppu::metrics::{NES_DISPLAY_HEIGHT, NES_DISPLAY_WIDTH, WindowMetrics};
+use crate::system::ppu::pattern_tables::render_pattern_tables;
+use crate::system::ppu::PPU;
+use crate::system::save_state::SaveStateRequest;
+
+//the SDL window: presents the frames completed by the PPU and forwards keyboard and joystick events
+pub struct VideoFrontend<'a>
+{
+    canvas : &'a mut WindowCanvas,
+    framebuffer_texture : Texture<'a>,
+    event_pump : EventPump,
+}
+
+impl <'a> VideoFrontend<'a>
+{
+    //SDL textures borrow their texture creator, so the frontend only lives inside the callback
+    pub fn with_window<R>(window_title : &str, callback : impl FnOnce(&mut VideoFrontend) -> Result<R>) -> Result<R>
+    {
+        let window_metrics = WindowMetrics::new();
+
+        let sdl = sdl2::init().map_err(|msg|anyhow!(msg)).context(codeloc!())?;
+        let video_subsystem = sdl.video().map_err(|msg|anyhow!(msg)).context(codeloc!())?;
+
+        let window = video_subsystem.window(window_title,
+            window_metrics.get_window_width(), window_metrics.get_window_height())
+            .position_centered().resizable().opengl().build().context(codeloc!())?;
+
+        let (opengl_driver_index, _) = sdl2::render::drivers().find_position(|d| d.name=="opengl").unwrap();
+        let mut canvas = window.into_canvas().index(opengl_driver_index as u32).accelerated().build().context(codeloc!())?;
+
+        let texture_creator = canvas.texture_creator();
+        let framebuffer_texture = texture_creator.create_texture_streaming(PixelFormatEnum::RGBA32,
+            NES_DISPLAY_WIDTH as u32, NES_DISPLAY_HEIGHT as u32).context(codeloc!())?;
+        let event_pump = sdl.event_pump().map_err(|e|anyhow!(e.clone())).context(codeloc!())?;
+
+        let mut frontend = VideoFrontend
+        {
+            canvas: &mut canvas,
+            framebuffer_texture: framebuffer_texture,
+            event_pump: event_pump,
+        };
+
+        return callback(&mut frontend);
+    }
+
+    //the whole picture is uploaded as a single texture and stretched over the window
+    pub fn render_frame(&mut self, ppu : &mut PPU) -> Result<()>
+    {
+        let rgba_bytes = if ppu.debugger.should_debug_pattern_table { render_pattern_tables(&ppu.bus).to_rgba_bytes(&ppu.bus.palette) }
+            else { ppu.framebuffer.to_rgba_bytes(&ppu.bus.palette) };
+
+        let pitch = (NES_DISPLAY_WIDTH as usize) * 4;
+        self.framebuffer_texture.update(None, &rgba_bytes, pitch).context(codeloc!())?;
+
+        self.canvas.copy(&self.framebuffer_texture, None, None).map_err(|msg|anyhow!(msg)).context(codeloc!())?;
+        self.canvas.present();
+        return Ok(());
+    }
+
+    pub fn handle_events(&mut self, input_subsystem : &mut InputSubsystem, debugger : &mut PPUDebugger, is_shutting_down : &AtomicBool) -> Option<SaveStateRequest>
+    {
+        input_subsystem.handle_physical_joystick_events();
+
+        let mut save_state_request = None;
+        for event in self.event_pump.poll_iter()
+        {
+            match event
+            {
+                Event::KeyDown { keycode: Some(keycode), .. } => { save_state_request = input_subsystem.handle_physical_keyboard_keydown(keycode, debugger).or(save_state_request); }
+                Event::KeyUp { keycode: Some(keycode), .. } => { input_subsystem.handle_physical_keyboard_keyup(keycode, debugger); }
+                Event::Quit { .. } => { is_shutting_down.store(true, Ordering::Relaxed); }
+                _ => {}
+            }
+        }
+
+        return save_state_request;
+    }
+}